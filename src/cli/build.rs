@@ -1,15 +1,14 @@
 use bytes::Bytes;
 use failure::Error;
 
-use super::error::CLIError;
 use super::opts::{BuildOpt, OutputType};
 use crate::codegen::{compile, initialization, target_machine};
-use crate::parser;
+use crate::module;
 use crate::transform::TransformManager;
 use crate::translator::translate_ast;
 
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::Write;
 
 pub fn build(opt: &BuildOpt) -> Result<(), Error> {
     let BuildOpt {
@@ -20,27 +19,20 @@ pub fn build(opt: &BuildOpt) -> Result<(), Error> {
     } = opt;
 
     initialization::initialize_all()?;
-    let mut f = File::open(&input).map_err(|_| CLIError::NotFound {
-        path: input.clone(),
-    })?;
-    let mut contents = String::new();
-    f.read_to_string(&mut contents)
-        .map_err(|error| CLIError::IOError { error })?;
-    let contents = contents.trim();
+    let ast = module::load(&input)?;
 
     let buffer: Bytes = match output_type {
-        OutputType::AST => format!("{:#?}", parser::parse(contents)?).into(),
+        OutputType::AST => format!("{:#?}", ast).into(),
         OutputType::EIR => {
-            let ast = parser::parse(contents)?;
             let eir = TransformManager::default().apply(translate_ast(ast)?)?;
             format!("{}", eir).into()
         }
         OutputType::IR => {
-            let result = compile::compile_string(contents, &codegen_opt.emit_func_name)?;
+            let result = compile::compile_ast(ast, &codegen_opt.emit_func_name)?;
             result.llvm_ir().into()
         }
         OutputType::Assembly | OutputType::Object => {
-            let result = compile::compile_string(contents, &codegen_opt.emit_func_name)?;
+            let result = compile::compile_ast(ast, &codegen_opt.emit_func_name)?;
 
             let target_machine = target_machine::create_target_machine(
                 codegen_opt.target_triple.as_ref(),
@@ -1,7 +1,10 @@
-use super::error::CLIError;
 use super::opts::RunOpt;
 use super::shell::Shell;
+use crate::codegen::compilation_result::{Args, JitProgram, Outcome};
 use crate::codegen::{compile, initialization};
+use crate::expression::Expression;
+use crate::ir;
+use crate::module;
 use crate::parser;
 use crate::transform::TransformManager;
 use crate::translator::translate_ast;
@@ -9,37 +12,44 @@ use crate::translator::translate_ast;
 use failure::Error;
 
 use ansi_term::Colour::{Blue, Red};
-use inkwell::execution_engine;
 
 use std::env;
-use std::fs::File;
-use std::io::prelude::*;
 use std::process;
+use std::slice;
 
 pub fn run(opt: &RunOpt) -> Result<!, Error> {
     initialization::initialize_native()?;
 
     if let Some(path) = &opt.input {
-        let mut f = File::open(path).map_err(|_| CLIError::NotFound { path: path.clone() })?;
-        let mut contents = String::new();
-        f.read_to_string(&mut contents)
-            .map_err(|error| CLIError::IOError { error })?;
-
-        let func = compile_jit(&contents.trim(), "file_input", opt)?;
-        process::exit(unsafe { func.call() } as i32)
+        let (program, _) = compile_jit(module::load(path)?, "file_input", opt)?;
+        let args = Args::new(path.to_string_lossy().into_owned(), &opt.args)?;
+        match unsafe { program.run(&args) } {
+            Outcome::Value(value) => process::exit(value as i32),
+            Outcome::Exit(status) => process::exit(status),
+        }
     } else {
+        let args = Args::new("repl".to_string(), &opt.args)?;
         let home = dirs::home_dir().unwrap_or_else(|| env::current_dir().unwrap());
         let mut shell = Shell::new(home.join(".expressi_history"));
         loop {
             let line = shell.get_next_line()?;
-            match compile_jit(line.trim(), "repl", opt) {
-                Ok(func) => {
-                    println!(
-                        "{}{}",
-                        Blue.paint("-> "),
-                        Blue.paint(unsafe { func.call() }.to_string())
-                    );
-                }
+            let result = parser::parse(line.trim())
+                .and_then(|ast| module::link(ast, &env::current_dir()?))
+                .and_then(|ast| compile_jit(ast, "repl", opt));
+            match result {
+                // `exit` returns the status instead of terminating the shell
+                Ok((program, ty)) => match unsafe { program.run(&args) } {
+                    Outcome::Value(value) => {
+                        println!(
+                            "{}{}",
+                            Blue.paint("-> "),
+                            Blue.paint(format_result(value, &ty))
+                        );
+                    }
+                    Outcome::Exit(status) => {
+                        eprintln!("{}: exited with status {}", Red.paint("Exit"), status);
+                    }
+                },
                 Err(e) => {
                     eprintln!("{}: {}", Red.paint("Error"), e);
                 }
@@ -48,19 +58,44 @@ pub fn run(opt: &RunOpt) -> Result<!, Error> {
     }
 }
 
-type CompiledFunc = unsafe extern "C" fn() -> u64;
+/// Layout of strings in compiled code
+#[repr(C)]
+struct RawString {
+    data: *const u8,
+    len: i64,
+}
 
+fn format_result(value: u64, ty: &ir::Type) -> String {
+    match ty {
+        ir::Type::String => {
+            let bytes = unsafe {
+                let string = &*(value as *const RawString);
+                slice::from_raw_parts(string.data, string.len as usize)
+            };
+            format!("{:?}", String::from_utf8_lossy(bytes))
+        }
+        ir::Type::Float => format!("{:?}", f64::from_bits(value)),
+        ir::Type::Char => match std::char::from_u32(value as u32) {
+            Some(c) => format!("{:?}", c),
+            None => value.to_string(),
+        },
+        _ if ty.is_signed() => (value as i64).to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// Compile `ast` to a function in the JIT, in which extern functions are resolved to the
+/// symbols loaded in this process such as the ones in libc.
 pub fn compile_jit(
-    source: &str,
+    ast: Expression,
     module_name: &str,
     opt: &RunOpt,
-) -> Result<execution_engine::JitFunction<CompiledFunc>, Error> {
-    let ast = parser::parse(&source)?;
-
+) -> Result<(JitProgram, ir::Type), Error> {
     if opt.print_ast {
         eprintln!("AST:\n{:#?}", ast);
     }
 
+    let (ast, entry) = compile::prepare_entry(ast);
     let eir = translate_ast(ast)?;
 
     if opt.print_eir {
@@ -73,7 +108,7 @@ pub fn compile_jit(
         eprintln!("Transformed EIR:\n{}\n", transformed);
     }
 
-    let result = compile::compile_eir(transformed, module_name)?;
+    let result = compile::compile_eir(transformed, module_name, entry)?;
 
     if opt.print_ir {
         eprintln!("LLVM IR: \n{}", result.llvm_ir());
@@ -81,5 +116,6 @@ pub fn compile_jit(
 
     result.verify()?;
 
-    result.emit_function(opt.optimization_level.into())
+    let program = result.emit_function(opt.optimization_level.into())?;
+    Ok((program, result.result_type().clone()))
 }
@@ -25,6 +25,10 @@ pub struct RunOpt {
         case_insensitive = "true"
     ))]
     pub optimization_level: OptimizationLevelOpt,
+
+    /// Arguments passed to the program, which are given after `--`
+    #[structopt(name = "ARGS", raw(last = "true"))]
+    pub args: Vec<String>,
 }
 
 arg_enum! {
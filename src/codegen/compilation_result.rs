@@ -1,4 +1,5 @@
 use super::error::LLVMError;
+use crate::ir;
 
 use failure::Error;
 
@@ -6,19 +7,59 @@ use inkwell::execution_engine::JitFunction;
 use inkwell::targets::{FileType, TargetMachine};
 use inkwell::{module, OptimizationLevel};
 
+use std::ffi::CString;
+use std::iter;
+use std::os::raw::c_char;
+use std::ptr;
+
+/// Signature of the compiled function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    /// `i64 ()`, which returns the value of the program
+    Plain,
+    /// `i64 (i32, i8**)` like C `main`, which returns the value of `main` applied to the
+    /// command-line arguments
+    Main,
+}
+
+pub type PlainFunction = unsafe extern "C" fn() -> u64;
+pub type MainFunction = unsafe extern "C" fn(i32, *const *const c_char) -> u64;
+type TerminatedFunction = unsafe extern "C" fn() -> i32;
+
+/// Name of the function which tells whether the compiled function named `name` returned an
+/// exit status instead of a value.
+pub fn terminated_function_name(name: &str) -> String {
+    format!("{}.terminated", name)
+}
+
 pub struct CompilationResult {
     module: module::Module,
+    result_type: ir::Type,
+    entry: Entry,
 }
 
 impl CompilationResult {
-    pub fn new(module: module::Module) -> Self {
-        CompilationResult { module }
+    pub fn new(module: module::Module, result_type: ir::Type, entry: Entry) -> Self {
+        CompilationResult {
+            module,
+            result_type,
+            entry,
+        }
     }
 
     pub fn module(&self) -> &module::Module {
         &self.module
     }
 
+    /// Type of the value returned from the compiled function
+    pub fn result_type(&self) -> &ir::Type {
+        &self.result_type
+    }
+
+    pub fn entry(&self) -> Entry {
+        self.entry
+    }
+
     pub fn llvm_ir(&self) -> String {
         self.module.print_to_string().to_string()
     }
@@ -57,16 +98,90 @@ impl CompilationResult {
             .map(|buffer| buffer.as_slice().to_vec())
     }
 
-    pub fn emit_function(
-        &self,
-        opt: OptimizationLevel,
-    ) -> Result<JitFunction<unsafe extern "C" fn() -> u64>, Error> {
+    pub fn emit_function(&self, opt: OptimizationLevel) -> Result<JitProgram, Error> {
         let execution_engine = self
             .module()
             .create_jit_execution_engine(opt)
             .map_err(|_| LLVMError::FailedToCreateJIT)?;
 
-        unsafe { execution_engine.get_function(self.module().get_name().to_str()?) }
-            .map_err(Into::into)
+        let name = self.module().get_name().to_str()?;
+        let entry = match self.entry {
+            Entry::Plain => JitEntry::Plain(unsafe { execution_engine.get_function(name) }?),
+            Entry::Main => JitEntry::Main(unsafe { execution_engine.get_function(name) }?),
+        };
+        let terminated = unsafe { execution_engine.get_function(&terminated_function_name(name)) }?;
+        Ok(JitProgram { entry, terminated })
+    }
+}
+
+enum JitEntry {
+    Plain(JitFunction<PlainFunction>),
+    Main(JitFunction<MainFunction>),
+}
+
+/// How a compiled program finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The program returned the value
+    Value(u64),
+    /// The program was terminated with the status
+    Exit(i32),
+}
+
+/// A program compiled in the JIT
+pub struct JitProgram {
+    entry: JitEntry,
+    terminated: JitFunction<TerminatedFunction>,
+}
+
+impl JitProgram {
+    /// Run the program, passing `args` to `main` if it is defined.
+    ///
+    /// # Safety
+    ///
+    /// The compiled code is called as it is, which is not checked to be memory-safe.
+    pub unsafe fn run(&self, args: &Args) -> Outcome {
+        let value = match &self.entry {
+            JitEntry::Plain(function) => function.call(),
+            JitEntry::Main(function) => function.call(args.argc(), args.argv()),
+        };
+        if self.terminated.call() != 0 {
+            Outcome::Exit(value as i32)
+        } else {
+            Outcome::Value(value)
+        }
+    }
+}
+
+/// Command-line arguments passed to the compiled function, in which the first one is the name
+/// of the program.
+pub struct Args {
+    _strings: Vec<CString>,
+    pointers: Vec<*const c_char>,
+}
+
+impl Args {
+    pub fn new(program: String, args: &[String]) -> Result<Self, Error> {
+        let strings = iter::once(program)
+            .chain(args.iter().cloned())
+            .map(CString::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        let pointers = strings
+            .iter()
+            .map(|s| s.as_ptr())
+            .chain(iter::once(ptr::null()))
+            .collect();
+        Ok(Args {
+            _strings: strings,
+            pointers,
+        })
+    }
+
+    fn argc(&self) -> i32 {
+        self.pointers.len() as i32 - 1
+    }
+
+    fn argv(&self) -> *const *const c_char {
+        self.pointers.as_ptr()
     }
 }
@@ -1,6 +1,8 @@
-use super::compilation_result::CompilationResult;
+use super::compilation_result::{terminated_function_name, CompilationResult, Entry};
+use crate::error::TranslationError;
 use crate::expression::Expression;
 use crate::ir;
+use crate::module;
 use crate::parser;
 use crate::transform::TransformManager;
 use crate::translator::eir_translator::Builder;
@@ -8,37 +10,83 @@ use crate::translator::{translate_ast, translate_eir};
 
 use failure::Error;
 
-use inkwell::context;
+use inkwell::{context, AddressSpace};
 
-pub fn compile_eir(eir: ir::Node, module_name: &str) -> Result<CompilationResult, Error> {
+use std::path::Path;
+
+/// Compile `eir` to a function named `module_name`, which has the signature of C `main` with
+/// `Entry::Main` to obtain the command-line arguments with `args`.
+pub fn compile_eir(
+    eir: ir::Node,
+    module_name: &str,
+    entry: Entry,
+) -> Result<CompilationResult, Error> {
     let context = context::Context::get_global();
     let inst_builder = context.create_builder();
 
     let module = context.create_module(module_name);
 
     let i64_type = context.i64_type();
-    let fn_type = i64_type.fn_type(&[], false);
+    let fn_type = match entry {
+        Entry::Plain => i64_type.fn_type(&[], false),
+        Entry::Main => {
+            let argv_type = context
+                .i8_type()
+                .ptr_type(AddressSpace::Generic)
+                .ptr_type(AddressSpace::Generic);
+            i64_type.fn_type(&[context.i32_type().into(), argv_type.into()], false)
+        }
+    };
 
     let function = module.add_function(module_name, fn_type, None);
     let basic_block = context.append_basic_block(&function, "entry");
 
     inst_builder.position_at_end(&basic_block);
 
+    let result_type = eir.type_().ok_or(TranslationError::NotTyped)?.clone();
     let mut builder = Builder::new(inst_builder, module);
+    if let Entry::Main = entry {
+        builder.set_args(
+            function.get_nth_param(0).unwrap().into_int_value(),
+            function.get_nth_param(1).unwrap().into_pointer_value(),
+        );
+    }
+    let terminate_block = builder.enter_program()?;
 
     let evaluated_value = translate_eir(&mut builder, eir)?.expect_value()?;
-    builder.ret_int(evaluated_value)?;
+    builder.ret_int(evaluated_value, &result_type)?;
+    builder.exit_program(&terminate_block, &terminated_function_name(module_name));
+
+    Ok(CompilationResult::new(
+        builder.take_module(),
+        result_type,
+        entry,
+    ))
+}
 
-    Ok(CompilationResult::new(builder.take_module()))
+/// Apply `main` to the command-line arguments at the end of `ast` if it is defined, returning
+/// the signature of the function to compile it to.
+pub fn prepare_entry(ast: Expression) -> (Expression, Entry) {
+    if module::defines_main(&ast) {
+        (module::call_main(ast), Entry::Main)
+    } else {
+        (ast, Entry::Plain)
+    }
 }
 
 pub fn compile_ast(ast: Expression, module_name: &str) -> Result<CompilationResult, Error> {
+    let (ast, entry) = prepare_entry(ast);
     let eir = translate_ast(ast)?;
 
-    compile_eir(TransformManager::default().apply(eir)?, module_name)
+    compile_eir(TransformManager::default().apply(eir)?, module_name, entry)
 }
 
+/// Compile `source`, in which the modules are imported relative to the current directory.
 pub fn compile_string(source: &str, module_name: &str) -> Result<CompilationResult, Error> {
-    let ast = parser::parse(&source)?;
+    let ast = module::link(parser::parse(&source)?, Path::new("."))?;
     compile_ast(ast, module_name)
 }
+
+pub fn compile_file(path: &Path, module_name: &str) -> Result<CompilationResult, Error> {
+    compile_ast(module::load(path)?, module_name)
+}
@@ -1,3 +1,4 @@
+use super::compilation_result::{Args, Outcome};
 use super::compile;
 use super::initialization;
 use super::target_machine::create_target_machine;
@@ -7,9 +8,13 @@ use inkwell::OptimizationLevel;
 use tempfile;
 
 use std::io::Write;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
-fn link_and_exec(bytes: &[u8], extension: &str) -> i32 {
+fn link_and_exec(bytes: &[u8], extension: &str, args: &[&str]) -> i32 {
+    link_and_exec_with_input(bytes, extension, args, "")
+}
+
+fn link_and_exec_with_input(bytes: &[u8], extension: &str, args: &[&str], input: &str) -> i32 {
     let mut f = tempfile::Builder::new()
         .suffix(extension)
         .tempfile()
@@ -26,7 +31,18 @@ fn link_and_exec(bytes: &[u8], extension: &str) -> i32 {
         .success();
     assert!(success);
 
-    Command::new(exe_path).status().unwrap().code().unwrap()
+    let mut child = Command::new(exe_path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait().unwrap().code().unwrap()
 }
 
 #[test]
@@ -37,7 +53,7 @@ fn emit_assembly() {
     let target =
         create_target_machine(None, None, None, OptimizationLevel::None, RelocMode::PIC).unwrap();
     let asm = result.emit_assembly(&target).unwrap();
-    let code = link_and_exec(asm.as_bytes(), ".s");
+    let code = link_and_exec(asm.as_bytes(), ".s", &[]);
     assert_eq!(code, 42);
 }
 
@@ -49,10 +65,140 @@ fn emit_object() {
     let target =
         create_target_machine(None, None, None, OptimizationLevel::None, RelocMode::PIC).unwrap();
     let obj = result.emit_object(&target).unwrap();
-    let code = link_and_exec(&obj, ".o");
+    let code = link_and_exec(&obj, ".o", &[]);
     assert_eq!(code, 42);
 }
 
+#[test]
+fn emit_with_args() {
+    initialization::initialize_native().unwrap();
+
+    let result = compile::compile_string("let main = a -> a.len", "main").unwrap();
+    let target =
+        create_target_machine(None, None, None, OptimizationLevel::None, RelocMode::PIC).unwrap();
+    let obj = result.emit_object(&target).unwrap();
+    let code = link_and_exec(&obj, ".o", &["x", "y"]);
+    assert_eq!(code, 3);
+}
+
+#[test]
+fn emit_read_number() {
+    initialization::initialize_native().unwrap();
+
+    let source = "match read_number() { Ok(n) => n, Err(_) => 100 }";
+    let result = compile::compile_string(source, "main").unwrap();
+    let target =
+        create_target_machine(None, None, None, OptimizationLevel::None, RelocMode::PIC).unwrap();
+    let obj = result.emit_object(&target).unwrap();
+    assert_eq!(link_and_exec_with_input(&obj, ".o", &[], "42\n"), 42);
+    assert_eq!(link_and_exec_with_input(&obj, ".o", &[], "42"), 42);
+    assert_eq!(link_and_exec_with_input(&obj, ".o", &[], "4x\n"), 100);
+    assert_eq!(link_and_exec_with_input(&obj, ".o", &[], "\n"), 100);
+    assert_eq!(link_and_exec_with_input(&obj, ".o", &[], ""), 100);
+    let too_large = "99999999999999999999\n";
+    assert_eq!(link_and_exec_with_input(&obj, ".o", &[], too_large), 100);
+}
+
+#[test]
+fn emit_exit() {
+    initialization::initialize_native().unwrap();
+
+    let result = compile::compile_string("let f = n -> { exit(n); 0 }; f(7) + 1", "main").unwrap();
+    let target =
+        create_target_machine(None, None, None, OptimizationLevel::None, RelocMode::PIC).unwrap();
+    let obj = result.emit_object(&target).unwrap();
+    let code = link_and_exec(&obj, ".o", &[]);
+    assert_eq!(code, 7);
+}
+
+#[test]
+fn jit_exit() {
+    initialization::initialize_native().unwrap();
+
+    let result = compile::compile_string("let f = n -> { exit(n); 0 }; f(7) + 1", "jit").unwrap();
+    let program = result.emit_function(OptimizationLevel::None).unwrap();
+    let args = Args::new("jit".to_string(), &[]).unwrap();
+    assert_eq!(unsafe { program.run(&args) }, Outcome::Exit(7));
+}
+
+#[test]
+fn emit_uncaught_exception() {
+    initialization::initialize_native().unwrap();
+
+    let result = compile::compile_string("throw \"oops\"; 0", "main").unwrap();
+    let target =
+        create_target_machine(None, None, None, OptimizationLevel::None, RelocMode::PIC).unwrap();
+    let obj = result.emit_object(&target).unwrap();
+    let code = link_and_exec(&obj, ".o", &[]);
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn jit_uncaught_exception() {
+    initialization::initialize_native().unwrap();
+
+    let result = compile::compile_string("throw \"oops\"; 0", "jit").unwrap();
+    let program = result.emit_function(OptimizationLevel::None).unwrap();
+    let args = Args::new("jit".to_string(), &[]).unwrap();
+    assert_eq!(unsafe { program.run(&args) }, Outcome::Exit(1));
+}
+
+#[test]
+fn jit_optimized_assign_in_try() {
+    initialization::initialize_native().unwrap();
+
+    let sources = [
+        ("let mut x = 0; try { x = 1; throw \"e\" } catch e { x }", 1),
+        (
+            "let mut n = 0; for i in 0..3 { try { n = n + i; throw \"e\" } catch e { n = n * 2 } }; n",
+            8,
+        ),
+    ];
+    for (source, expected) in &sources {
+        let result = compile::compile_string(source, "jit").unwrap();
+        let program = result.emit_function(OptimizationLevel::Aggressive).unwrap();
+        let args = Args::new("jit".to_string(), &[]).unwrap();
+        assert_eq!(unsafe { program.run(&args) }, Outcome::Value(*expected));
+    }
+}
+
+#[test]
+fn emit_optimized_assign_in_try() {
+    initialization::initialize_native().unwrap();
+
+    let source = "let mut x = 0; try { x = 1; throw \"e\" } catch e { x }";
+    let result = compile::compile_string(source, "main").unwrap();
+    let target = create_target_machine(
+        None,
+        None,
+        None,
+        OptimizationLevel::Aggressive,
+        RelocMode::PIC,
+    )
+    .unwrap();
+    let obj = result.emit_object(&target).unwrap();
+    let code = link_and_exec(&obj, ".o", &[]);
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn jit_index_out_of_bounds() {
+    initialization::initialize_native().unwrap();
+
+    let sources = [
+        "let a = [1, 2, 3]; a[3]",
+        "let a = [1, 2, 3]; a[-1]",
+        "let mut a = [1, 2, 3]; a[3] = 4; 0",
+        "let s = \"abc\"; s[3] as Number",
+    ];
+    for source in &sources {
+        let result = compile::compile_string(source, "jit").unwrap();
+        let program = result.emit_function(OptimizationLevel::None).unwrap();
+        let args = Args::new("jit".to_string(), &[]).unwrap();
+        assert_eq!(unsafe { program.run(&args) }, Outcome::Exit(1));
+    }
+}
+
 #[test]
 fn emit_llvm() {
     let result = compile::compile_string("2 * 3 * 7", "main").unwrap();
@@ -67,6 +213,6 @@ fn emit_llvm() {
         .unwrap();
     assert!(out.status.success());
 
-    let code = link_and_exec(&out.stdout, ".s");
+    let code = link_and_exec(&out.stdout, ".s", &[]);
     assert_eq!(code, 42);
 }
@@ -1,5 +1,9 @@
+use crate::ir::Type;
+
 use failure::Fail;
 
+use std::path::PathBuf;
+
 #[derive(Debug, Fail)]
 pub enum TranslationError {
     #[fail(display = "Use of undeclared variable")]
@@ -8,9 +12,67 @@ pub enum TranslationError {
     #[fail(display = "Cannot assign twice to immutable variable")]
     ImmutableAssign,
 
+    #[fail(display = "Invalid left-hand side of assignment")]
+    InvalidAssignTarget,
+
     #[fail(display = "Use of undeclared type identifier")]
     UndeclaredType,
 
+    #[fail(
+        display = "Type {} expects {} type arguments, but {} given",
+        name, expected, found
+    )]
+    TypeArgumentMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[fail(display = "Integer literal {} is out of range for {}", value, ty)]
+    IntegerOutOfRange { value: i128, ty: Type },
+
+    #[fail(display = "Placeholder `_` is only allowed in arguments of application")]
+    InvalidPlaceholder,
+
+    #[fail(display = "Type expression cannot be used as a value")]
+    TypeInValuePosition,
+
+    #[fail(display = "Type definition must be bound to a name")]
+    UnboundTypeDefinition,
+
+    #[fail(display = "Constructor {} is already declared by type {}", name, ty)]
+    DuplicateConstructor { name: String, ty: String },
+
+    #[fail(display = "Struct {} cannot contain itself", name)]
+    RecursiveStruct { name: String },
+
+    #[fail(display = "Type alias {} cannot refer to itself", name)]
+    RecursiveTypeAlias { name: String },
+
+    #[fail(display = "Method {} of an instance must be a function", name)]
+    InvalidMethod { name: String },
+
+    #[fail(display = "Extern function {} must have a function type", name)]
+    InvalidExtern { name: String },
+
+    #[fail(
+        display = "Extern function {} is declared with conflicting types",
+        name
+    )]
+    ConflictingExtern { name: String },
+
+    #[fail(display = "Value of type {} cannot be printed", ty)]
+    NotPrintable { ty: Type },
+
+    #[fail(display = "Cannot assign to immutable field {}", field)]
+    ImmutableFieldAssign { field: String },
+
+    #[fail(
+        display = "Only functions can be bound with let rec, but {} is not a function",
+        name
+    )]
+    RecursiveValue { name: String },
+
     #[fail(display = "Value with fixed type is expected")]
     ValueExpected,
 
@@ -70,6 +132,39 @@ pub enum InternalError {
     AlreadyTyped,
 }
 
+#[derive(Debug, Fail)]
+pub enum FixityError {
+    #[fail(display = "Use of undeclared operator {}", name)]
+    UndeclaredOperator { name: String },
+
+    #[fail(
+        display = "Operators {} and {} of the same precedence cannot be chained",
+        lhs, rhs
+    )]
+    AmbiguousOperators { lhs: String, rhs: String },
+}
+
+#[derive(Debug, Fail)]
+pub enum ModuleError {
+    #[fail(display = "Module not found: {:?}", path)]
+    NotFound { path: PathBuf },
+
+    #[fail(display = "Cyclic import of module {:?}", path)]
+    CyclicImport { path: PathBuf },
+
+    #[fail(display = "Module {} does not export {}", module, name)]
+    NotExported { module: String, name: String },
+
+    #[fail(display = "Only bindings can be exported")]
+    InvalidExport,
+
+    #[fail(display = "import and export are only allowed at the top level of a file")]
+    NotTopLevel,
+
+    #[fail(display = "{} is declared in more than one module", name)]
+    DuplicateType { name: String },
+}
+
 #[derive(Fail, Debug)]
 #[fail(display = "Failed to parse: {}", message)]
 pub struct ParseError {
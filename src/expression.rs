@@ -1,4 +1,4 @@
-use crate::ir::BindingKind;
+use crate::ir::{BindingKind, Pattern, Type};
 use std::str::FromStr;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -7,9 +7,15 @@ pub enum Operator {
     Sub,
     Mul,
     Div,
+    Rem,
+    Shl,
+    Shr,
+    UShr,
     BitAnd,
     BitXor,
     BitOr,
+    And,
+    Or,
     Lt,
     Gt,
     Le,
@@ -19,6 +25,33 @@ pub enum Operator {
     Index,
 }
 
+impl Operator {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/",
+            Operator::Rem => "%",
+            Operator::Shl => "<<",
+            Operator::Shr => ">>",
+            Operator::UShr => ">>>",
+            Operator::BitAnd => "&",
+            Operator::BitXor => "^",
+            Operator::BitOr => "|",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Lt => "<",
+            Operator::Gt => ">",
+            Operator::Le => "<=",
+            Operator::Ge => ">=",
+            Operator::Eq => "==",
+            Operator::Ne => "!=",
+            Operator::Index => "[]",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct OperatorParseError;
 
@@ -31,9 +64,15 @@ impl FromStr for Operator {
             "-" => Operator::Sub,
             "*" => Operator::Mul,
             "/" => Operator::Div,
+            "%" => Operator::Rem,
+            "<<" => Operator::Shl,
+            ">>" => Operator::Shr,
+            ">>>" => Operator::UShr,
             "&" => Operator::BitAnd,
             "^" => Operator::BitXor,
             "|" => Operator::BitOr,
+            "&&" => Operator::And,
+            "||" => Operator::Or,
             "<" => Operator::Lt,
             ">" => Operator::Gt,
             "<=" => Operator::Le,
@@ -45,24 +84,173 @@ impl FromStr for Operator {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+/// Prefix operator such as `-` in `-x`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum UnaryOperator {
+    Neg,
+    Not,
+    BitNot,
+}
+
+impl UnaryOperator {
+    /// Negation of a number literal is folded into a negative literal.
+    pub fn apply_to(self, expr: Expression) -> Expression {
+        match (self, expr) {
+            (UnaryOperator::Neg, Expression::Number(n)) => Expression::Number(-n),
+            (UnaryOperator::Neg, Expression::Float(n)) => Expression::Float(-n),
+            (UnaryOperator::Neg, Expression::SizedNumber(n, ty)) => Expression::SizedNumber(-n, ty),
+            (op, expr) => Expression::UnaryOp(op, box expr),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
+/// How tightly an infix operator binds, as declared by `infixl 6 <+> = ...`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Fixity {
+    pub associativity: Associativity,
+    pub precedence: u8,
+}
+
+/// Whether the end of a range such as `a..b` or `a..=b` is included in it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RangeKind {
+    Exclusive,
+    Inclusive,
+}
+
+#[derive(Clone, PartialEq, Debug)]
 pub enum Expression {
     Number(i64),
+    /// Integer literal with a type suffix such as `255u8`, which is checked to fit in the type later
+    SizedNumber(i128, Type),
+    Float(f64),
     Boolean(bool),
+    String(String),
+    Char(char),
     Array(Vec<Expression>),
+    Tuple(Vec<Expression>),
     Function(String, Box<Expression>),
     Identifier(String),
     TypeIdentifier(String),
+    TypeApply(String, Vec<Expression>),
+    FunctionType(Box<Expression>, Box<Expression>),
+    TupleType(Vec<Expression>),
+    /// Type with class constraints on its variables such as `Eq a => a -> a -> Boolean`.
+    ConstrainedType(Vec<(String, String)>, Box<Expression>),
     Empty,
     Assign(Box<Expression>, Box<Expression>),
     Bind(BindingKind, String, Box<Expression>),
+    BindTuple(BindingKind, Vec<String>, Box<Expression>),
+    BindRec(Vec<(String, Expression)>),
     Follow(Box<Expression>, Box<Expression>),
     BinOp(Operator, Box<Expression>, Box<Expression>),
+    UnaryOp(UnaryOperator, Box<Expression>),
+    /// Operands and operators such as `a + b <+> c`, whose structure is not resolved yet
+    Infix(Box<Expression>, Vec<(String, Expression)>),
+    BindOperator(Fixity, String, Box<Expression>),
+    /// Operator as a function such as `(+)`, `(+ 1)` or `(1 -)`, with the given operands.
+    Section(String, Option<Box<Expression>>, Option<Box<Expression>>),
+    /// Application to arguments some of which are placeholders `_`, such as `f(_, 2)`.
+    PartialApply(Box<Expression>, Vec<Expression>),
+    Placeholder,
+    TupleIndex(Box<Expression>, usize),
+    Field(Box<Expression>, String),
+    Struct(String, Vec<(String, Expression)>),
+    StructUpdate(Box<Expression>, Vec<(String, Expression)>),
     Apply(Box<Expression>, Box<Expression>),
     IfElse(Box<Expression>, Box<Expression>, Box<Expression>),
+    Match(
+        Box<Expression>,
+        Vec<(Pattern, Option<Expression>, Expression)>,
+    ),
+    While(Box<Expression>, Box<Expression>),
+    Break(Option<Box<Expression>>),
+    Continue,
+    Throw(Box<Expression>),
+    /// `try { ... } catch x { ... }`, in which `x` is bound to the thrown value in the handler.
+    Try(Box<Expression>, String, Box<Expression>),
+    Range(Box<Expression>, Box<Expression>, RangeKind),
+    For(String, Box<Expression>, Box<Expression>),
     Cast(Box<Expression>, Box<Expression>),
+    /// Expression with a type annotation such as `(e : T)`.
+    Ascribe(Box<Expression>, Box<Expression>),
     Scope(Box<Expression>),
     Type(Vec<(Expression, Vec<Expression>)>),
+    StructType(Vec<(BindingKind, String, Expression)>),
+    /// Declaration of another name for a type such as `type Pair(a) = (a, a)`.
+    TypeAlias(String, Vec<String>, Box<Expression>),
+    /// Declaration of a type class such as `class Eq a { (==): a -> a -> Boolean }`.
+    Class(String, String, Vec<(String, Expression)>),
+    /// Declaration of an instance such as `instance Eq Point { (==) = (p, q) -> ... }`.
+    Instance(String, Box<Expression>, Vec<(String, Expression)>),
+    /// C function declared with `extern putchar: Number -> Number`, which is bound to its name.
+    Extern(String, Box<Expression>),
+    /// Declaration of the type of the thrown values such as `exception Error`.
+    Exception(Box<Expression>),
+    /// Import of the module in a file such as `import "list.epi" as list`.
+    Import(String, String),
+    /// Binding which is accessible from the modules importing this one.
+    Export(Box<Expression>),
+}
+
+impl Expression {
+    /// Apply `self` to `args` one by one, unless some of them are placeholders.
+    pub fn apply_args(self, args: Vec<Expression>) -> Expression {
+        if args.contains(&Expression::Placeholder) {
+            Expression::PartialApply(box self, args)
+        } else {
+            args.into_iter()
+                .fold(self, |e, arg| Expression::Apply(box e, box arg))
+        }
+    }
+
+    /// Function of `param`, whose argument is checked to have type `ty` if it is annotated.
+    pub fn function(param: String, ty: Option<Expression>, body: Expression) -> Expression {
+        let body = match ty {
+            Some(ty) => {
+                let check = Expression::Ascribe(box Expression::Identifier(param.clone()), box ty);
+                Expression::Follow(
+                    box Expression::Bind(BindingKind::Immutable, param.clone(), box check),
+                    box body,
+                )
+            }
+            None => body,
+        };
+        Expression::Function(param, box body)
+    }
+
+    /// Annotate `self` with the type `ty` if it is given.
+    pub fn ascribe(self, ty: Option<Expression>) -> Expression {
+        match ty {
+            Some(ty) => Expression::Ascribe(box self, box ty),
+            None => self,
+        }
+    }
+}
+
+/// Postfix part of an expression such as `[0]` in `a[0]`, `.0` in `t.0` or `.x` in `p.x`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Postfix {
+    Index(Expression),
+    TupleIndex(usize),
+    Field(String),
+}
+
+impl Postfix {
+    pub fn apply_to(self, expr: Expression) -> Expression {
+        match self {
+            Postfix::Index(index) => Expression::BinOp(Operator::Index, box expr, box index),
+            Postfix::TupleIndex(index) => Expression::TupleIndex(box expr, index),
+            Postfix::Field(name) => Expression::Field(box expr, name),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,249 @@
+use crate::error::FixityError;
+use crate::expression::{Associativity, Expression, Fixity, Operator};
+use crate::ir::BindingKind;
+
+use failure::Error;
+
+use std::collections::HashMap;
+use std::iter::Peekable;
+
+/// Symbols which are a part of the syntax and cannot be used as operators
+const RESERVED_OPERATORS: [&str; 3] = ["=", "=>", "->"];
+
+pub fn is_reserved(op: &str) -> bool {
+    RESERVED_OPERATORS.contains(&op)
+}
+
+/// Builtin operators are all left-associative.
+fn builtin_fixity(op: Operator) -> Fixity {
+    let precedence = match op {
+        Operator::Or => 1,
+        Operator::And => 2,
+        Operator::BitOr | Operator::BitXor | Operator::BitAnd => 3,
+        Operator::Lt | Operator::Gt | Operator::Le | Operator::Ge | Operator::Eq | Operator::Ne => {
+            4
+        }
+        Operator::Shl | Operator::Shr | Operator::UShr => 5,
+        Operator::Add | Operator::Sub => 6,
+        Operator::Mul | Operator::Div | Operator::Rem | Operator::Index => 7,
+    };
+    Fixity {
+        associativity: Associativity::Left,
+        precedence,
+    }
+}
+
+/// Apply an infix operator, which is either a builtin one or a function bound to its name.
+fn apply_operator(op: String, lhs: Expression, rhs: Expression) -> Expression {
+    match op.parse() {
+        Ok(op) => Expression::BinOp(op, box lhs, box rhs),
+        Err(_) => Expression::Apply(
+            box Expression::Apply(box Expression::Identifier(op), box lhs),
+            box rhs,
+        ),
+    }
+}
+
+/// Fixities of the user-defined operators in scope.
+#[derive(Default, Clone)]
+struct Fixities(HashMap<String, Fixity>);
+
+type Operations = Peekable<std::vec::IntoIter<(String, Expression)>>;
+
+impl Fixities {
+    fn get(&self, op: &str) -> Result<Fixity, Error> {
+        match op.parse() {
+            Ok(op) => Ok(builtin_fixity(op)),
+            Err(_) => self.0.get(op).cloned().ok_or_else(|| {
+                FixityError::UndeclaredOperator {
+                    name: op.to_string(),
+                }
+                .into()
+            }),
+        }
+    }
+
+    fn peek_fixity(&self, rest: &mut Operations) -> Result<Option<(String, Fixity)>, Error> {
+        match rest.peek() {
+            Some((op, _)) => Ok(Some((op.clone(), self.get(op)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Precedence climbing over the operations following `lhs`.
+    fn climb(
+        &self,
+        mut lhs: Expression,
+        rest: &mut Operations,
+        min_precedence: u8,
+    ) -> Result<Expression, Error> {
+        while let Some((_, fixity)) = self.peek_fixity(rest)? {
+            if fixity.precedence < min_precedence {
+                break;
+            }
+            let (op, rhs) = rest.next().unwrap();
+            let mut rhs = self.resolve(rhs)?;
+
+            while let Some((next_op, next)) = self.peek_fixity(rest)? {
+                if next.precedence > fixity.precedence {
+                    rhs = self.climb(rhs, rest, next.precedence)?;
+                } else if next.precedence < fixity.precedence {
+                    break;
+                } else {
+                    match (fixity.associativity, next.associativity) {
+                        (Associativity::Left, Associativity::Left) => break,
+                        (Associativity::Right, Associativity::Right) => {
+                            rhs = self.climb(rhs, rest, next.precedence)?;
+                        }
+                        _ => {
+                            return Err(FixityError::AmbiguousOperators {
+                                lhs: op,
+                                rhs: next_op,
+                            }
+                            .into());
+                        }
+                    }
+                }
+            }
+            lhs = apply_operator(op, lhs, rhs);
+        }
+        Ok(lhs)
+    }
+
+    fn resolve_box(&self, expr: Box<Expression>) -> Result<Box<Expression>, Error> {
+        Ok(box self.resolve(*expr)?)
+    }
+
+    fn resolve_option(
+        &self,
+        expr: Option<Box<Expression>>,
+    ) -> Result<Option<Box<Expression>>, Error> {
+        match expr {
+            Some(expr) => Ok(Some(self.resolve_box(expr)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn resolve_all(&self, exprs: Vec<Expression>) -> Result<Vec<Expression>, Error> {
+        exprs.into_iter().map(|e| self.resolve(e)).collect()
+    }
+
+    fn resolve_fields(
+        &self,
+        fields: Vec<(String, Expression)>,
+    ) -> Result<Vec<(String, Expression)>, Error> {
+        fields
+            .into_iter()
+            .map(|(name, e)| Ok((name, self.resolve(e)?)))
+            .collect()
+    }
+
+    fn resolve(&self, expr: Expression) -> Result<Expression, Error> {
+        Ok(match expr {
+            Expression::Infix(box first, rest) => {
+                let first = self.resolve(first)?;
+                self.climb(first, &mut rest.into_iter().peekable(), 0)?
+            }
+            // A declaration takes effect in the expressions following it
+            Expression::Follow(box Expression::BindOperator(fixity, name, box value), box rest) => {
+                let value = self.resolve(value)?;
+                let mut scope = self.clone();
+                scope.0.insert(name.clone(), fixity);
+                Expression::Follow(
+                    box Expression::Bind(BindingKind::Immutable, name, box value),
+                    box scope.resolve(rest)?,
+                )
+            }
+            Expression::BindOperator(_, name, value) => {
+                Expression::Bind(BindingKind::Immutable, name, self.resolve_box(value)?)
+            }
+            Expression::Array(elems) => Expression::Array(self.resolve_all(elems)?),
+            Expression::Tuple(elems) => Expression::Tuple(self.resolve_all(elems)?),
+            Expression::Function(param, body) => {
+                Expression::Function(param, self.resolve_box(body)?)
+            }
+            Expression::Assign(lhs, rhs) => {
+                Expression::Assign(self.resolve_box(lhs)?, self.resolve_box(rhs)?)
+            }
+            Expression::Bind(kind, name, value) => {
+                Expression::Bind(kind, name, self.resolve_box(value)?)
+            }
+            Expression::BindTuple(kind, names, value) => {
+                Expression::BindTuple(kind, names, self.resolve_box(value)?)
+            }
+            Expression::BindRec(bindings) => Expression::BindRec(self.resolve_fields(bindings)?),
+            Expression::Follow(lhs, rhs) => {
+                Expression::Follow(self.resolve_box(lhs)?, self.resolve_box(rhs)?)
+            }
+            Expression::BinOp(op, lhs, rhs) => {
+                Expression::BinOp(op, self.resolve_box(lhs)?, self.resolve_box(rhs)?)
+            }
+            Expression::UnaryOp(op, value) => Expression::UnaryOp(op, self.resolve_box(value)?),
+            Expression::Section(op, lhs, rhs) => {
+                // Fail early if the operator is not declared
+                self.get(&op)?;
+                Expression::Section(op, self.resolve_option(lhs)?, self.resolve_option(rhs)?)
+            }
+            Expression::PartialApply(func, args) => {
+                Expression::PartialApply(self.resolve_box(func)?, self.resolve_all(args)?)
+            }
+            Expression::TupleIndex(tuple, index) => {
+                Expression::TupleIndex(self.resolve_box(tuple)?, index)
+            }
+            Expression::Field(value, name) => Expression::Field(self.resolve_box(value)?, name),
+            Expression::Struct(name, fields) => {
+                Expression::Struct(name, self.resolve_fields(fields)?)
+            }
+            Expression::StructUpdate(value, fields) => {
+                Expression::StructUpdate(self.resolve_box(value)?, self.resolve_fields(fields)?)
+            }
+            Expression::Apply(lhs, rhs) => {
+                Expression::Apply(self.resolve_box(lhs)?, self.resolve_box(rhs)?)
+            }
+            Expression::IfElse(cond, then_, else_) => Expression::IfElse(
+                self.resolve_box(cond)?,
+                self.resolve_box(then_)?,
+                self.resolve_box(else_)?,
+            ),
+            Expression::Match(value, arms) => Expression::Match(
+                self.resolve_box(value)?,
+                arms.into_iter()
+                    .map(|(pattern, guard, body)| {
+                        let guard = match guard {
+                            Some(guard) => Some(self.resolve(guard)?),
+                            None => None,
+                        };
+                        Ok((pattern, guard, self.resolve(body)?))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            ),
+            Expression::While(cond, body) => {
+                Expression::While(self.resolve_box(cond)?, self.resolve_box(body)?)
+            }
+            Expression::Break(value) => Expression::Break(self.resolve_option(value)?),
+            Expression::Throw(value) => Expression::Throw(self.resolve_box(value)?),
+            Expression::Try(body, name, handler) => {
+                Expression::Try(self.resolve_box(body)?, name, self.resolve_box(handler)?)
+            }
+            Expression::Range(start, end, kind) => {
+                Expression::Range(self.resolve_box(start)?, self.resolve_box(end)?, kind)
+            }
+            Expression::For(name, range, body) => {
+                Expression::For(name, self.resolve_box(range)?, self.resolve_box(body)?)
+            }
+            Expression::Cast(value, ty) => Expression::Cast(self.resolve_box(value)?, ty),
+            Expression::Ascribe(value, ty) => Expression::Ascribe(self.resolve_box(value)?, ty),
+            Expression::Instance(class, ty, methods) => {
+                Expression::Instance(class, ty, self.resolve_fields(methods)?)
+            }
+            Expression::Scope(body) => Expression::Scope(self.resolve_box(body)?),
+            Expression::Export(item) => Expression::Export(self.resolve_box(item)?),
+            expr => expr,
+        })
+    }
+}
+
+/// Resolve the structure of infix operations according to the fixities of the operators.
+pub fn resolve(expr: Expression) -> Result<Expression, Error> {
+    Fixities::default().resolve(expr)
+}
@@ -0,0 +1,170 @@
+use super::{Constructor, DataType, Type, TypeExpr};
+
+use std::fmt;
+
+/// Name of the data type `<Ok(a) | Err(String)>` returned from the builtins which may fail.
+pub const RESULT_TYPE: &str = "Result";
+
+fn result(ty: Type) -> Type {
+    Type::Data(RESULT_TYPE.to_string(), vec![ty])
+}
+
+/// Functions provided by the language itself, which are bound with their names.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Builtin {
+    Sqrt,
+    Pow,
+    Floor,
+    Ceil,
+    Exp,
+    Log,
+    Sin,
+    Cos,
+    Print,
+    Println,
+    ReadLine,
+    ReadNumber,
+    OpenFile,
+    CloseFile,
+    ReadFrom,
+    WriteTo,
+    ReadFile,
+    WriteFile,
+    Args,
+    GetEnv,
+    Exit,
+}
+
+impl Builtin {
+    pub fn all() -> &'static [Builtin] {
+        &[
+            Builtin::Sqrt,
+            Builtin::Pow,
+            Builtin::Floor,
+            Builtin::Ceil,
+            Builtin::Exp,
+            Builtin::Log,
+            Builtin::Sin,
+            Builtin::Cos,
+            Builtin::Print,
+            Builtin::Println,
+            Builtin::ReadLine,
+            Builtin::ReadNumber,
+            Builtin::OpenFile,
+            Builtin::CloseFile,
+            Builtin::ReadFrom,
+            Builtin::WriteTo,
+            Builtin::ReadFile,
+            Builtin::WriteFile,
+            Builtin::Args,
+            Builtin::GetEnv,
+            Builtin::Exit,
+        ]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Sqrt => "sqrt",
+            Builtin::Pow => "pow",
+            Builtin::Floor => "floor",
+            Builtin::Ceil => "ceil",
+            Builtin::Exp => "exp",
+            Builtin::Log => "log",
+            Builtin::Sin => "sin",
+            Builtin::Cos => "cos",
+            Builtin::Print => "print",
+            Builtin::Println => "println",
+            Builtin::ReadLine => "read_line",
+            Builtin::ReadNumber => "read_number",
+            Builtin::OpenFile => "open_file",
+            Builtin::CloseFile => "close_file",
+            Builtin::ReadFrom => "read_from",
+            Builtin::WriteTo => "write_to",
+            Builtin::ReadFile => "read_file",
+            Builtin::WriteFile => "write_file",
+            Builtin::Args => "args",
+            Builtin::GetEnv => "get_env",
+            Builtin::Exit => "exit",
+        }
+    }
+
+    /// Types of the parameters and the result, in which `None` is a parameter that takes a
+    /// value of any type, which is checked once it is known.
+    pub fn signature(self) -> (Vec<Option<Type>>, Type) {
+        match self {
+            Builtin::Pow => (vec![Some(Type::Float), Some(Type::Float)], Type::Float),
+            Builtin::Print | Builtin::Println => (vec![None], Type::Empty),
+            Builtin::ReadLine => (vec![Some(Type::Empty)], Type::String),
+            Builtin::ReadNumber => (vec![Some(Type::Empty)], result(Type::Number)),
+            Builtin::OpenFile => (
+                vec![Some(Type::String), Some(Type::String)],
+                result(Type::File),
+            ),
+            Builtin::CloseFile => (vec![Some(Type::File)], result(Type::Empty)),
+            Builtin::ReadFrom => (
+                vec![Some(Type::File), Some(Type::Number)],
+                result(Type::String),
+            ),
+            Builtin::WriteTo => (
+                vec![Some(Type::File), Some(Type::String)],
+                result(Type::Empty),
+            ),
+            Builtin::ReadFile => (vec![Some(Type::String)], result(Type::String)),
+            Builtin::WriteFile => (
+                vec![Some(Type::String), Some(Type::String)],
+                result(Type::Empty),
+            ),
+            Builtin::Args => (vec![Some(Type::Empty)], Type::Array(box Type::String)),
+            Builtin::GetEnv => (vec![Some(Type::String)], result(Type::String)),
+            Builtin::Exit => (vec![Some(Type::Number)], Type::Empty),
+            Builtin::Sqrt
+            | Builtin::Floor
+            | Builtin::Ceil
+            | Builtin::Exp
+            | Builtin::Log
+            | Builtin::Sin
+            | Builtin::Cos => (vec![Some(Type::Float)], Type::Float),
+        }
+    }
+
+    /// Whether the builtin returns `Result`, which is declared when it is used.
+    pub fn is_fallible(self) -> bool {
+        match self.signature().1 {
+            Type::Data(name, _) => name == RESULT_TYPE,
+            _ => false,
+        }
+    }
+
+    /// Declaration of `Result`, in which an error is described with a message.
+    pub fn result_type() -> DataType {
+        let param = "a".to_string();
+        DataType {
+            name: RESULT_TYPE.to_string(),
+            params: vec![param.clone()],
+            constructors: vec![
+                Constructor {
+                    name: "Ok".to_string(),
+                    fields: vec![TypeExpr::Variable(param)],
+                },
+                Constructor {
+                    name: "Err".to_string(),
+                    fields: vec![TypeExpr::Named("String".to_string(), Vec::new())],
+                },
+            ],
+        }
+    }
+
+    /// Whether a value of `ty` can be written to stdout with `print` and `println`.
+    pub fn is_printable(ty: &Type) -> bool {
+        match ty {
+            Type::Number | Type::Int(_) | Type::Float | Type::Boolean | Type::String => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,55 @@
+use super::{Identifier, Node, TypeExpr};
+
+use std::fmt;
+
+/// A type class, declared with `class Name a { method: T }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Class {
+    pub name: Identifier,
+    pub param: Identifier,
+    pub methods: Vec<(Identifier, TypeExpr)>,
+}
+
+impl Class {
+    pub fn method(&self, name: &str) -> Option<&TypeExpr> {
+        self.methods
+            .iter()
+            .find(|(method, _)| method == name)
+            .map(|(_, ty)| ty)
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "class {} {} {{ ", self.name, self.param)?;
+        for (i, (name, ty)) in self.methods.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", name, ty)?;
+        }
+        write!(f, " }}")
+    }
+}
+
+/// Implementation of the methods of a class for a type, declared with
+/// `instance Name T { method = ... }`.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub class: Identifier,
+    pub ty: TypeExpr,
+    pub methods: Vec<(Identifier, Node)>,
+}
+
+impl fmt::Display for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "instance {} {} {{ ", self.class, self.ty)?;
+        for (i, (name, value)) in self.methods.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} = {}", name, value)?;
+        }
+        write!(f, " }}")
+    }
+}
@@ -1,4 +1,5 @@
 use super::{Literal, Node, Value};
+use crate::expression::RangeKind;
 
 use std::fmt;
 
@@ -7,7 +8,14 @@ impl fmt::Display for Value {
         match self {
             Value::Literal(c) => match c {
                 Literal::Number(number) => write!(f, "{}", number),
+                Literal::Int(bits, ty) if ty.is_signed() => {
+                    write!(f, "{}{}", bits, ty.name().to_lowercase())
+                }
+                Literal::Int(bits, ty) => write!(f, "{}{}", *bits as u64, ty.name().to_lowercase()),
+                Literal::Float(number) => write!(f, "{:?}", number),
                 Literal::Boolean(tf) => write!(f, "{}", tf),
+                Literal::String(s) => write!(f, "{:?}", s),
+                Literal::Char(c) => write!(f, "{:?}", c),
                 Literal::Function(param, body, captures) => {
                     write!(f, "{}", param)?;
                     if !captures.is_empty() {
@@ -24,6 +32,22 @@ impl fmt::Display for Value {
                 rhs.fmt(f)
             }
 
+            Value::UnaryOp(op, value) => {
+                write!(f, "{:?} ", op)?;
+                value.fmt(f)
+            }
+
+            Value::Cast(value, ty) => {
+                value.fmt(f)?;
+                write!(f, " as {}", ty)
+            }
+
+            Value::Ascribe(value, ty) => {
+                write!(f, "(")?;
+                value.fmt(f)?;
+                write!(f, " : {})", ty)
+            }
+
             Value::Follow(lhs, rhs) => {
                 lhs.fmt(f)?;
                 writeln!(f, ";")?;
@@ -36,6 +60,23 @@ impl fmt::Display for Value {
                 write!(f, " in {}", body)
             }
 
+            Value::LetTuple(kind, names, value, body) => {
+                write!(f, "let {} ({}) = ", kind, names.join(", "))?;
+                value.fmt(f)?;
+                write!(f, " in {}", body)
+            }
+
+            Value::LetRec(bindings, body) => {
+                write!(f, "let rec ")?;
+                for (i, (name, value)) in bindings.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " and ")?;
+                    }
+                    write!(f, "{} = {}", name, value)?;
+                }
+                write!(f, " in {}", body)
+            }
+
             Value::Assign(lhs, rhs) => {
                 lhs.fmt(f)?;
                 write!(f, " = ")?;
@@ -50,12 +91,135 @@ impl fmt::Display for Value {
                 write!(f, " : ")?;
                 else_expr.fmt(f)
             }
+            Value::Match(value, arms) => {
+                write!(f, "match {} {{ ", value)?;
+                for (i, arm) in arms.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    arm.fmt(f)?;
+                }
+                write!(f, " }}")
+            }
+            Value::While(cond, body) => write!(f, "while {} {{ {} }}", cond, body),
+            Value::Break(Some(value)) => write!(f, "break {}", value),
+            Value::Break(None) => write!(f, "break"),
+            Value::Continue => write!(f, "continue"),
+            Value::Throw(value) => write!(f, "throw {}", value),
+            Value::Try(body, name, handler) => {
+                write!(f, "try {{ {} }} catch {} {{ {} }}", body, name, handler)
+            }
             Value::Apply(func, arg) => {
                 func.fmt(f)?;
                 write!(f, "(")?;
                 arg.fmt(f)?;
                 write!(f, ")")
             }
+            Value::Array(elems) => {
+                write!(f, "[")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    elem.fmt(f)?;
+                }
+                write!(f, "]")
+            }
+            Value::Tuple(elems) => {
+                write!(f, "(")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    elem.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+            Value::TupleIndex(tuple, index) => {
+                tuple.fmt(f)?;
+                write!(f, ".{}", index)
+            }
+            Value::Range(start, end, RangeKind::Exclusive) => write!(f, "{}..{}", start, end),
+            Value::Range(start, end, RangeKind::Inclusive) => write!(f, "{}..={}", start, end),
+            Value::DefineType(data_type, body) => {
+                write!(f, "type {} = {} in ", data_type.name, data_type)?;
+                body.fmt(f)
+            }
+            Value::DefineStruct(struct_type, body) => {
+                write!(f, "type {} = {} in ", struct_type.name, struct_type)?;
+                body.fmt(f)
+            }
+            Value::DefineAlias(alias, body) => {
+                write!(f, "type {} = {} in ", alias.name, alias)?;
+                body.fmt(f)
+            }
+            Value::DefineClass(class, body) => {
+                write!(f, "{} in ", class)?;
+                body.fmt(f)
+            }
+            Value::DefineInstance(instance, body) => {
+                write!(f, "{} in ", instance)?;
+                body.fmt(f)
+            }
+            Value::DefineException(ty, body) => {
+                write!(f, "exception {} in ", ty)?;
+                body.fmt(f)
+            }
+            Value::Method(name, _) => write!(f, "{}", name),
+            Value::Struct(name, fields) => {
+                write!(f, "{} {{ ", name)?;
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field, value)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Field(value, field) => {
+                value.fmt(f)?;
+                write!(f, ".{}", field)
+            }
+            Value::StructUpdate(value, fields) => {
+                write!(f, "{{ {} with ", value)?;
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} = {}", field, value)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Construct(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    arg.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+            Value::CallBuiltin(builtin, args) => {
+                write!(f, "builtin {}(", builtin)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    arg.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+            Value::CallExtern(name, ty, args) => {
+                write!(f, "extern ({}: {})(", name, ty)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    arg.fmt(f)?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
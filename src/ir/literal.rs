@@ -1,11 +1,16 @@
-use super::{Identifier, Node, Type};
+use super::{Identifier, IntType, Node, Type};
 
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub enum Literal {
     Number(i64),
+    /// Integer of `IntType`, holding its bit pattern
+    Int(i64, IntType),
+    Float(f64),
     Boolean(bool),
+    String(String),
+    Char(char),
     Function(Identifier, Box<Node>, HashMap<Identifier, Type>),
     Empty,
 }
@@ -1,14 +1,24 @@
 pub mod binding_kind;
+pub mod builtin;
+pub mod class;
 pub mod display;
 pub mod identifier;
 pub mod literal;
 pub mod node;
+pub mod pattern;
 pub mod type_;
+pub mod type_def;
+pub mod type_expr;
 pub mod value;
 
 pub use self::binding_kind::BindingKind;
+pub use self::builtin::Builtin;
+pub use self::class::{Class, Instance};
 pub use self::identifier::Identifier;
 pub use self::literal::Literal;
 pub use self::node::Node;
-pub use self::type_::Type;
+pub use self::pattern::{MatchArm, Pattern};
+pub use self::type_::{IntType, Type};
+pub use self::type_def::{Constructor, DataType, StructField, StructType, TypeAlias, TypeDefs};
+pub use self::type_expr::TypeExpr;
 pub use self::value::Value;
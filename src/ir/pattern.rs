@@ -0,0 +1,70 @@
+use super::{Identifier, Node};
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    Wildcard,
+    Variable(Identifier),
+    Number(i64),
+    Boolean(bool),
+    Tuple(Vec<Pattern>),
+    Constructor(Identifier, Vec<Pattern>),
+}
+
+impl Pattern {
+    /// Variables bound by this pattern, in order of appearance.
+    pub fn bound_vars(&self) -> Vec<Identifier> {
+        match self {
+            Pattern::Variable(name) => vec![name.clone()],
+            Pattern::Tuple(elems) | Pattern::Constructor(_, elems) => {
+                elems.iter().flat_map(Pattern::bound_vars).collect()
+            }
+            Pattern::Wildcard | Pattern::Number(_) | Pattern::Boolean(_) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Node>,
+    pub body: Node,
+}
+
+fn fmt_list(f: &mut fmt::Formatter, elems: &[Pattern]) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, elem) in elems.iter().enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", elem)?;
+    }
+    write!(f, ")")
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Variable(name) => write!(f, "{}", name),
+            Pattern::Number(number) => write!(f, "{}", number),
+            Pattern::Boolean(tf) => write!(f, "{}", tf),
+            Pattern::Tuple(elems) => fmt_list(f, elems),
+            Pattern::Constructor(name, args) => {
+                write!(f, "{}", name)?;
+                fmt_list(f, args)
+            }
+        }
+    }
+}
+
+impl fmt::Display for MatchArm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.pattern.fmt(f)?;
+        if let Some(guard) = &self.guard {
+            write!(f, " if {}", guard)?;
+        }
+        write!(f, " => {}", self.body)
+    }
+}
@@ -1,3 +1,5 @@
+use super::Identifier;
+
 use std::fmt;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -15,20 +17,150 @@ impl fmt::Display for TypeVarID {
     }
 }
 
+/// Integers of explicit width and signedness; `Number` is the signed 64-bit one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IntType {
+    I8,
+    I16,
+    I32,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntType {
+    pub fn all() -> &'static [IntType] {
+        &[
+            IntType::I8,
+            IntType::I16,
+            IntType::I32,
+            IntType::U8,
+            IntType::U16,
+            IntType::U32,
+            IntType::U64,
+        ]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IntType::I8 => "I8",
+            IntType::I16 => "I16",
+            IntType::I32 => "I32",
+            IntType::U8 => "U8",
+            IntType::U16 => "U16",
+            IntType::U32 => "U32",
+            IntType::U64 => "U64",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<IntType> {
+        IntType::all().iter().cloned().find(|t| t.name() == name)
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            IntType::I8 | IntType::U8 => 8,
+            IntType::I16 | IntType::U16 => 16,
+            IntType::I32 | IntType::U32 => 32,
+            IntType::U64 => 64,
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        match self {
+            IntType::I8 | IntType::I16 | IntType::I32 => true,
+            IntType::U8 | IntType::U16 | IntType::U32 | IntType::U64 => false,
+        }
+    }
+
+    /// Whether `value` is representable in this type.
+    pub fn contains(self, value: i128) -> bool {
+        let bits = self.bits();
+        if self.is_signed() {
+            -(1 << (bits - 1)) <= value && value < 1 << (bits - 1)
+        } else {
+            0 <= value && value < 1 << bits
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
     Variable(TypeVarID),
     Number,
+    Int(IntType),
+    Float,
     Boolean,
     Empty,
+    String,
+    Char,
     Function(Box<Type>, Box<Type>),
+    Array(Box<Type>),
+    Tuple(Vec<Type>),
+    Range,
+    /// File opened with `open_file`
+    File,
+    Data(Identifier, Vec<Type>),
+}
+
+/// Builtin types which have fields
+pub const BUILTIN_STRUCTS: [Type; 2] = [Type::Range, Type::String];
+
+impl Type {
+    /// The index in the representation and the type of `field` of a builtin type.
+    pub fn builtin_field(&self, field: &str) -> Option<(u32, Type)> {
+        match (self, field) {
+            (Type::Range, "start") => Some((0, Type::Number)),
+            (Type::Range, "end") => Some((1, Type::Number)),
+            (Type::Range, "inclusive") => Some((2, Type::Boolean)),
+            // The length of a string is the number of bytes in UTF-8
+            (Type::String, "len") | (Type::Array(_), "len") => Some((1, Type::Number)),
+            _ => None,
+        }
+    }
+
+    /// Whether the type is represented as an integer which is sign-extended.
+    pub fn is_signed(&self) -> bool {
+        match self {
+            Type::Number => true,
+            Type::Int(t) => t.is_signed(),
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Type::Variable(id) => write!(f, "{}", id),
+            Type::Int(t) => write!(f, "{}", t.name()),
             Type::Function(box t1, box t2) => write!(f, "({} -> {})", t1, t2),
+            Type::Array(box t) => write!(f, "[{}]", t),
+            Type::Tuple(elems) => {
+                write!(f, "(")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    elem.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+            Type::Data(name, args) => {
+                write!(f, "{}", name)?;
+                if !args.is_empty() {
+                    write!(f, "(")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i != 0 {
+                            write!(f, ", ")?;
+                        }
+                        arg.fmt(f)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
             _ => write!(f, "{:?}", self),
         }
     }
@@ -0,0 +1,309 @@
+use super::{BindingKind, Identifier, IntType, Literal, Node, Type, TypeExpr, Value};
+use crate::error::TranslationError;
+
+use failure::Error;
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constructor {
+    pub name: Identifier,
+    pub fields: Vec<TypeExpr>,
+}
+
+/// A user-defined sum type, declared with `let Name = <A(..)|B(..)>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataType {
+    pub name: Identifier,
+    pub params: Vec<Identifier>,
+    pub constructors: Vec<Constructor>,
+}
+
+impl DataType {
+    /// Bind each constructor to a curried function which constructs the value.
+    pub fn bind_constructors(&self, body: Node) -> Node {
+        self.constructors.iter().rev().fold(body, |body, ctor| {
+            let params: Vec<Identifier> =
+                (0..ctor.fields.len()).map(|i| format!("_{}", i)).collect();
+            let args = params
+                .iter()
+                .map(|p| Value::Variable(p.clone()).untyped_node())
+                .collect();
+            let construct = Value::Construct(ctor.name.clone(), args).untyped_node();
+            let value = params.iter().rev().fold(construct, |e, p| {
+                Value::Literal(Literal::Function(p.clone(), box e, HashMap::new())).untyped_node()
+            });
+            Value::Let(
+                BindingKind::Immutable,
+                ctor.name.clone(),
+                box value,
+                box body,
+            )
+            .untyped_node()
+        })
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<")?;
+        for (i, ctor) in self.constructors.iter().enumerate() {
+            if i != 0 {
+                write!(f, "|")?;
+            }
+            write!(f, "{}(", ctor.name)?;
+            for (j, field) in ctor.fields.iter().enumerate() {
+                if j != 0 {
+                    write!(f, ", ")?;
+                }
+                field.fmt(f)?;
+            }
+            write!(f, ")")?;
+        }
+        write!(f, ">")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructField {
+    pub kind: BindingKind,
+    pub name: Identifier,
+    pub ty: TypeExpr,
+}
+
+/// A user-defined struct type, declared with `let Name = struct { x: T, mut y: U }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructType {
+    pub name: Identifier,
+    pub params: Vec<Identifier>,
+    pub fields: Vec<StructField>,
+}
+
+impl StructType {
+    /// Find the field `name`, with the index of the field.
+    pub fn field(&self, name: &str) -> Option<(usize, &StructField)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .find(|(_, field)| field.name == name)
+    }
+}
+
+impl fmt::Display for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "struct {{ ")?;
+        for (i, field) in self.fields.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            if field.kind == BindingKind::Mutable {
+                write!(f, "mut ")?;
+            }
+            write!(f, "{}: {}", field.name, field.ty)?;
+        }
+        write!(f, " }}")
+    }
+}
+
+/// Another name for a type, declared with `type Name(a) = T`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeAlias {
+    pub name: Identifier,
+    pub params: Vec<Identifier>,
+    pub ty: TypeExpr,
+}
+
+impl fmt::Display for TypeAlias {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.ty)
+    }
+}
+
+/// Definitions of the named types in the program.
+#[derive(Debug, Clone, Default)]
+pub struct TypeDefs {
+    data_types: HashMap<Identifier, DataType>,
+    /// The name of the data type which declares each constructor.
+    constructors: HashMap<Identifier, Identifier>,
+    struct_types: HashMap<Identifier, StructType>,
+    aliases: HashMap<Identifier, TypeAlias>,
+}
+
+impl TypeDefs {
+    pub fn new() -> Self {
+        TypeDefs {
+            data_types: HashMap::new(),
+            constructors: HashMap::new(),
+            struct_types: HashMap::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Define `data_type`, which must not reuse the constructor names of the other data types.
+    pub fn define_data_type(&mut self, data_type: DataType) -> Result<(), Error> {
+        for ctor in &data_type.constructors {
+            match self.constructors.get(&ctor.name) {
+                Some(owner) if owner != &data_type.name => {
+                    return Err(TranslationError::DuplicateConstructor {
+                        name: ctor.name.clone(),
+                        ty: owner.clone(),
+                    }
+                    .into());
+                }
+                _ => {}
+            }
+        }
+
+        self.remove_data_type(&data_type.name);
+        self.struct_types.remove(&data_type.name);
+        self.aliases.remove(&data_type.name);
+        for ctor in &data_type.constructors {
+            self.constructors
+                .insert(ctor.name.clone(), data_type.name.clone());
+        }
+        self.data_types.insert(data_type.name.clone(), data_type);
+        Ok(())
+    }
+
+    fn remove_data_type(&mut self, name: &str) {
+        if let Some(data_type) = self.data_types.remove(name) {
+            for ctor in &data_type.constructors {
+                self.constructors.remove(&ctor.name);
+            }
+        }
+    }
+
+    pub fn define_struct_type(&mut self, struct_type: StructType) {
+        self.remove_data_type(&struct_type.name);
+        self.aliases.remove(&struct_type.name);
+        self.struct_types
+            .insert(struct_type.name.clone(), struct_type);
+    }
+
+    pub fn define_alias(&mut self, alias: TypeAlias) {
+        self.remove_data_type(&alias.name);
+        self.struct_types.remove(&alias.name);
+        self.aliases.insert(alias.name.clone(), alias);
+    }
+
+    pub fn data_type(&self, name: &str) -> Option<&DataType> {
+        self.data_types.get(name)
+    }
+
+    pub fn struct_type(&self, name: &str) -> Option<&StructType> {
+        self.struct_types.get(name)
+    }
+
+    /// Find the struct types which have the field `name`.
+    pub fn find_struct_with_field(&self, name: &str) -> Vec<&StructType> {
+        self.struct_types
+            .values()
+            .filter(|struct_type| struct_type.field(name).is_some())
+            .collect()
+    }
+
+    fn params(&self, name: &str) -> Option<&[Identifier]> {
+        self.data_types
+            .get(name)
+            .map(|data_type| data_type.params.as_slice())
+            .or_else(|| {
+                self.struct_types
+                    .get(name)
+                    .map(|struct_type| struct_type.params.as_slice())
+            })
+            .or_else(|| self.aliases.get(name).map(|alias| alias.params.as_slice()))
+    }
+
+    /// Find the data type which has the constructor `name`, with the index of the constructor.
+    pub fn find_constructor(&self, name: &str) -> Option<(&DataType, usize)> {
+        let data_type = &self.data_types[self.constructors.get(name)?];
+        data_type
+            .constructors
+            .iter()
+            .position(|ctor| ctor.name == name)
+            .map(|tag| (data_type, tag))
+    }
+
+    /// Resolve `expr` to `Type`, replacing type variables according to `vars`.
+    pub fn resolve(
+        &self,
+        expr: &TypeExpr,
+        vars: &HashMap<Identifier, Type>,
+    ) -> Result<Type, Error> {
+        self.resolve_expanding(expr, vars, &[])
+    }
+
+    /// Resolve `expr` inside of the expansion of the aliases in `expanding`.
+    fn resolve_expanding(
+        &self,
+        expr: &TypeExpr,
+        vars: &HashMap<Identifier, Type>,
+        expanding: &[&str],
+    ) -> Result<Type, Error> {
+        let resolve_all = |exprs: &[TypeExpr]| {
+            exprs
+                .iter()
+                .map(|expr| self.resolve_expanding(expr, vars, expanding))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(match expr {
+            TypeExpr::Variable(name) => vars
+                .get(name)
+                .cloned()
+                .ok_or(TranslationError::UndeclaredType)?,
+            TypeExpr::Function(param, ret) => Type::Function(
+                box self.resolve_expanding(param, vars, expanding)?,
+                box self.resolve_expanding(ret, vars, expanding)?,
+            ),
+            TypeExpr::Tuple(elems) => Type::Tuple(resolve_all(elems)?),
+            // The constraints are checked where the type is instantiated
+            TypeExpr::Constrained(_, ty) => self.resolve_expanding(ty, vars, expanding)?,
+            TypeExpr::Named(name, args) => {
+                let mut args = resolve_all(args)?;
+                match name.as_str() {
+                    "Number" | "I64" if args.is_empty() => Type::Number,
+                    "Float" if args.is_empty() => Type::Float,
+                    "Boolean" if args.is_empty() => Type::Boolean,
+                    "Empty" if args.is_empty() => Type::Empty,
+                    "Range" if args.is_empty() => Type::Range,
+                    "File" if args.is_empty() => Type::File,
+                    "String" if args.is_empty() => Type::String,
+                    "Char" if args.is_empty() => Type::Char,
+                    "Array" if args.len() == 1 => Type::Array(box args.remove(0)),
+                    _ => match IntType::from_name(name) {
+                        Some(t) if args.is_empty() => Type::Int(t),
+                        _ => {
+                            let params =
+                                self.params(name).ok_or(TranslationError::UndeclaredType)?;
+                            if params.len() != args.len() {
+                                return Err(TranslationError::TypeArgumentMismatch {
+                                    name: name.clone(),
+                                    expected: params.len(),
+                                    found: args.len(),
+                                }
+                                .into());
+                            }
+                            match self.aliases.get(name) {
+                                Some(alias) => {
+                                    if expanding.contains(&name.as_str()) {
+                                        return Err(TranslationError::RecursiveTypeAlias {
+                                            name: name.clone(),
+                                        }
+                                        .into());
+                                    }
+                                    let alias_vars =
+                                        params.iter().cloned().zip(args.into_iter()).collect();
+                                    let mut expanding = expanding.to_vec();
+                                    expanding.push(name);
+                                    self.resolve_expanding(&alias.ty, &alias_vars, &expanding)?
+                                }
+                                None => Type::Data(name.clone(), args),
+                            }
+                        }
+                    },
+                }
+            }
+        })
+    }
+}
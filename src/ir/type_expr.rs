@@ -0,0 +1,124 @@
+use super::Identifier;
+
+use std::fmt;
+
+/// A type written in the source code, which is resolved to `Type` once the definitions of the
+/// named types are known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeExpr {
+    Named(Identifier, Vec<TypeExpr>),
+    Variable(Identifier),
+    Function(Box<TypeExpr>, Box<TypeExpr>),
+    Tuple(Vec<TypeExpr>),
+    /// Type whose variables are required to have instances of classes, such as `Eq a => a`
+    Constrained(Vec<(Identifier, Identifier)>, Box<TypeExpr>),
+}
+
+impl TypeExpr {
+    /// Type variables appearing in this expression, in order of their first appearance.
+    pub fn free_vars(&self) -> Vec<Identifier> {
+        let mut vars = Vec::new();
+        self.collect_vars(&mut vars);
+        vars
+    }
+
+    /// Whether the named type `name` appears in this expression.
+    pub fn mentions(&self, name: &str) -> bool {
+        match self {
+            TypeExpr::Named(n, args) => n == name || args.iter().any(|arg| arg.mentions(name)),
+            TypeExpr::Variable(_) => false,
+            TypeExpr::Function(param, ret) => param.mentions(name) || ret.mentions(name),
+            TypeExpr::Tuple(elems) => elems.iter().any(|elem| elem.mentions(name)),
+            TypeExpr::Constrained(_, ty) => ty.mentions(name),
+        }
+    }
+
+    /// Replace the type variable `var` with `ty`.
+    pub fn substitute(&self, var: &str, ty: &TypeExpr) -> TypeExpr {
+        match self {
+            TypeExpr::Named(name, args) => TypeExpr::Named(
+                name.clone(),
+                args.iter().map(|arg| arg.substitute(var, ty)).collect(),
+            ),
+            TypeExpr::Variable(name) if name == var => ty.clone(),
+            TypeExpr::Variable(_) => self.clone(),
+            TypeExpr::Function(param, ret) => {
+                TypeExpr::Function(box param.substitute(var, ty), box ret.substitute(var, ty))
+            }
+            TypeExpr::Tuple(elems) => {
+                TypeExpr::Tuple(elems.iter().map(|elem| elem.substitute(var, ty)).collect())
+            }
+            TypeExpr::Constrained(constraints, inner) => {
+                TypeExpr::Constrained(constraints.clone(), box inner.substitute(var, ty))
+            }
+        }
+    }
+
+    fn collect_vars(&self, vars: &mut Vec<Identifier>) {
+        match self {
+            TypeExpr::Named(_, args) => {
+                for arg in args {
+                    arg.collect_vars(vars);
+                }
+            }
+            TypeExpr::Variable(name) => {
+                if !vars.contains(name) {
+                    vars.push(name.clone());
+                }
+            }
+            TypeExpr::Function(param, ret) => {
+                param.collect_vars(vars);
+                ret.collect_vars(vars);
+            }
+            TypeExpr::Tuple(elems) => {
+                for elem in elems {
+                    elem.collect_vars(vars);
+                }
+            }
+            TypeExpr::Constrained(_, ty) => ty.collect_vars(vars),
+        }
+    }
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeExpr::Named(name, args) => {
+                write!(f, "{}", name)?;
+                if !args.is_empty() {
+                    write!(f, "(")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i != 0 {
+                            write!(f, ", ")?;
+                        }
+                        arg.fmt(f)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            TypeExpr::Variable(name) => write!(f, "{}", name),
+            TypeExpr::Function(param, ret) => write!(f, "({} -> {})", param, ret),
+            TypeExpr::Tuple(elems) => {
+                write!(f, "(")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    elem.fmt(f)?;
+                }
+                write!(f, ")")
+            }
+            TypeExpr::Constrained(constraints, ty) => {
+                write!(f, "(")?;
+                for (i, (class, var)) in constraints.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} {}", class, var)?;
+                }
+                write!(f, ") => {}", ty)
+            }
+        }
+    }
+}
@@ -1,16 +1,52 @@
-use super::{BindingKind, Identifier, Literal, Node, Type};
-use crate::expression::Operator;
+use super::{
+    BindingKind, Builtin, Class, DataType, Identifier, Instance, Literal, MatchArm, Node,
+    StructType, Type, TypeAlias, TypeExpr,
+};
+use crate::expression::{Operator, RangeKind, UnaryOperator};
 
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub enum Value {
     Let(BindingKind, Identifier, Box<Node>, Box<Node>),
+    LetTuple(BindingKind, Vec<Identifier>, Box<Node>, Box<Node>),
+    LetRec(Vec<(Identifier, Node)>, Box<Node>),
     Follow(Box<Node>, Box<Node>),
     Assign(Box<Node>, Box<Node>),
     Apply(Box<Node>, Box<Node>),
     BinOp(Operator, Box<Node>, Box<Node>),
+    UnaryOp(UnaryOperator, Box<Node>),
+    Cast(Box<Node>, TypeExpr),
+    Ascribe(Box<Node>, TypeExpr),
     IfElse(Box<Node>, Box<Node>, Box<Node>),
+    Match(Box<Node>, Vec<MatchArm>),
+    While(Box<Node>, Box<Node>),
+    Break(Option<Box<Node>>),
+    Continue,
+    Throw(Box<Node>),
+    /// Evaluation of the body, in which a thrown value is bound to the identifier in the handler
+    Try(Box<Node>, Identifier, Box<Node>),
+    Array(Vec<Node>),
+    Tuple(Vec<Node>),
+    TupleIndex(Box<Node>, usize),
+    Range(Box<Node>, Box<Node>, RangeKind),
+    DefineType(DataType, Box<Node>),
+    Construct(Identifier, Vec<Node>),
+    CallBuiltin(Builtin, Vec<Node>),
+    /// Call of a C function declared with its type, which takes all the arguments at once
+    CallExtern(Identifier, TypeExpr, Vec<Node>),
+    DefineStruct(StructType, Box<Node>),
+    DefineAlias(TypeAlias, Box<Node>),
+    DefineClass(Class, Box<Node>),
+    DefineInstance(Instance, Box<Node>),
+    /// Declaration of the type of the values thrown in the whole program
+    DefineException(TypeExpr, Box<Node>),
+    /// Reference to a class method with the number of the instances declared before it, which
+    /// is resolved to the implementation in an instance once its type is known
+    Method(Identifier, usize),
+    Struct(Identifier, Vec<(Identifier, Node)>),
+    Field(Box<Node>, Identifier),
+    StructUpdate(Box<Node>, Vec<(Identifier, Node)>),
     Variable(Identifier),
     Literal(Literal),
 }
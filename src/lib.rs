@@ -8,7 +8,9 @@ pub mod test;
 
 pub mod codegen;
 pub mod expression;
+pub mod fixity;
 pub mod ir;
+pub mod module;
 pub mod parser;
 pub mod scope;
 pub mod transform;
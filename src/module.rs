@@ -0,0 +1,457 @@
+use crate::error::ModuleError;
+use crate::expression::Expression;
+use crate::ir::Builtin;
+use crate::parser;
+
+use failure::Error;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A module loaded from a file, with its exported names mapped to the names they are bound with
+/// in the linked program.
+#[derive(Debug, Clone)]
+struct Module {
+    exports: HashMap<String, String>,
+}
+
+/// Names in scope while linking a file.
+#[derive(Default, Clone)]
+struct Names {
+    /// Top-level bindings of a module, which are renamed to be unique in the linked program
+    renamed: HashMap<String, String>,
+    /// Imported modules by their aliases
+    modules: HashMap<String, Module>,
+}
+
+impl Names {
+    /// Names in scope of the expressions in which `names` are bound again.
+    fn shadow<'a>(&self, names: impl IntoIterator<Item = &'a String>) -> Names {
+        let mut scope = self.clone();
+        for name in names {
+            scope.renamed.remove(name);
+            scope.modules.remove(name);
+        }
+        scope
+    }
+
+    fn rename(&self, name: String) -> String {
+        self.renamed.get(&name).cloned().unwrap_or(name)
+    }
+
+    fn resolve_box(&self, expr: Box<Expression>) -> Result<Box<Expression>, Error> {
+        Ok(box self.resolve(*expr)?)
+    }
+
+    fn resolve_option(
+        &self,
+        expr: Option<Box<Expression>>,
+    ) -> Result<Option<Box<Expression>>, Error> {
+        match expr {
+            Some(expr) => Ok(Some(self.resolve_box(expr)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn resolve_all(&self, exprs: Vec<Expression>) -> Result<Vec<Expression>, Error> {
+        exprs.into_iter().map(|e| self.resolve(e)).collect()
+    }
+
+    fn resolve_fields(
+        &self,
+        fields: Vec<(String, Expression)>,
+    ) -> Result<Vec<(String, Expression)>, Error> {
+        fields
+            .into_iter()
+            .map(|(name, e)| Ok((name, self.resolve(e)?)))
+            .collect()
+    }
+
+    /// Replace the references to the renamed bindings and the qualified names such as `m.name`.
+    fn resolve(&self, expr: Expression) -> Result<Expression, Error> {
+        Ok(match expr {
+            Expression::Identifier(name) => Expression::Identifier(self.rename(name)),
+            Expression::Field(box Expression::Identifier(alias), name)
+                if self.modules.contains_key(&alias) =>
+            {
+                match self.modules[&alias].exports.get(&name) {
+                    Some(renamed) => Expression::Identifier(renamed.clone()),
+                    None => {
+                        return Err(ModuleError::NotExported {
+                            module: alias,
+                            name,
+                        }
+                        .into());
+                    }
+                }
+            }
+            Expression::Follow(box lhs, box rhs) => match lhs {
+                Expression::Bind(kind, name, value) => {
+                    let value = self.resolve_box(value)?;
+                    let rhs = self.shadow(&[name.clone()]).resolve(rhs)?;
+                    Expression::Follow(box Expression::Bind(kind, name, value), box rhs)
+                }
+                Expression::BindTuple(kind, names, value) => {
+                    let value = self.resolve_box(value)?;
+                    let rhs = self.shadow(&names).resolve(rhs)?;
+                    Expression::Follow(box Expression::BindTuple(kind, names, value), box rhs)
+                }
+                Expression::BindRec(bindings) => {
+                    let scope = self.shadow(bindings.iter().map(|(name, _)| name));
+                    let bindings = scope.resolve_fields(bindings)?;
+                    Expression::Follow(box Expression::BindRec(bindings), box scope.resolve(rhs)?)
+                }
+                lhs => Expression::Follow(box self.resolve(lhs)?, box self.resolve(rhs)?),
+            },
+            Expression::Function(param, body) => {
+                let body = self.shadow(&[param.clone()]).resolve_box(body)?;
+                Expression::Function(param, body)
+            }
+            Expression::Bind(kind, name, value) => {
+                Expression::Bind(kind, name, self.resolve_box(value)?)
+            }
+            Expression::BindTuple(kind, names, value) => {
+                Expression::BindTuple(kind, names, self.resolve_box(value)?)
+            }
+            Expression::BindRec(bindings) => {
+                let scope = self.shadow(bindings.iter().map(|(name, _)| name));
+                Expression::BindRec(scope.resolve_fields(bindings)?)
+            }
+            Expression::Match(value, arms) => Expression::Match(
+                self.resolve_box(value)?,
+                arms.into_iter()
+                    .map(|(pattern, guard, body)| {
+                        let scope = self.shadow(&pattern.bound_vars());
+                        let guard = match guard {
+                            Some(guard) => Some(scope.resolve(guard)?),
+                            None => None,
+                        };
+                        Ok((pattern, guard, scope.resolve(body)?))
+                    })
+                    .collect::<Result<_, Error>>()?,
+            ),
+            Expression::Try(body, name, handler) => {
+                let body = self.resolve_box(body)?;
+                let handler = self.shadow(&[name.clone()]).resolve_box(handler)?;
+                Expression::Try(body, name, handler)
+            }
+            Expression::For(name, range, body) => {
+                let range = self.resolve_box(range)?;
+                let body = self.shadow(&[name.clone()]).resolve_box(body)?;
+                Expression::For(name, range, body)
+            }
+            Expression::Section(op, lhs, rhs) => Expression::Section(
+                self.rename(op),
+                self.resolve_option(lhs)?,
+                self.resolve_option(rhs)?,
+            ),
+            Expression::Array(elems) => Expression::Array(self.resolve_all(elems)?),
+            Expression::Tuple(elems) => Expression::Tuple(self.resolve_all(elems)?),
+            Expression::Assign(lhs, rhs) => {
+                Expression::Assign(self.resolve_box(lhs)?, self.resolve_box(rhs)?)
+            }
+            Expression::BinOp(op, lhs, rhs) => {
+                Expression::BinOp(op, self.resolve_box(lhs)?, self.resolve_box(rhs)?)
+            }
+            Expression::UnaryOp(op, value) => Expression::UnaryOp(op, self.resolve_box(value)?),
+            Expression::PartialApply(func, args) => {
+                Expression::PartialApply(self.resolve_box(func)?, self.resolve_all(args)?)
+            }
+            Expression::TupleIndex(tuple, index) => {
+                Expression::TupleIndex(self.resolve_box(tuple)?, index)
+            }
+            Expression::Field(value, name) => Expression::Field(self.resolve_box(value)?, name),
+            Expression::Struct(name, fields) => {
+                Expression::Struct(name, self.resolve_fields(fields)?)
+            }
+            Expression::StructUpdate(value, fields) => {
+                Expression::StructUpdate(self.resolve_box(value)?, self.resolve_fields(fields)?)
+            }
+            Expression::Apply(lhs, rhs) => {
+                Expression::Apply(self.resolve_box(lhs)?, self.resolve_box(rhs)?)
+            }
+            Expression::IfElse(cond, then_, else_) => Expression::IfElse(
+                self.resolve_box(cond)?,
+                self.resolve_box(then_)?,
+                self.resolve_box(else_)?,
+            ),
+            Expression::While(cond, body) => {
+                Expression::While(self.resolve_box(cond)?, self.resolve_box(body)?)
+            }
+            Expression::Break(value) => Expression::Break(self.resolve_option(value)?),
+            Expression::Throw(value) => Expression::Throw(self.resolve_box(value)?),
+            Expression::Range(start, end, kind) => {
+                Expression::Range(self.resolve_box(start)?, self.resolve_box(end)?, kind)
+            }
+            Expression::Cast(value, ty) => Expression::Cast(self.resolve_box(value)?, ty),
+            Expression::Ascribe(value, ty) => Expression::Ascribe(self.resolve_box(value)?, ty),
+            Expression::Instance(class, ty, methods) => {
+                Expression::Instance(class, ty, self.resolve_fields(methods)?)
+            }
+            Expression::Scope(body) => Expression::Scope(self.resolve_box(body)?),
+            Expression::Import(..) | Expression::Export(..) => {
+                return Err(ModuleError::NotTopLevel.into());
+            }
+            expr => expr,
+        })
+    }
+}
+
+/// Expressions in the top-level sequence of `expr`.
+fn items(expr: Expression) -> Vec<Expression> {
+    match expr {
+        Expression::Follow(box lhs, box rhs) => {
+            let mut items = vec![lhs];
+            items.extend(self::items(rhs));
+            items
+        }
+        expr => vec![expr],
+    }
+}
+
+/// Names of the values bound by a top-level expression. Types are not included, as they are
+/// visible in all the modules.
+fn bound_names(item: &Expression) -> Vec<String> {
+    match item {
+        Expression::Bind(_, _, box Expression::Type(_))
+        | Expression::Bind(_, _, box Expression::StructType(_)) => Vec::new(),
+        Expression::Bind(_, name, _) => vec![name.clone()],
+        Expression::BindTuple(_, names, _) => names.clone(),
+        Expression::BindRec(bindings) => bindings.iter().map(|(name, _)| name.clone()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Names of the types, classes and constructors declared by a top-level expression, which are
+/// visible in all the modules without being exported.
+fn declared_types(item: &Expression) -> Vec<String> {
+    match item {
+        Expression::Bind(_, name, box Expression::Type(ctors)) => {
+            let mut names = vec![name.clone()];
+            for (ctor, _) in ctors {
+                if let Expression::TypeIdentifier(ctor) = ctor {
+                    names.push(ctor.clone());
+                }
+            }
+            names
+        }
+        Expression::Bind(_, name, box Expression::StructType(_))
+        | Expression::TypeAlias(name, ..)
+        | Expression::Class(name, ..) => vec![name.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Links the modules imported from a program into one expression, in which each module is
+/// included once before its first import.
+#[derive(Default)]
+struct Loader {
+    modules: HashMap<PathBuf, Module>,
+    /// Modules being loaded, which cannot be imported again
+    loading: Vec<PathBuf>,
+    /// Names declared by `declared_types` with the prefix of the module declaring them, which
+    /// is `None` for the main program
+    types: HashMap<String, Option<String>>,
+}
+
+impl Loader {
+    fn load(&mut self, path: &Path, items: &mut Vec<Expression>) -> Result<Module, Error> {
+        let not_found = || ModuleError::NotFound {
+            path: path.to_path_buf(),
+        };
+        let path = fs::canonicalize(path).map_err(|_| not_found())?;
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(module.clone());
+        }
+        if self.loading.contains(&path) {
+            return Err(ModuleError::CyclicImport { path }.into());
+        }
+
+        let source = fs::read_to_string(&path).map_err(|_| not_found())?;
+        let expr = parser::parse(source.trim())?;
+        // The top-level names are prefixed with the number of the module to be unique, which
+        // is the number of the modules loaded or being loaded before it
+        let prefix = format!("_m{}_", self.modules.len() + self.loading.len());
+        self.loading.push(path.clone());
+        let exports = self.link(expr, path.parent().unwrap(), Some(&prefix), items)?;
+        self.loading.pop();
+
+        let module = Module { exports };
+        self.modules.insert(path, module.clone());
+        Ok(module)
+    }
+
+    /// Append the top-level expressions of `expr` to `items`, preceded by the modules imported
+    /// from it. Returns the exported names.
+    fn link(
+        &mut self,
+        expr: Expression,
+        dir: &Path,
+        prefix: Option<&str>,
+        items: &mut Vec<Expression>,
+    ) -> Result<HashMap<String, String>, Error> {
+        let mut names = Names::default();
+        let mut exports = HashMap::new();
+        for item in self::items(expr) {
+            let (item, is_exported) = match item {
+                Expression::Import(path, alias) => {
+                    let module = self.load(&dir.join(path), items)?;
+                    names = names.shadow(&[alias.clone()]);
+                    names.modules.insert(alias, module);
+                    continue;
+                }
+                Expression::Export(box item) => (item, true),
+                item => (item, false),
+            };
+
+            let bound = bound_names(&item);
+            let declared = declared_types(&item);
+            if is_exported && bound.is_empty() && declared.is_empty() {
+                return Err(ModuleError::InvalidExport.into());
+            }
+            for name in declared {
+                let module = prefix.map(str::to_string);
+                match self.types.get(&name) {
+                    Some(declared_in) if *declared_in != module => {
+                        return Err(ModuleError::DuplicateType { name }.into());
+                    }
+                    _ => {
+                        self.types.insert(name, module);
+                    }
+                }
+            }
+            let mut scope = names.shadow(&bound);
+            if let Some(prefix) = prefix {
+                for name in &bound {
+                    scope
+                        .renamed
+                        .insert(name.clone(), format!("{}{}", prefix, name));
+                }
+            }
+
+            let item = match item {
+                Expression::Bind(kind, name, value) => {
+                    Expression::Bind(kind, scope.rename(name), names.resolve_box(value)?)
+                }
+                Expression::BindTuple(kind, idents, value) => Expression::BindTuple(
+                    kind,
+                    idents.into_iter().map(|n| scope.rename(n)).collect(),
+                    names.resolve_box(value)?,
+                ),
+                Expression::BindRec(bindings) => Expression::BindRec(
+                    bindings
+                        .into_iter()
+                        .map(|(name, value)| Ok((scope.rename(name), scope.resolve(value)?)))
+                        .collect::<Result<_, Error>>()?,
+                ),
+                item => names.resolve(item)?,
+            };
+            if is_exported {
+                exports.extend(bound.into_iter().map(|name| {
+                    let renamed = scope.rename(name.clone());
+                    (name, renamed)
+                }));
+            }
+            items.push(item);
+            names = scope;
+        }
+        Ok(exports)
+    }
+}
+
+fn follow(mut items: Vec<Expression>) -> Expression {
+    let last = items.pop().unwrap();
+    items
+        .into_iter()
+        .rev()
+        .fold(last, |rest, item| Expression::Follow(box item, box rest))
+}
+
+/// Link the modules imported from `expr`, whose paths are relative to `dir`.
+pub fn link(expr: Expression, dir: &Path) -> Result<Expression, Error> {
+    let mut items = Vec::new();
+    Loader::default().link(expr, dir, None, &mut items)?;
+    Ok(follow(items))
+}
+
+/// Load the program in the file at `path` with the modules imported from it.
+pub fn load(path: &Path) -> Result<Expression, Error> {
+    let not_found = || ModuleError::NotFound {
+        path: path.to_path_buf(),
+    };
+    let path = fs::canonicalize(path).map_err(|_| not_found())?;
+    let source = fs::read_to_string(&path).map_err(|_| not_found())?;
+    let expr = parser::parse(source.trim())?;
+
+    let mut loader = Loader::default();
+    loader.loading.push(path.clone());
+    let mut items = Vec::new();
+    loader.link(expr, path.parent().unwrap(), None, &mut items)?;
+    Ok(follow(items))
+}
+
+/// Name of the function which is applied to the command-line arguments if a program defines it.
+const MAIN: &str = "main";
+
+/// Whether `main` is bound to a function at the top level of the linked program `expr`, which
+/// is decided by the last binding of the name.
+pub fn defines_main(expr: &Expression) -> bool {
+    main_binding(expr).map_or(false, |value| value.map_or(false, is_function))
+}
+
+/// The value of the last top-level binding of `main` in `expr`, which is `None` inside if it is
+/// bound by destructuring.
+fn main_binding(expr: &Expression) -> Option<Option<&Expression>> {
+    match expr {
+        Expression::Follow(box lhs, box rhs) => main_binding(rhs).or_else(|| main_binding(lhs)),
+        Expression::Bind(_, name, box value) if name == MAIN => Some(Some(value)),
+        Expression::BindRec(bindings) => bindings
+            .iter()
+            .find(|(name, _)| name == MAIN)
+            .map(|(_, value)| Some(value)),
+        item if bound_names(item).iter().any(|name| name == MAIN) => Some(None),
+        _ => None,
+    }
+}
+
+fn is_function(expr: &Expression) -> bool {
+    match expr {
+        Expression::Function(..) | Expression::Section(..) | Expression::PartialApply(..) => true,
+        Expression::Ascribe(box value, _) | Expression::Scope(box value) => is_function(value),
+        _ => false,
+    }
+}
+
+/// Append the application of `main` to the command-line arguments to the linked program `expr`.
+pub fn call_main(expr: Expression) -> Expression {
+    let args = Expression::Apply(
+        box Expression::Identifier(Builtin::Args.name().to_string()),
+        box Expression::Empty,
+    );
+    let mut items = items(expr);
+    items.push(Expression::Apply(
+        box Expression::Identifier(MAIN.to_string()),
+        box args,
+    ));
+    follow(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load;
+
+    use std::path::Path;
+
+    #[test]
+    fn duplicate_type() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/test/test_data/modules/duplicate_type.epi"
+        );
+        assert_eq!(
+            load(Path::new(path)).unwrap_err().to_string(),
+            "Shape is declared in more than one module"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::ParseError;
 use crate::expression::Expression;
+use crate::fixity;
 
 use failure::Error;
 
@@ -9,12 +10,16 @@ pub mod syntax {
 }
 
 pub fn parse(x: &str) -> Result<Expression, Error> {
-    syntax::expression(x).map_err(|e| {
-        ParseError {
-            message: e.to_string(),
-        }
-        .into()
-    })
+    // The interpreter line of a script such as `#!/usr/bin/env -S expressi run` is skipped
+    let x = if x.starts_with("#!") {
+        &x[x.find('\n').unwrap_or_else(|| x.len())..]
+    } else {
+        x
+    };
+    let expr = syntax::expression(x).map_err(|e| ParseError {
+        message: e.to_string(),
+    })?;
+    fixity::resolve(expr)
 }
 
 #[cfg(test)]
@@ -42,6 +47,14 @@ mod tests {
         )
     }
 
+    #[test]
+    fn skip_shebang() {
+        assert_eq!(
+            parse("#!/usr/bin/env expressi run\n1+1").unwrap(),
+            parse("1+1").unwrap()
+        )
+    }
+
     #[test]
     fn skip_line_comment() {
         assert_eq!(
@@ -61,18 +74,79 @@ mod tests {
     mod literal {
         use super::parse;
         use crate::expression::Expression;
+        use crate::ir::{IntType, Type};
 
         #[test]
         fn number() {
             assert_eq!(parse("0").unwrap(), Expression::Number(0))
         }
 
+        #[test]
+        fn number_out_of_range() {
+            assert!(parse("9223372036854775808").is_err());
+            assert!(parse("x.99999999999999999999").is_err())
+        }
+
+        #[test]
+        fn sized_number() {
+            assert_eq!(
+                parse("255u8").unwrap(),
+                Expression::SizedNumber(255, Type::Int(IntType::U8))
+            );
+            assert_eq!(
+                parse("-128i8").unwrap(),
+                Expression::SizedNumber(-128, Type::Int(IntType::I8))
+            );
+            assert_eq!(
+                parse("18446744073709551615u64").unwrap(),
+                Expression::SizedNumber(18446744073709551615, Type::Int(IntType::U64))
+            );
+            assert_eq!(
+                parse("1i64").unwrap(),
+                Expression::SizedNumber(1, Type::Number)
+            );
+            assert!(parse("1u7").is_err());
+        }
+
         #[test]
         fn boolean() {
             assert_eq!(parse("true").unwrap(), Expression::Boolean(true));
             assert_eq!(parse("false").unwrap(), Expression::Boolean(false));
         }
 
+        #[test]
+        fn float() {
+            assert_eq!(parse("1.5").unwrap(), Expression::Float(1.5));
+            assert_eq!(parse("2.5e-3").unwrap(), Expression::Float(2.5e-3));
+            assert_eq!(parse("10.0E2").unwrap(), Expression::Float(1000.0))
+        }
+
+        #[test]
+        fn string() {
+            assert_eq!(
+                parse("\"hello, world\"").unwrap(),
+                Expression::String("hello, world".to_owned())
+            );
+            assert_eq!(parse("\"\"").unwrap(), Expression::String("".to_owned()))
+        }
+
+        #[test]
+        fn string_escape() {
+            assert_eq!(
+                parse(r#""a\n\t\"\\\u{3042}""#).unwrap(),
+                Expression::String("a\n\t\"\\\u{3042}".to_owned())
+            );
+            assert!(parse(r#""\q""#).is_err())
+        }
+
+        #[test]
+        fn char_() {
+            assert_eq!(parse("'a'").unwrap(), Expression::Char('a'));
+            assert_eq!(parse("'\"'").unwrap(), Expression::Char('"'));
+            assert_eq!(parse(r"'\''").unwrap(), Expression::Char('\''));
+            assert!(parse("'ab'").is_err())
+        }
+
         #[test]
         fn multidigit_number() {
             assert_eq!(parse("10").unwrap(), Expression::Number(10))
@@ -91,6 +165,49 @@ mod tests {
             assert_eq!(parse("a").unwrap(), Expression::Identifier("a".to_string()))
         }
 
+        #[test]
+        fn array() {
+            assert_eq!(
+                parse("[0, 1]").unwrap(),
+                Expression::Array(vec![Expression::Number(0), Expression::Number(1)])
+            );
+            assert_eq!(parse("[ ]").unwrap(), Expression::Array(vec![]))
+        }
+
+        #[test]
+        fn tuple() {
+            assert_eq!(
+                parse("(0, true)").unwrap(),
+                Expression::Tuple(vec![Expression::Number(0), Expression::Boolean(true)])
+            );
+            assert_eq!(parse("( 0 )").unwrap(), Expression::Number(0))
+        }
+
+        #[test]
+        fn struct_() {
+            assert_eq!(
+                parse("P { x: 0, y: true }").unwrap(),
+                Expression::Struct(
+                    "P".to_owned(),
+                    vec![
+                        ("x".to_owned(), Expression::Number(0)),
+                        ("y".to_owned(), Expression::Boolean(true))
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn struct_update() {
+            assert_eq!(
+                parse("{ p with x = 0 }").unwrap(),
+                Expression::StructUpdate(
+                    Box::new(Expression::Identifier("p".to_owned())),
+                    vec![("x".to_owned(), Expression::Number(0))]
+                )
+            )
+        }
+
         #[test]
         fn invalid_identifier() {
             assert!(parse("_a").is_err());
@@ -100,7 +217,8 @@ mod tests {
 
     mod operator {
         use super::parse;
-        use crate::expression::{Expression, Operator};
+        use crate::expression::{Expression, Operator, RangeKind, UnaryOperator};
+        use crate::ir::BindingKind;
 
         macro_rules! test_binop {
             ($x:expr, $op:expr) => {
@@ -125,6 +243,7 @@ mod tests {
         fn operator_mul() {
             test_binop!("0*0", Operator::Mul);
             test_binop!("0/0", Operator::Div);
+            test_binop!("0%0", Operator::Rem);
         }
 
         #[test]
@@ -134,6 +253,51 @@ mod tests {
             test_binop!("0|0", Operator::BitOr);
         }
 
+        #[test]
+        fn operator_shift() {
+            test_binop!("0<<0", Operator::Shl);
+            test_binop!("0>>0", Operator::Shr);
+            test_binop!("0>>>0", Operator::UShr);
+            assert_eq!(
+                parse("1 << 2 + 3 < 4").unwrap(),
+                Expression::BinOp(
+                    Operator::Lt,
+                    Box::new(Expression::BinOp(
+                        Operator::Shl,
+                        Box::new(Expression::Number(1)),
+                        Box::new(Expression::BinOp(
+                            Operator::Add,
+                            Box::new(Expression::Number(2)),
+                            Box::new(Expression::Number(3))
+                        ))
+                    )),
+                    Box::new(Expression::Number(4))
+                )
+            );
+        }
+
+        #[test]
+        fn operator_logical() {
+            test_binop!("0&&0", Operator::And);
+            test_binop!("0||0", Operator::Or);
+            assert_eq!(
+                parse("a || b && c | d").unwrap(),
+                Expression::BinOp(
+                    Operator::Or,
+                    Box::new(Expression::Identifier("a".to_owned())),
+                    Box::new(Expression::BinOp(
+                        Operator::And,
+                        Box::new(Expression::Identifier("b".to_owned())),
+                        Box::new(Expression::BinOp(
+                            Operator::BitOr,
+                            Box::new(Expression::Identifier("c".to_owned())),
+                            Box::new(Expression::Identifier("d".to_owned()))
+                        ))
+                    ))
+                )
+            );
+        }
+
         #[test]
         fn operator_comp() {
             test_binop!("0<0", Operator::Lt);
@@ -144,6 +308,119 @@ mod tests {
             test_binop!("0!=0", Operator::Ne);
         }
 
+        #[test]
+        fn operator_user_defined() {
+            let apply = |op: &str, lhs, rhs| {
+                Expression::Apply(
+                    Box::new(Expression::Apply(
+                        Box::new(Expression::Identifier(op.to_owned())),
+                        Box::new(lhs),
+                    )),
+                    Box::new(rhs),
+                )
+            };
+            assert_eq!(
+                parse("infixl 6 <+> = f; a <+> b * c").unwrap(),
+                Expression::Follow(
+                    Box::new(Expression::Bind(
+                        BindingKind::Immutable,
+                        "<+>".to_owned(),
+                        Box::new(Expression::Identifier("f".to_owned()))
+                    )),
+                    Box::new(apply(
+                        "<+>",
+                        Expression::Identifier("a".to_owned()),
+                        Expression::BinOp(
+                            Operator::Mul,
+                            Box::new(Expression::Identifier("b".to_owned())),
+                            Box::new(Expression::Identifier("c".to_owned()))
+                        )
+                    ))
+                )
+            );
+            assert_eq!(
+                parse("infixr 5 ++> = f; a ++> b ++> c").unwrap(),
+                Expression::Follow(
+                    Box::new(Expression::Bind(
+                        BindingKind::Immutable,
+                        "++>".to_owned(),
+                        Box::new(Expression::Identifier("f".to_owned()))
+                    )),
+                    Box::new(apply(
+                        "++>",
+                        Expression::Identifier("a".to_owned()),
+                        apply(
+                            "++>",
+                            Expression::Identifier("b".to_owned()),
+                            Expression::Identifier("c".to_owned())
+                        )
+                    ))
+                )
+            );
+        }
+
+        #[test]
+        fn operator_user_defined_error() {
+            assert!(parse("a <+> b").is_err());
+            assert!(parse("infix 4 =~ = f; a =~ b =~ c").is_err());
+            assert!(parse("infixl 6 + = f").is_err());
+        }
+
+        #[test]
+        fn operator_unary() {
+            assert_eq!(
+                parse("!a").unwrap(),
+                Expression::UnaryOp(
+                    UnaryOperator::Not,
+                    Box::new(Expression::Identifier("a".to_owned()))
+                )
+            );
+            assert_eq!(
+                parse("-~a").unwrap(),
+                Expression::UnaryOp(
+                    UnaryOperator::Neg,
+                    Box::new(Expression::UnaryOp(
+                        UnaryOperator::BitNot,
+                        Box::new(Expression::Identifier("a".to_owned()))
+                    ))
+                )
+            );
+        }
+
+        #[test]
+        fn negative_literal() {
+            assert_eq!(parse("-1").unwrap(), Expression::Number(-1));
+            assert_eq!(parse("-1.5").unwrap(), Expression::Float(-1.5));
+            assert_eq!(
+                parse("-9223372036854775808").unwrap(),
+                Expression::Number(i64::min_value())
+            );
+            assert!(parse("-9223372036854775809").is_err());
+            assert_eq!(
+                parse("0 - -1").unwrap(),
+                Expression::BinOp(
+                    Operator::Sub,
+                    Box::new(Expression::Number(0)),
+                    Box::new(Expression::Number(-1))
+                )
+            );
+        }
+
+        #[test]
+        fn cast() {
+            assert_eq!(
+                parse("0 + 1 as Boolean").unwrap(),
+                Expression::BinOp(
+                    Operator::Add,
+                    Box::new(Expression::Number(0)),
+                    Box::new(Expression::Cast(
+                        Box::new(Expression::Number(1)),
+                        Box::new(Expression::TypeIdentifier("Boolean".to_owned()))
+                    ))
+                )
+            )
+        }
+
         #[test]
         fn operator_index() {
             test_binop!("0[0]", Operator::Index);
@@ -170,6 +447,199 @@ mod tests {
             )
         }
 
+        #[test]
+        fn tuple_index() {
+            assert_eq!(
+                parse("a.1").unwrap(),
+                Expression::TupleIndex(Box::new(Expression::Identifier("a".to_owned())), 1)
+            )
+        }
+
+        #[test]
+        fn tuple_index_multi() {
+            assert_eq!(
+                parse("a[0].1.0").unwrap(),
+                Expression::TupleIndex(
+                    Box::new(Expression::TupleIndex(
+                        Box::new(Expression::BinOp(
+                            Operator::Index,
+                            Box::new(Expression::Identifier("a".to_owned())),
+                            Box::new(Expression::Number(0))
+                        )),
+                        1
+                    )),
+                    0
+                )
+            )
+        }
+
+        #[test]
+        fn field() {
+            assert_eq!(
+                parse("a.x.y").unwrap(),
+                Expression::Field(
+                    Box::new(Expression::Field(
+                        Box::new(Expression::Identifier("a".to_owned())),
+                        "x".to_owned()
+                    )),
+                    "y".to_owned()
+                )
+            )
+        }
+
+        #[test]
+        fn assign_field() {
+            assert_eq!(
+                parse("a.x=0").unwrap(),
+                Expression::Assign(
+                    Box::new(Expression::Field(
+                        Box::new(Expression::Identifier("a".to_owned())),
+                        "x".to_owned()
+                    )),
+                    Box::new(Expression::Number(0))
+                )
+            )
+        }
+
+        #[test]
+        fn bind_tuple() {
+            assert_eq!(
+                parse("let (a, b) = 0").unwrap(),
+                Expression::BindTuple(
+                    BindingKind::Immutable,
+                    vec!["a".to_owned(), "b".to_owned()],
+                    Box::new(Expression::Number(0))
+                )
+            )
+        }
+
+        #[test]
+        fn bind_rec() {
+            assert_eq!(
+                parse("let rec f = x -> g(x) and g = x -> f(x)").unwrap(),
+                Expression::BindRec(vec![
+                    (
+                        "f".to_owned(),
+                        Expression::Function(
+                            "x".to_owned(),
+                            Box::new(Expression::Apply(
+                                Box::new(Expression::Identifier("g".to_owned())),
+                                Box::new(Expression::Identifier("x".to_owned()))
+                            ))
+                        )
+                    ),
+                    (
+                        "g".to_owned(),
+                        Expression::Function(
+                            "x".to_owned(),
+                            Box::new(Expression::Apply(
+                                Box::new(Expression::Identifier("f".to_owned())),
+                                Box::new(Expression::Identifier("x".to_owned()))
+                            ))
+                        )
+                    )
+                ])
+            )
+        }
+
+        #[test]
+        fn bind_rec_prefixed_name() {
+            assert_eq!(
+                parse("let record = 0").unwrap(),
+                Expression::Bind(
+                    BindingKind::Immutable,
+                    "record".to_owned(),
+                    Box::new(Expression::Number(0))
+                )
+            )
+        }
+
+        #[test]
+        fn while_() {
+            assert_eq!(
+                parse("while a { b = 1 }").unwrap(),
+                Expression::While(
+                    Box::new(Expression::Identifier("a".to_owned())),
+                    Box::new(Expression::Assign(
+                        Box::new(Expression::Identifier("b".to_owned())),
+                        Box::new(Expression::Number(1))
+                    ))
+                )
+            )
+        }
+
+        #[test]
+        fn break_value() {
+            assert_eq!(
+                parse("while true { break 1 }").unwrap(),
+                Expression::While(
+                    Box::new(Expression::Boolean(true)),
+                    Box::new(Expression::Break(Some(Box::new(Expression::Number(1)))))
+                )
+            )
+        }
+
+        #[test]
+        fn break_continue() {
+            assert_eq!(
+                parse("while true { continue; break }").unwrap(),
+                Expression::While(
+                    Box::new(Expression::Boolean(true)),
+                    Box::new(Expression::Follow(
+                        Box::new(Expression::Continue),
+                        Box::new(Expression::Break(None))
+                    ))
+                )
+            )
+        }
+
+        #[test]
+        fn range() {
+            assert_eq!(
+                parse("0..n").unwrap(),
+                Expression::Range(
+                    Box::new(Expression::Number(0)),
+                    Box::new(Expression::Identifier("n".to_owned())),
+                    RangeKind::Exclusive
+                )
+            )
+        }
+
+        #[test]
+        fn range_inclusive() {
+            assert_eq!(
+                parse("1..=n + 1").unwrap(),
+                Expression::Range(
+                    Box::new(Expression::Number(1)),
+                    Box::new(Expression::BinOp(
+                        Operator::Add,
+                        Box::new(Expression::Identifier("n".to_owned())),
+                        Box::new(Expression::Number(1))
+                    )),
+                    RangeKind::Inclusive
+                )
+            )
+        }
+
+        #[test]
+        fn for_() {
+            assert_eq!(
+                parse("for i in 0..3 { f(i) }").unwrap(),
+                Expression::For(
+                    "i".to_owned(),
+                    Box::new(Expression::Range(
+                        Box::new(Expression::Number(0)),
+                        Box::new(Expression::Number(3)),
+                        RangeKind::Exclusive
+                    )),
+                    Box::new(Expression::Apply(
+                        Box::new(Expression::Identifier("f".to_owned())),
+                        Box::new(Expression::Identifier("i".to_owned()))
+                    ))
+                )
+            )
+        }
+
         #[test]
         fn apply() {
             assert_eq!(
@@ -327,17 +797,517 @@ mod tests {
         }
     }
 
+    mod types {
+        use super::parse;
+        use crate::expression::Expression;
+        use crate::ir::BindingKind;
+
+        fn ident(name: &str) -> Expression {
+            Expression::TypeIdentifier(name.to_string())
+        }
+
+        #[test]
+        fn sum_type() {
+            assert_eq!(
+                parse("<A(Number)|B()>").unwrap(),
+                Expression::Type(vec![
+                    (ident("A"), vec![ident("Number")]),
+                    (ident("B"), vec![])
+                ])
+            )
+        }
+
+        #[test]
+        fn sum_type_spaced() {
+            assert_eq!(
+                parse("<A(Number, Boolean) | B()>").unwrap(),
+                Expression::Type(vec![
+                    (ident("A"), vec![ident("Number"), ident("Boolean")]),
+                    (ident("B"), vec![])
+                ])
+            )
+        }
+
+        #[test]
+        fn sum_type_apply() {
+            assert_eq!(
+                parse("<Cons(a, List(a))|Nil()>").unwrap(),
+                Expression::Type(vec![
+                    (
+                        ident("Cons"),
+                        vec![
+                            ident("a"),
+                            Expression::TypeApply("List".to_string(), vec![ident("a")])
+                        ]
+                    ),
+                    (ident("Nil"), vec![])
+                ])
+            )
+        }
+
+        #[test]
+        fn ascription() {
+            assert_eq!(
+                parse("(f : (a, Number) -> a -> Boolean)").unwrap(),
+                Expression::Ascribe(
+                    Box::new(Expression::Identifier("f".to_string())),
+                    Box::new(Expression::FunctionType(
+                        Box::new(Expression::TupleType(vec![ident("a"), ident("Number")])),
+                        Box::new(Expression::FunctionType(
+                            Box::new(ident("a")),
+                            Box::new(ident("Boolean"))
+                        ))
+                    ))
+                )
+            )
+        }
+
+        #[test]
+        fn let_annotation() {
+            assert_eq!(
+                parse("let x: Number = 1").unwrap(),
+                Expression::Bind(
+                    BindingKind::Immutable,
+                    "x".to_string(),
+                    Box::new(Expression::Ascribe(
+                        Box::new(Expression::Number(1)),
+                        Box::new(ident("Number"))
+                    ))
+                )
+            )
+        }
+
+        #[test]
+        fn param_annotation() {
+            assert_eq!(
+                parse("(x: Boolean, y) -> y").unwrap(),
+                Expression::function(
+                    "x".to_string(),
+                    Some(ident("Boolean")),
+                    Expression::Function(
+                        "y".to_string(),
+                        Box::new(Expression::Identifier("y".to_string()))
+                    )
+                )
+            )
+        }
+
+        #[test]
+        fn type_alias() {
+            assert_eq!(
+                parse("type Pair(a) = (a, a)").unwrap(),
+                Expression::TypeAlias(
+                    "Pair".to_string(),
+                    vec!["a".to_string()],
+                    Box::new(Expression::TupleType(vec![ident("a"), ident("a")]))
+                )
+            )
+        }
+
+        #[test]
+        fn class() {
+            assert_eq!(
+                parse("class Eq a { (==): a -> a -> Boolean, ne: a -> Boolean }").unwrap(),
+                Expression::Class(
+                    "Eq".to_string(),
+                    "a".to_string(),
+                    vec![
+                        (
+                            "==".to_string(),
+                            Expression::FunctionType(
+                                Box::new(ident("a")),
+                                Box::new(Expression::FunctionType(
+                                    Box::new(ident("a")),
+                                    Box::new(ident("Boolean"))
+                                ))
+                            )
+                        ),
+                        (
+                            "ne".to_string(),
+                            Expression::FunctionType(
+                                Box::new(ident("a")),
+                                Box::new(ident("Boolean"))
+                            )
+                        ),
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn instance() {
+            assert_eq!(
+                parse("instance Eq Point { (==) = p -> true }").unwrap(),
+                Expression::Instance(
+                    "Eq".to_string(),
+                    Box::new(ident("Point")),
+                    vec![(
+                        "==".to_string(),
+                        Expression::Function("p".to_string(), Box::new(Expression::Boolean(true)))
+                    )]
+                )
+            )
+        }
+
+        #[test]
+        fn constrained_type() {
+            assert_eq!(
+                parse("let f: (Eq a, Show b) => a -> b = g").unwrap(),
+                Expression::Bind(
+                    BindingKind::Immutable,
+                    "f".to_string(),
+                    Box::new(Expression::Ascribe(
+                        Box::new(Expression::Identifier("g".to_string())),
+                        Box::new(Expression::ConstrainedType(
+                            vec![
+                                ("Eq".to_string(), "a".to_string()),
+                                ("Show".to_string(), "b".to_string())
+                            ],
+                            Box::new(Expression::FunctionType(
+                                Box::new(ident("a")),
+                                Box::new(ident("b"))
+                            ))
+                        ))
+                    ))
+                )
+            )
+        }
+    }
+
+    mod struct_type {
+        use super::parse;
+        use crate::expression::Expression;
+        use crate::ir::BindingKind;
+
+        #[test]
+        fn struct_type() {
+            assert_eq!(
+                parse("struct { x: Number, mut y: List(a) }").unwrap(),
+                Expression::StructType(vec![
+                    (
+                        BindingKind::Immutable,
+                        "x".to_owned(),
+                        Expression::TypeIdentifier("Number".to_owned())
+                    ),
+                    (
+                        BindingKind::Mutable,
+                        "y".to_owned(),
+                        Expression::TypeApply(
+                            "List".to_owned(),
+                            vec![Expression::TypeIdentifier("a".to_owned())]
+                        )
+                    )
+                ])
+            )
+        }
+
+        #[test]
+        fn struct_type_mut_prefixed_field() {
+            assert_eq!(
+                parse("struct { mutable: Number }").unwrap(),
+                Expression::StructType(vec![(
+                    BindingKind::Immutable,
+                    "mutable".to_owned(),
+                    Expression::TypeIdentifier("Number".to_owned())
+                )])
+            )
+        }
+    }
+
+    mod pattern {
+        use super::parse;
+        use crate::expression::{Expression, Operator};
+        use crate::ir::Pattern;
+
+        #[test]
+        fn match_literal() {
+            assert_eq!(
+                parse("match x { 0 => true, _ => false }").unwrap(),
+                Expression::Match(
+                    Box::new(Expression::Identifier("x".to_owned())),
+                    vec![
+                        (Pattern::Number(0), None, Expression::Boolean(true)),
+                        (Pattern::Wildcard, None, Expression::Boolean(false))
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn match_negative_literal() {
+            assert_eq!(
+                parse("match x { -1 => true, _ => false }").unwrap(),
+                Expression::Match(
+                    Box::new(Expression::Identifier("x".to_owned())),
+                    vec![
+                        (Pattern::Number(-1), None, Expression::Boolean(true)),
+                        (Pattern::Wildcard, None, Expression::Boolean(false))
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn match_literal_out_of_range() {
+            assert!(parse("match x { 9223372036854775808 => true, _ => false }").is_err())
+        }
+
+        #[test]
+        fn match_boolean_prefix() {
+            assert_eq!(
+                parse("match x { trueish => 0 }").unwrap(),
+                Expression::Match(
+                    Box::new(Expression::Identifier("x".to_owned())),
+                    vec![(
+                        Pattern::Variable("trueish".to_owned()),
+                        None,
+                        Expression::Number(0)
+                    )]
+                )
+            )
+        }
+
+        #[test]
+        fn match_trailing_comma() {
+            assert_eq!(
+                parse("match x { _ => 0, }").unwrap(),
+                parse("match x { _ => 0 }").unwrap()
+            )
+        }
+
+        #[test]
+        fn match_guard() {
+            assert_eq!(
+                parse("match x { n if n > 0 => n, _ => 0 }").unwrap(),
+                Expression::Match(
+                    Box::new(Expression::Identifier("x".to_owned())),
+                    vec![
+                        (
+                            Pattern::Variable("n".to_owned()),
+                            Some(Expression::BinOp(
+                                Operator::Gt,
+                                Box::new(Expression::Identifier("n".to_owned())),
+                                Box::new(Expression::Number(0))
+                            )),
+                            Expression::Identifier("n".to_owned())
+                        ),
+                        (Pattern::Wildcard, None, Expression::Number(0))
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn match_constructor() {
+            assert_eq!(
+                parse("match x { Cons(h, (a, true)) => h, Nil() => 0 }").unwrap(),
+                Expression::Match(
+                    Box::new(Expression::Identifier("x".to_owned())),
+                    vec![
+                        (
+                            Pattern::Constructor(
+                                "Cons".to_owned(),
+                                vec![
+                                    Pattern::Variable("h".to_owned()),
+                                    Pattern::Tuple(vec![
+                                        Pattern::Variable("a".to_owned()),
+                                        Pattern::Boolean(true)
+                                    ])
+                                ]
+                            ),
+                            None,
+                            Expression::Identifier("h".to_owned())
+                        ),
+                        (
+                            Pattern::Constructor("Nil".to_owned(), vec![]),
+                            None,
+                            Expression::Number(0)
+                        )
+                    ]
+                )
+            )
+        }
+
+        #[test]
+        fn match_follow() {
+            assert_eq!(
+                parse("match x { _ => 0 }; 1").unwrap(),
+                Expression::Follow(
+                    Box::new(Expression::Match(
+                        Box::new(Expression::Identifier("x".to_owned())),
+                        vec![(Pattern::Wildcard, None, Expression::Number(0))]
+                    )),
+                    Box::new(Expression::Number(1))
+                )
+            )
+        }
+    }
+
     mod sugar {
         use super::parse;
+        use crate::expression::{Expression, Operator};
 
         #[test]
         fn function_params() {
             assert_eq!(parse("(a,b,c)->0").unwrap(), parse("a->b->c->0").unwrap())
         }
 
+        #[test]
+        fn function_params_not_tuple() {
+            assert_eq!(parse("(a, b) -> a").unwrap(), parse("a -> b -> a").unwrap())
+        }
+
         #[test]
         fn application() {
             assert_eq!(parse("f(a,b,c)").unwrap(), parse("f(a)(b)(c)").unwrap())
         }
+
+        #[test]
+        fn application_without_args() {
+            assert_eq!(
+                parse("f()").unwrap(),
+                Expression::Apply(
+                    Box::new(Expression::Identifier("f".to_owned())),
+                    Box::new(Expression::Empty)
+                )
+            )
+        }
+
+        #[test]
+        fn section() {
+            assert_eq!(
+                parse("(+)").unwrap(),
+                Expression::Section("+".to_owned(), None, None)
+            );
+            assert_eq!(
+                parse("( < 1)").unwrap(),
+                Expression::Section("<".to_owned(), None, Some(Box::new(Expression::Number(1))))
+            );
+            assert_eq!(
+                parse("(1 -)").unwrap(),
+                Expression::Section("-".to_owned(), Some(Box::new(Expression::Number(1))), None)
+            );
+        }
+
+        #[test]
+        fn section_not_negation() {
+            assert_eq!(parse("(- 1)").unwrap(), Expression::Number(-1));
+            assert_eq!(parse("(1 - 2)").unwrap(), parse("1 - 2").unwrap())
+        }
+
+        #[test]
+        fn placeholder() {
+            assert_eq!(
+                parse("f(_, b)(c)").unwrap(),
+                Expression::Apply(
+                    Box::new(Expression::PartialApply(
+                        Box::new(Expression::Identifier("f".to_owned())),
+                        vec![
+                            Expression::Placeholder,
+                            Expression::Identifier("b".to_owned())
+                        ]
+                    )),
+                    Box::new(Expression::Identifier("c".to_owned()))
+                )
+            )
+        }
+    }
+
+    mod module {
+        use super::parse;
+        use crate::expression::Expression;
+        use crate::ir::BindingKind;
+
+        #[test]
+        fn import() {
+            assert_eq!(
+                parse("import \"lib/list.epi\" as list; list.length").unwrap(),
+                Expression::Follow(
+                    Box::new(Expression::Import(
+                        "lib/list.epi".to_owned(),
+                        "list".to_owned()
+                    )),
+                    Box::new(Expression::Field(
+                        Box::new(Expression::Identifier("list".to_owned())),
+                        "length".to_owned()
+                    ))
+                )
+            )
+        }
+
+        #[test]
+        fn export() {
+            assert_eq!(
+                parse("export let x = 1").unwrap(),
+                Expression::Export(Box::new(Expression::Bind(
+                    BindingKind::Immutable,
+                    "x".to_owned(),
+                    Box::new(Expression::Number(1))
+                )))
+            );
+            assert!(parse("export 1").is_err())
+        }
+    }
+
+    mod ffi {
+        use super::parse;
+        use crate::expression::Expression;
+        use crate::ir::BindingKind;
+
+        #[test]
+        fn extern_declaration() {
+            assert_eq!(
+                parse("extern labs: Number -> Number").unwrap(),
+                Expression::Bind(
+                    BindingKind::Immutable,
+                    "labs".to_owned(),
+                    Box::new(Expression::Extern(
+                        "labs".to_owned(),
+                        Box::new(Expression::FunctionType(
+                            Box::new(Expression::TypeIdentifier("Number".to_owned())),
+                            Box::new(Expression::TypeIdentifier("Number".to_owned()))
+                        ))
+                    ))
+                )
+            );
+            assert!(parse("extern labs").is_err())
+        }
+    }
+
+    mod exception {
+        use super::parse;
+        use crate::expression::Expression;
+
+        #[test]
+        fn throw() {
+            assert_eq!(
+                parse("throw \"error\"").unwrap(),
+                Expression::Throw(Box::new(Expression::String("error".to_owned())))
+            )
+        }
+
+        #[test]
+        fn try_catch() {
+            assert_eq!(
+                parse("try { f(1) } catch e { 0 }").unwrap(),
+                Expression::Try(
+                    Box::new(Expression::Apply(
+                        Box::new(Expression::Identifier("f".to_owned())),
+                        Box::new(Expression::Number(1))
+                    )),
+                    "e".to_owned(),
+                    Box::new(Expression::Number(0))
+                )
+            );
+            assert!(parse("try { 1 }").is_err())
+        }
+
+        #[test]
+        fn exception_declaration() {
+            assert_eq!(
+                parse("exception Number").unwrap(),
+                Expression::Exception(Box::new(Expression::TypeIdentifier("Number".to_owned())))
+            )
+        }
     }
 }
@@ -1,19 +1,39 @@
 macro_rules! file_test {
     ($name: ident) => {
+        file_test!(@test $name, false);
+    };
+    // The program is given a new temporary directory as the argument
+    ($name: ident, temp_dir) => {
+        file_test!(@test $name, true);
+    };
+    (@test $name: ident, $temp_dir: expr) => {
         #[test]
         fn $name() {
+            use crate::codegen::compilation_result::{Args, Outcome};
             use crate::codegen::{compile, initialization};
             use inkwell::OptimizationLevel;
 
             initialization::initialize_native().unwrap();
 
-            let contents = include_str!(concat!("test_data/", stringify!($name), ".epi"));
-            match compile::compile_string(&contents.trim(), "test_input") {
+            let path = concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/src/test/test_data/",
+                stringify!($name),
+                ".epi"
+            );
+            match compile::compile_file(std::path::Path::new(path), "test_input") {
                 Ok(result) => {
-                    let func = result.emit_function(OptimizationLevel::None).unwrap();
+                    let program = result.emit_function(OptimizationLevel::None).unwrap();
+                    let dir = tempfile::tempdir().unwrap();
+                    let dir_args = if $temp_dir {
+                        vec![dir.path().to_str().unwrap().to_string()]
+                    } else {
+                        vec![]
+                    };
+                    let args = Args::new(path.to_string(), &dir_args).unwrap();
                     assert_eq!(
-                        unsafe { func.call() },
-                        include!(concat!("test_data/", stringify!($name), ".ans"))
+                        unsafe { program.run(&args) },
+                        Outcome::Value(include!(concat!("test_data/", stringify!($name), ".ans")))
                     );
                 }
                 Err(err) => assert!(false, format!("{}", err)),
@@ -33,3 +53,61 @@ file_test!(infer_poly);
 file_test!(capture_list_let);
 file_test!(complex_subst_apply);
 file_test!(translate_polyfunc_one_candidate);
+file_test!(array_index);
+file_test!(array_assign);
+file_test!(array_nested);
+file_test!(sum_type);
+file_test!(sum_type_param);
+file_test!(sum_type_recursive);
+file_test!(tuple);
+file_test!(tuple_destructure);
+file_test!(tuple_poly);
+file_test!(struct_field);
+file_test!(struct_update);
+file_test!(struct_assign);
+file_test!(struct_param);
+file_test!(match_literal);
+file_test!(match_data);
+file_test!(match_tuple);
+file_test!(match_diverge);
+file_test!(nested_ifelse);
+file_test!(cast);
+file_test!(let_rec);
+file_test!(let_rec_mutual);
+file_test!(let_rec_poly);
+file_test!(let_rec_capture);
+file_test!(while_sum);
+file_test!(while_break);
+file_test!(while_continue);
+file_test!(while_large);
+file_test!(for_sum);
+file_test!(for_nested);
+file_test!(for_large);
+file_test!(for_inclusive_max);
+file_test!(range_value);
+file_test!(string_len);
+file_test!(string_compare);
+file_test!(string_index);
+file_test!(string_bytes);
+file_test!(float_arith);
+file_test!(float_builtin);
+file_test!(float_compare);
+file_test!(unary_op);
+file_test!(short_circuit);
+file_test!(signed_div);
+file_test!(sized_int);
+file_test!(sized_int_match);
+file_test!(sized_int_literal);
+file_test!(section);
+file_test!(user_operator);
+file_test!(annotation);
+file_test!(type_class);
+file_test!(type_class_poly);
+file_test!(import_module);
+file_test!(extern_function);
+file_test!(console_io);
+file_test!(file_io, temp_dir);
+file_test!(process_env);
+file_test!(main_value);
+file_test!(exceptions);
+file_test!(exception_type);
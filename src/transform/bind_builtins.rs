@@ -0,0 +1,79 @@
+use super::Transform;
+use crate::ir;
+
+use failure::Error;
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+pub struct BindBuiltins;
+
+/// Collects the names of all variables referred in a program.
+#[derive(Default)]
+struct VariableNames(HashSet<ir::Identifier>);
+
+impl Transform for VariableNames {
+    fn transform_variable(&mut self, ident: &str) -> Result<ir::Value, Error> {
+        self.0.insert(ident.to_string());
+        Ok(ir::Value::Variable(ident.to_string()))
+    }
+
+    fn transform_literal(&mut self, lit: &ir::Literal) -> Result<ir::Value, Error> {
+        if let ir::Literal::Function(_, box body, _) = lit {
+            self.transform(body)?;
+        }
+        Ok(ir::Value::Literal(lit.clone()))
+    }
+}
+
+/// Bind `builtin` to a curried function which calls it.
+fn bind_builtin(builtin: ir::Builtin, body: ir::Node) -> ir::Node {
+    let (param_types, _) = builtin.signature();
+    let params: Vec<ir::Identifier> = (0..param_types.len()).map(|i| format!("_{}", i)).collect();
+    let args = params
+        .iter()
+        .map(|p| ir::Value::Variable(p.clone()).untyped_node())
+        .collect();
+    let call = ir::Value::CallBuiltin(builtin, args).untyped_node();
+    let value = params.iter().rev().fold(call, |e, p| {
+        ir::Value::Literal(ir::Literal::Function(p.clone(), box e, HashMap::new())).untyped_node()
+    });
+    ir::Value::Let(
+        ir::BindingKind::Immutable,
+        builtin.name().to_string(),
+        box value,
+        box body,
+    )
+    .untyped_node()
+}
+
+impl BindBuiltins {
+    pub fn new() -> Self {
+        BindBuiltins
+    }
+}
+
+impl Transform for BindBuiltins {
+    fn transform(&mut self, eir: &ir::Node) -> Result<ir::Node, Error> {
+        // Only the builtins which may be used are bound, to keep the output small
+        let mut names = VariableNames::default();
+        names.transform(eir)?;
+
+        let used: Vec<_> = ir::Builtin::all()
+            .iter()
+            .filter(|builtin| names.0.contains(builtin.name()))
+            .collect();
+        let body = used
+            .iter()
+            .fold(eir.clone(), |body, builtin| bind_builtin(**builtin, body));
+
+        // Failures are returned as `Result`, which has to be declared outside of them
+        if used.iter().any(|builtin| builtin.is_fallible()) {
+            let data_type = ir::Builtin::result_type();
+            let body = data_type.bind_constructors(body);
+            Ok(ir::Value::DefineType(data_type, box body).untyped_node())
+        } else {
+            Ok(body)
+        }
+    }
+}
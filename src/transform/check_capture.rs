@@ -28,13 +28,107 @@ fn collect_vars(
                 .chain(collect_vars(body)?)
                 .filter(move |(e, _)| *e != ident)
         }
+        ir::Value::LetTuple(_, idents, box v, box body) => {
+            let idents = idents.clone();
+            box collect_vars(v)?
+                .chain(collect_vars(body)?)
+                .filter(move |(e, _)| !idents.contains(e))
+        }
+        ir::Value::LetRec(bindings, box body) => {
+            let idents: Vec<_> = bindings.iter().map(|(ident, _)| ident.clone()).collect();
+            let mut vars = collect_vars(body)?.collect::<Vec<_>>();
+            for (_, v) in bindings {
+                vars.extend(collect_vars(v)?);
+            }
+            box vars.into_iter().filter(move |(e, _)| !idents.contains(e))
+        }
         ir::Value::Assign(box lhs, box rhs) => box collect_vars(lhs)?.chain(collect_vars(rhs)?),
         ir::Value::Follow(box lhs, box rhs) => box collect_vars(lhs)?.chain(collect_vars(rhs)?),
         ir::Value::Apply(box lhs, box rhs) => box collect_vars(lhs)?.chain(collect_vars(rhs)?),
         ir::Value::BinOp(_, box lhs, box rhs) => box collect_vars(lhs)?.chain(collect_vars(rhs)?),
+        ir::Value::UnaryOp(_, box value) => collect_vars(value)?,
+        ir::Value::Cast(box value, _) | ir::Value::Ascribe(box value, _) => collect_vars(value)?,
         ir::Value::IfElse(box cond, box then_, box else_) => box collect_vars(cond)?
             .chain(collect_vars(then_)?)
             .chain(collect_vars(else_)?),
+        ir::Value::Match(box value, arms) => {
+            let mut vars: Box<dyn Iterator<Item = _>> = collect_vars(value)?;
+            for arm in arms {
+                let bound = arm.pattern.bound_vars();
+                let arm_vars = match &arm.guard {
+                    Some(guard) => collect_vars(guard)?
+                        .chain(collect_vars(&arm.body)?)
+                        .collect(),
+                    None => collect_vars(&arm.body)?.collect::<Vec<_>>(),
+                };
+                vars = box vars.chain(
+                    arm_vars
+                        .into_iter()
+                        .filter(move |(e, _)| !bound.contains(e)),
+                );
+            }
+            vars
+        }
+        ir::Value::While(box cond, box body) => box collect_vars(cond)?.chain(collect_vars(body)?),
+        ir::Value::Break(Some(box value)) | ir::Value::Throw(box value) => collect_vars(value)?,
+        ir::Value::Try(box body, ident, box handler) => {
+            let ident = ident.clone();
+            box collect_vars(body)?.chain(collect_vars(handler)?.filter(move |(e, _)| *e != ident))
+        }
+        ir::Value::Break(None) | ir::Value::Continue | ir::Value::Method(..) => {
+            box vec![].into_iter()
+        }
+        ir::Value::Array(elems) => box elems
+            .iter()
+            .map(collect_vars)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten(),
+        ir::Value::Tuple(elems) => box elems
+            .iter()
+            .map(collect_vars)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten(),
+        ir::Value::TupleIndex(box tuple, _) => collect_vars(tuple)?,
+        ir::Value::Range(box start, box end, _) => {
+            box collect_vars(start)?.chain(collect_vars(end)?)
+        }
+        ir::Value::DefineType(_, box body) => collect_vars(body)?,
+        ir::Value::Construct(_, args)
+        | ir::Value::CallBuiltin(_, args)
+        | ir::Value::CallExtern(_, _, args) => box args
+            .iter()
+            .map(collect_vars)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten(),
+        ir::Value::DefineStruct(_, box body) => collect_vars(body)?,
+        ir::Value::DefineAlias(_, box body)
+        | ir::Value::DefineClass(_, box body)
+        | ir::Value::DefineException(_, box body) => collect_vars(body)?,
+        ir::Value::DefineInstance(instance, box body) => {
+            let mut vars = collect_vars(body)?.collect::<Vec<_>>();
+            for (_, v) in &instance.methods {
+                vars.extend(collect_vars(v)?);
+            }
+            box vars.into_iter()
+        }
+        ir::Value::Struct(_, fields) => box fields
+            .iter()
+            .map(|(_, e)| collect_vars(e))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten(),
+        ir::Value::Field(box value, _) => collect_vars(value)?,
+        ir::Value::StructUpdate(box value, fields) => box collect_vars(value)?.chain(
+            fields
+                .iter()
+                .map(|(_, e)| collect_vars(e))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten(),
+        ),
     })
 }
 
@@ -13,6 +13,106 @@ pub enum TypeInferError {
     #[fail(display = "Undeclared identifier \"{}\"", ident)]
     UndeclaredIdentifier { ident: String },
 
+    #[fail(display = "Expected a tuple type, found: {}", ty)]
+    NotTuple { ty: Type },
+
+    #[fail(display = "Index {} is out of range for tuple type {}", index, ty)]
+    TupleIndexOutOfRange { index: usize, ty: Type },
+
+    #[fail(display = "Expected a struct type, found: {}", ty)]
+    NotStruct { ty: Type },
+
+    #[fail(display = "Type {} has no field named {}", ty, field)]
+    UnknownField { ty: String, field: String },
+
+    #[fail(display = "Missing field {} in the literal of {}", field, ty)]
+    MissingField { ty: String, field: String },
+
+    #[fail(display = "Field {} is specified more than once", field)]
+    DuplicateField { field: String },
+
+    #[fail(display = "Cannot determine the struct type which has field {}", field)]
+    AmbiguousField { field: String },
+
+    #[fail(
+        display = "Constructor {} expects {} arguments, but {} given",
+        name, expected, found
+    )]
+    PatternArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[fail(
+        display = "Variable \"{}\" is bound more than once in the pattern",
+        ident
+    )]
+    DuplicatePatternVariable { ident: String },
+
+    #[fail(display = "Non-exhaustive patterns in match expression")]
+    NonExhaustiveMatch,
+
+    #[fail(display = "Unreachable pattern: {}", pattern)]
+    UnreachablePattern { pattern: String },
+
+    #[fail(display = "Invalid cast from {} to {}", from, to)]
+    InvalidCast { from: Type, to: Type },
+
+    #[fail(
+        display = "Type variable {} in the annotation is instantiated to {}",
+        var, ty
+    )]
+    NotPolymorphic { var: String, ty: Type },
+
+    #[fail(display = "Undeclared class {}", name)]
+    UndeclaredClass { name: String },
+
+    #[fail(display = "Class {} has no method named {}", class, method)]
+    UnknownMethod { class: String, method: String },
+
+    #[fail(display = "Missing method {} in the instance of {}", method, class)]
+    MissingMethod { class: String, method: String },
+
+    #[fail(display = "Instance {} {} is declared more than once", class, ty)]
+    DuplicateInstance { class: String, ty: Type },
+
+    #[fail(
+        display = "Instance {} {} must be declared for a concrete type",
+        class, ty
+    )]
+    PolymorphicInstance { class: String, ty: String },
+
+    #[fail(
+        display = "Type of method {} must refer to the class parameter {} only",
+        method, param
+    )]
+    InvalidMethodType { method: String, param: String },
+
+    #[fail(display = "No instance of {} for type {}", class, ty)]
+    NoInstance { class: String, ty: Type },
+
+    #[fail(
+        display = "Extern function {} cannot take or return a value of type {}",
+        name, ty
+    )]
+    InvalidExternType { name: String, ty: Type },
+
+    #[fail(display = "break outside of a loop")]
+    BreakOutsideLoop,
+
+    #[fail(display = "continue outside of a loop")]
+    ContinueOutsideLoop,
+
+    #[fail(display = "A loop which breaks with a value must be `while true`")]
+    ConditionalLoopValue,
+
+    #[fail(
+        display = "Exceptions are declared to be {}, but {} is already thrown",
+        declared, used
+    )]
+    ConflictingException { declared: Type, used: Type },
+
     #[fail(display = "Unexpected not-typed value")]
     NotTyped,
 }
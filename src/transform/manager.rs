@@ -1,4 +1,4 @@
-use super::{CheckCapture, Transform, TypeInfer};
+use super::{BindBuiltins, CheckCapture, Transform, TypeInfer};
 use crate::ir;
 
 use failure::Error;
@@ -20,7 +20,11 @@ impl TransformManager {
 impl Default for TransformManager {
     fn default() -> Self {
         TransformManager {
-            transforms: vec![box TypeInfer::new(), box CheckCapture::new()],
+            transforms: vec![
+                box BindBuiltins::new(),
+                box TypeInfer::new(),
+                box CheckCapture::new(),
+            ],
         }
     }
 }
@@ -1,9 +1,11 @@
+pub mod bind_builtins;
 pub mod check_capture;
 pub mod error;
 pub mod manager;
 pub mod traits;
 pub mod type_infer;
 
+pub use self::bind_builtins::BindBuiltins;
 pub use self::check_capture::CheckCapture;
 pub use self::manager::TransformManager;
 pub use self::traits::Transform;
@@ -1,4 +1,4 @@
-use crate::expression::Operator;
+use crate::expression::{Operator, RangeKind, UnaryOperator};
 use crate::ir;
 
 use failure::Error;
@@ -15,6 +15,19 @@ pub trait Transform {
                 let body = self.transform(body)?;
                 self.transform_let(*kind, ident, &value, &body)?
             }
+            ir::Value::LetTuple(kind, idents, box value, box body) => {
+                let value = self.transform(value)?;
+                let body = self.transform(body)?;
+                self.transform_let_tuple(*kind, idents, &value, &body)?
+            }
+            ir::Value::LetRec(bindings, box body) => {
+                let bindings = bindings
+                    .iter()
+                    .map(|(ident, value)| Ok((ident.clone(), self.transform(value)?)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let body = self.transform(body)?;
+                self.transform_let_rec(&bindings, &body)?
+            }
             ir::Value::Assign(box lhs, box rhs) => {
                 let lhs = self.transform(lhs)?;
                 let rhs = self.transform(rhs)?;
@@ -35,12 +48,160 @@ pub trait Transform {
                 let rhs = self.transform(rhs)?;
                 self.transform_binop(*op, &lhs, &rhs)?
             }
+            ir::Value::UnaryOp(op, box value) => {
+                let value = self.transform(value)?;
+                self.transform_unary_op(*op, &value)?
+            }
+            ir::Value::Cast(box value, ty) => {
+                let value = self.transform(value)?;
+                self.transform_cast(&value, ty)?
+            }
+            ir::Value::Ascribe(box value, ty) => {
+                let value = self.transform(value)?;
+                self.transform_ascribe(&value, ty)?
+            }
             ir::Value::IfElse(box cond, box then_, box else_) => {
                 let cond = self.transform(cond)?;
                 let then_ = self.transform(then_)?;
                 let else_ = self.transform(else_)?;
                 self.transform_ifelse(&cond, &then_, &else_)?
             }
+            ir::Value::Match(box value, arms) => {
+                let value = self.transform(value)?;
+                let arms = arms
+                    .iter()
+                    .map(|arm| {
+                        Ok(ir::MatchArm {
+                            pattern: arm.pattern.clone(),
+                            guard: match &arm.guard {
+                                Some(guard) => Some(self.transform(guard)?),
+                                None => None,
+                            },
+                            body: self.transform(&arm.body)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                self.transform_match(&value, &arms)?
+            }
+            ir::Value::While(box cond, box body) => {
+                let cond = self.transform(cond)?;
+                let body = self.transform(body)?;
+                self.transform_while(&cond, &body)?
+            }
+            ir::Value::Break(value) => {
+                let value = match value {
+                    Some(box value) => Some(self.transform(value)?),
+                    None => None,
+                };
+                self.transform_break(value.as_ref())?
+            }
+            ir::Value::Continue => self.transform_continue()?,
+            ir::Value::Throw(box value) => {
+                let value = self.transform(value)?;
+                self.transform_throw(&value)?
+            }
+            ir::Value::Try(box body, ident, box handler) => {
+                let body = self.transform(body)?;
+                let handler = self.transform(handler)?;
+                self.transform_try(&body, ident, &handler)?
+            }
+            ir::Value::Array(elems) => {
+                let elems = elems
+                    .iter()
+                    .map(|e| self.transform(e))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.transform_array(&elems)?
+            }
+            ir::Value::Tuple(elems) => {
+                let elems = elems
+                    .iter()
+                    .map(|e| self.transform(e))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.transform_tuple(&elems)?
+            }
+            ir::Value::TupleIndex(box tuple, index) => {
+                let tuple = self.transform(tuple)?;
+                self.transform_tuple_index(&tuple, *index)?
+            }
+            ir::Value::Range(box start, box end, kind) => {
+                let start = self.transform(start)?;
+                let end = self.transform(end)?;
+                self.transform_range(&start, &end, *kind)?
+            }
+            ir::Value::DefineType(data_type, box body) => {
+                let body = self.transform(body)?;
+                self.transform_define_type(data_type, &body)?
+            }
+            ir::Value::Construct(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|e| self.transform(e))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.transform_construct(name, &args)?
+            }
+            ir::Value::CallBuiltin(builtin, args) => {
+                let args = args
+                    .iter()
+                    .map(|e| self.transform(e))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.transform_call_builtin(*builtin, &args)?
+            }
+            ir::Value::CallExtern(name, ty, args) => {
+                let args = args
+                    .iter()
+                    .map(|e| self.transform(e))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.transform_call_extern(name, ty, &args)?
+            }
+            ir::Value::DefineStruct(struct_type, box body) => {
+                let body = self.transform(body)?;
+                self.transform_define_struct(struct_type, &body)?
+            }
+            ir::Value::DefineAlias(alias, box body) => {
+                let body = self.transform(body)?;
+                self.transform_define_alias(alias, &body)?
+            }
+            ir::Value::DefineClass(class, box body) => {
+                let body = self.transform(body)?;
+                self.transform_define_class(class, &body)?
+            }
+            ir::Value::DefineInstance(instance, box body) => {
+                let methods = instance
+                    .methods
+                    .iter()
+                    .map(|(name, e)| Ok((name.clone(), self.transform(e)?)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let instance = ir::Instance {
+                    methods,
+                    ..instance.clone()
+                };
+                let body = self.transform(body)?;
+                self.transform_define_instance(&instance, &body)?
+            }
+            ir::Value::DefineException(ty, box body) => {
+                let body = self.transform(body)?;
+                self.transform_define_exception(ty, &body)?
+            }
+            ir::Value::Method(name, visible) => self.transform_method(name, *visible)?,
+            ir::Value::Struct(name, fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, e)| Ok((field.clone(), self.transform(e)?)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                self.transform_struct(name, &fields)?
+            }
+            ir::Value::Field(box value, field) => {
+                let value = self.transform(value)?;
+                self.transform_field(&value, field)?
+            }
+            ir::Value::StructUpdate(box value, fields) => {
+                let value = self.transform(value)?;
+                let fields = fields
+                    .iter()
+                    .map(|(field, e)| Ok((field.clone(), self.transform(e)?)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                self.transform_struct_update(&value, &fields)?
+            }
         };
 
         let instantiation_table = eir
@@ -77,6 +238,29 @@ pub trait Transform {
         ))
     }
 
+    fn transform_let_tuple(
+        &mut self,
+        kind: ir::BindingKind,
+        idents: &[ir::Identifier],
+        v: &ir::Node,
+        body: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::LetTuple(
+            kind,
+            idents.to_vec(),
+            box v.clone(),
+            box body.clone(),
+        ))
+    }
+
+    fn transform_let_rec(
+        &mut self,
+        bindings: &[(ir::Identifier, ir::Node)],
+        body: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::LetRec(bindings.to_vec(), box body.clone()))
+    }
+
     fn transform_assign(&mut self, lhs: &ir::Node, rhs: &ir::Node) -> Result<ir::Value, Error> {
         Ok(ir::Value::Assign(box lhs.clone(), box rhs.clone()))
     }
@@ -98,6 +282,26 @@ pub trait Transform {
         Ok(ir::Value::BinOp(op, box lhs.clone(), box rhs.clone()))
     }
 
+    fn transform_unary_op(
+        &mut self,
+        op: UnaryOperator,
+        value: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::UnaryOp(op, box value.clone()))
+    }
+
+    fn transform_cast(&mut self, value: &ir::Node, ty: &ir::TypeExpr) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Cast(box value.clone(), ty.clone()))
+    }
+
+    fn transform_ascribe(
+        &mut self,
+        value: &ir::Node,
+        ty: &ir::TypeExpr,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Ascribe(box value.clone(), ty.clone()))
+    }
+
     fn transform_ifelse(
         &mut self,
         cond: &ir::Node,
@@ -110,4 +314,169 @@ pub trait Transform {
             box else_.clone(),
         ))
     }
+
+    fn transform_match(
+        &mut self,
+        value: &ir::Node,
+        arms: &[ir::MatchArm],
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Match(box value.clone(), arms.to_vec()))
+    }
+
+    fn transform_while(&mut self, cond: &ir::Node, body: &ir::Node) -> Result<ir::Value, Error> {
+        Ok(ir::Value::While(box cond.clone(), box body.clone()))
+    }
+
+    fn transform_break(&mut self, value: Option<&ir::Node>) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Break(value.map(|v| box v.clone())))
+    }
+
+    fn transform_continue(&mut self) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Continue)
+    }
+
+    fn transform_throw(&mut self, value: &ir::Node) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Throw(box value.clone()))
+    }
+
+    fn transform_try(
+        &mut self,
+        body: &ir::Node,
+        ident: &str,
+        handler: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Try(
+            box body.clone(),
+            ident.to_string(),
+            box handler.clone(),
+        ))
+    }
+
+    fn transform_array(&mut self, elems: &[ir::Node]) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Array(elems.to_vec()))
+    }
+
+    fn transform_tuple(&mut self, elems: &[ir::Node]) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Tuple(elems.to_vec()))
+    }
+
+    fn transform_tuple_index(
+        &mut self,
+        tuple: &ir::Node,
+        index: usize,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::TupleIndex(box tuple.clone(), index))
+    }
+
+    fn transform_range(
+        &mut self,
+        start: &ir::Node,
+        end: &ir::Node,
+        kind: RangeKind,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Range(box start.clone(), box end.clone(), kind))
+    }
+
+    fn transform_define_type(
+        &mut self,
+        data_type: &ir::DataType,
+        body: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::DefineType(data_type.clone(), box body.clone()))
+    }
+
+    fn transform_construct(&mut self, name: &str, args: &[ir::Node]) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Construct(name.to_string(), args.to_vec()))
+    }
+
+    fn transform_call_builtin(
+        &mut self,
+        builtin: ir::Builtin,
+        args: &[ir::Node],
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::CallBuiltin(builtin, args.to_vec()))
+    }
+
+    fn transform_call_extern(
+        &mut self,
+        name: &str,
+        ty: &ir::TypeExpr,
+        args: &[ir::Node],
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::CallExtern(
+            name.to_string(),
+            ty.clone(),
+            args.to_vec(),
+        ))
+    }
+
+    fn transform_define_struct(
+        &mut self,
+        struct_type: &ir::StructType,
+        body: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::DefineStruct(
+            struct_type.clone(),
+            box body.clone(),
+        ))
+    }
+
+    fn transform_define_alias(
+        &mut self,
+        alias: &ir::TypeAlias,
+        body: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::DefineAlias(alias.clone(), box body.clone()))
+    }
+
+    fn transform_define_class(
+        &mut self,
+        class: &ir::Class,
+        body: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::DefineClass(class.clone(), box body.clone()))
+    }
+
+    fn transform_define_instance(
+        &mut self,
+        instance: &ir::Instance,
+        body: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::DefineInstance(
+            instance.clone(),
+            box body.clone(),
+        ))
+    }
+
+    fn transform_define_exception(
+        &mut self,
+        ty: &ir::TypeExpr,
+        body: &ir::Node,
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::DefineException(ty.clone(), box body.clone()))
+    }
+
+    fn transform_method(&mut self, name: &str, visible: usize) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Method(name.to_string(), visible))
+    }
+
+    fn transform_struct(
+        &mut self,
+        name: &str,
+        fields: &[(ir::Identifier, ir::Node)],
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Struct(name.to_string(), fields.to_vec()))
+    }
+
+    fn transform_field(&mut self, value: &ir::Node, field: &str) -> Result<ir::Value, Error> {
+        Ok(ir::Value::Field(box value.clone(), field.to_string()))
+    }
+
+    fn transform_struct_update(
+        &mut self,
+        value: &ir::Node,
+        fields: &[(ir::Identifier, ir::Node)],
+    ) -> Result<ir::Value, Error> {
+        Ok(ir::Value::StructUpdate(box value.clone(), fields.to_vec()))
+    }
 }
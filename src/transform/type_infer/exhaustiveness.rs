@@ -0,0 +1,302 @@
+//
+// Exhaustiveness and reachability checking of match expressions, based on the usefulness
+// algorithm described in "Warnings for pattern matching" (Luc Maranget, 2007).
+//
+
+use crate::ir::{Pattern, Type, TypeDefs};
+use crate::transform::error::TypeInferError;
+
+use failure::Error;
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Ctor {
+    Number(i64),
+    Boolean(bool),
+    Tuple(usize),
+    /// Constructor of a data type, with its tag and arity.
+    Data(usize, usize),
+}
+
+impl Ctor {
+    fn arity(&self) -> usize {
+        match self {
+            Ctor::Tuple(arity) | Ctor::Data(_, arity) => *arity,
+            Ctor::Number(_) | Ctor::Boolean(_) => 0,
+        }
+    }
+}
+
+struct Checker<'a> {
+    type_defs: &'a TypeDefs,
+}
+
+impl<'a> Checker<'a> {
+    fn head_ctor(&self, pattern: &Pattern) -> Result<Option<Ctor>, Error> {
+        Ok(match pattern {
+            Pattern::Wildcard | Pattern::Variable(_) => None,
+            Pattern::Number(number) => Some(Ctor::Number(*number)),
+            Pattern::Boolean(tf) => Some(Ctor::Boolean(*tf)),
+            Pattern::Tuple(elems) => Some(Ctor::Tuple(elems.len())),
+            Pattern::Constructor(name, args) => {
+                let (_, tag) = self.type_defs.find_constructor(name).ok_or_else(|| {
+                    TypeInferError::UndeclaredIdentifier {
+                        ident: name.clone(),
+                    }
+                })?;
+                Some(Ctor::Data(tag, args.len()))
+            }
+        })
+    }
+
+    /// All constructors of `ty`, or `None` if they can't be enumerated.
+    fn all_ctors(&self, ty: &Type) -> Option<Vec<Ctor>> {
+        match ty {
+            Type::Boolean => Some(vec![Ctor::Boolean(true), Ctor::Boolean(false)]),
+            Type::Tuple(elems) => Some(vec![Ctor::Tuple(elems.len())]),
+            Type::Data(name, _) => self.type_defs.data_type(name).map(|data_type| {
+                data_type
+                    .constructors
+                    .iter()
+                    .enumerate()
+                    .map(|(tag, ctor)| Ctor::Data(tag, ctor.fields.len()))
+                    .collect()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Types of the arguments of `ctor` whose type is `ty`.
+    fn arg_types(&self, ty: &Type, ctor: &Ctor) -> Result<Vec<Type>, Error> {
+        Ok(match (ty, ctor) {
+            (Type::Tuple(elems), Ctor::Tuple(_)) => elems.clone(),
+            (Type::Data(name, args), Ctor::Data(tag, _)) => match self.type_defs.data_type(name) {
+                Some(data_type) => {
+                    let vars: HashMap<_, _> = data_type
+                        .params
+                        .iter()
+                        .cloned()
+                        .zip(args.iter().cloned())
+                        .collect();
+                    data_type.constructors[*tag]
+                        .fields
+                        .iter()
+                        .map(|field| self.type_defs.resolve(field, &vars))
+                        .collect::<Result<_, _>>()?
+                }
+                None => vec![Type::Empty; ctor.arity()],
+            },
+            // The type is unknown; arguments are treated as values of an opaque type
+            _ => vec![Type::Empty; ctor.arity()],
+        })
+    }
+
+    /// Specialize `row` by `ctor`, or `None` if the head of `row` doesn't match `ctor`.
+    fn specialize(&self, row: &[Pattern], ctor: &Ctor) -> Result<Option<Vec<Pattern>>, Error> {
+        let mut new_row = match &row[0] {
+            Pattern::Wildcard | Pattern::Variable(_) => vec![Pattern::Wildcard; ctor.arity()],
+            head if self.head_ctor(head)?.as_ref() == Some(ctor) => match head {
+                Pattern::Tuple(args) | Pattern::Constructor(_, args) => args.clone(),
+                _ => Vec::new(),
+            },
+            _ => return Ok(None),
+        };
+        new_row.extend_from_slice(&row[1..]);
+        Ok(Some(new_row))
+    }
+
+    fn useful_specialized(
+        &self,
+        matrix: &[Vec<Pattern>],
+        row: &[Pattern],
+        tys: &[Type],
+        ctor: &Ctor,
+    ) -> Result<bool, Error> {
+        let mut new_matrix = Vec::new();
+        for r in matrix {
+            if let Some(r) = self.specialize(r, ctor)? {
+                new_matrix.push(r);
+            }
+        }
+        let new_row = self.specialize(row, ctor)?.unwrap();
+        let mut new_tys = self.arg_types(&tys[0], ctor)?;
+        new_tys.extend_from_slice(&tys[1..]);
+        self.useful(&new_matrix, &new_row, &new_tys)
+    }
+
+    /// Whether `row` matches some value which is not matched by any row of `matrix`.
+    fn useful(
+        &self,
+        matrix: &[Vec<Pattern>],
+        row: &[Pattern],
+        tys: &[Type],
+    ) -> Result<bool, Error> {
+        if row.is_empty() {
+            return Ok(matrix.is_empty());
+        }
+
+        if let Some(ctor) = self.head_ctor(&row[0])? {
+            return self.useful_specialized(matrix, row, tys, &ctor);
+        }
+
+        let mut heads = Vec::new();
+        for r in matrix {
+            if let Some(ctor) = self.head_ctor(&r[0])? {
+                if !heads.contains(&ctor) {
+                    heads.push(ctor);
+                }
+            }
+        }
+
+        match self.all_ctors(&tys[0]) {
+            Some(ref all) if all.iter().all(|ctor| heads.contains(ctor)) => {
+                for ctor in all {
+                    if self.useful_specialized(matrix, row, tys, ctor)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            _ => {
+                let mut default = Vec::new();
+                for r in matrix {
+                    if self.head_ctor(&r[0])?.is_none() {
+                        default.push(r[1..].to_vec());
+                    }
+                }
+                self.useful(&default, &row[1..], &tys[1..])
+            }
+        }
+    }
+}
+
+/// Check that the arms cover every value of `ty` and that every arm is reachable.
+/// Each arm is given as its pattern and whether it has a guard.
+pub fn check_match(
+    type_defs: &TypeDefs,
+    ty: &Type,
+    arms: &[(&Pattern, bool)],
+) -> Result<(), Error> {
+    let checker = Checker { type_defs };
+    let tys = [ty.clone()];
+
+    let mut matrix = Vec::new();
+    for (pattern, guarded) in arms {
+        let row = vec![(*pattern).clone()];
+        if !checker.useful(&matrix, &row, &tys)? {
+            return Err(TypeInferError::UnreachablePattern {
+                pattern: pattern.to_string(),
+            }
+            .into());
+        }
+        // Arms with a guard may not match, so they don't cover the values
+        if !guarded {
+            matrix.push(row);
+        }
+    }
+
+    if checker.useful(&matrix, &[Pattern::Wildcard], &tys)? {
+        return Err(TypeInferError::NonExhaustiveMatch.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_match;
+    use crate::ir::{Constructor, DataType, Pattern, Type, TypeDefs, TypeExpr};
+
+    fn option_defs() -> TypeDefs {
+        let mut type_defs = TypeDefs::new();
+        type_defs
+            .define_data_type(DataType {
+                name: "Option".to_string(),
+                params: vec!["a".to_string()],
+                constructors: vec![
+                    Constructor {
+                        name: "Some".to_string(),
+                        fields: vec![TypeExpr::Variable("a".to_string())],
+                    },
+                    Constructor {
+                        name: "None".to_string(),
+                        fields: vec![],
+                    },
+                ],
+            })
+            .unwrap();
+        type_defs
+    }
+
+    fn option(ty: Type) -> Type {
+        Type::Data("Option".to_string(), vec![ty])
+    }
+
+    fn some(p: Pattern) -> Pattern {
+        Pattern::Constructor("Some".to_string(), vec![p])
+    }
+
+    fn none() -> Pattern {
+        Pattern::Constructor("None".to_string(), vec![])
+    }
+
+    #[test]
+    fn boolean() {
+        let (t, f) = (Pattern::Boolean(true), Pattern::Boolean(false));
+        let defs = TypeDefs::new();
+        assert!(check_match(&defs, &Type::Boolean, &[(&t, false), (&f, false)]).is_ok());
+        assert!(check_match(&defs, &Type::Boolean, &[(&t, false)]).is_err());
+    }
+
+    #[test]
+    fn number_requires_wildcard() {
+        let (zero, x) = (Pattern::Number(0), Pattern::Variable("x".to_string()));
+        let defs = TypeDefs::new();
+        assert!(check_match(&defs, &Type::Number, &[(&zero, false)]).is_err());
+        assert!(check_match(&defs, &Type::Number, &[(&zero, false), (&x, false)]).is_ok());
+    }
+
+    #[test]
+    fn unreachable() {
+        let (x, zero) = (Pattern::Variable("x".to_string()), Pattern::Number(0));
+        let defs = TypeDefs::new();
+        assert!(check_match(&defs, &Type::Number, &[(&x, false), (&zero, false)]).is_err());
+    }
+
+    #[test]
+    fn guard() {
+        let (x, y) = (
+            Pattern::Variable("x".to_string()),
+            Pattern::Variable("y".to_string()),
+        );
+        let defs = TypeDefs::new();
+        assert!(check_match(&defs, &Type::Number, &[(&x, true)]).is_err());
+        assert!(check_match(&defs, &Type::Number, &[(&x, true), (&y, false)]).is_ok());
+    }
+
+    #[test]
+    fn constructor() {
+        let defs = option_defs();
+        let ty = option(Type::Number);
+        let (s, n) = (some(Pattern::Wildcard), none());
+        assert!(check_match(&defs, &ty, &[(&s, false), (&n, false)]).is_ok());
+        assert!(check_match(&defs, &ty, &[(&s, false)]).is_err());
+    }
+
+    #[test]
+    fn nested() {
+        let defs = option_defs();
+        let ty = Type::Tuple(vec![option(Type::Boolean), Type::Boolean]);
+        let p1 = Pattern::Tuple(vec![some(Pattern::Boolean(true)), Pattern::Wildcard]);
+        let p2 = Pattern::Tuple(vec![some(Pattern::Boolean(false)), Pattern::Boolean(true)]);
+        let p3 = Pattern::Tuple(vec![none(), Pattern::Wildcard]);
+        let p4 = Pattern::Tuple(vec![Pattern::Wildcard, Pattern::Boolean(false)]);
+        assert!(check_match(&defs, &ty, &[(&p1, false), (&p2, false), (&p3, false)]).is_err());
+        assert!(check_match(
+            &defs,
+            &ty,
+            &[(&p1, false), (&p2, false), (&p3, false), (&p4, false)]
+        )
+        .is_ok());
+    }
+}
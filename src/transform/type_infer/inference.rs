@@ -8,12 +8,14 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 //
 
-use crate::expression::Operator;
+use crate::error::TranslationError;
+use crate::expression::{Operator, UnaryOperator};
 use crate::ir;
-use crate::ir::type_::Type;
+use crate::ir::type_::{Type, TypeVarID, BUILTIN_STRUCTS};
 use crate::transform::error::TypeInferError;
 use crate::transform::Transform;
 
+use super::exhaustiveness;
 use super::poly_type::PolyType;
 use super::subst::Subst;
 use super::traits::{Types, Unify};
@@ -22,10 +24,39 @@ use super::type_var_gen::TypeVarGen;
 
 use failure::Error;
 
+use std::collections::HashMap;
+use std::mem;
+
+/// The loop which `break` and `continue` refer to
+struct LoopContext {
+    /// The type of values given to `break`
+    break_ty: Type,
+    /// Whether the loop is exited with `break` with a value
+    has_value: bool,
+}
+
 #[derive(Default)]
 pub struct TypeInfer {
     tvg: TypeVarGen,
     instantiation_table: Vec<(Type, Subst)>,
+    /// Types of the other functions defined in the same `let rec` group, keyed by the type of
+    /// each function in the group
+    rec_groups: Vec<(Type, Vec<Type>)>,
+    loops: Vec<LoopContext>,
+    type_defs: ir::TypeDefs,
+    classes: HashMap<ir::Identifier, ir::Class>,
+    /// The class, the variable of the class parameter and the type of each method
+    methods: HashMap<ir::Identifier, (ir::Identifier, TypeVarID, Type)>,
+    /// Classes and types of the instances in the order of declaration
+    instances: Vec<(ir::Identifier, Type)>,
+    /// Classes which the types are required to be instances of by annotations
+    constraints: Vec<(ir::Identifier, Type)>,
+    /// Type of the values thrown in the program, which is fixed by the declaration or the first
+    /// `throw` or `try`
+    exception: Option<Type>,
+    /// Number literals without suffixes and their types, which are resolved once the whole
+    /// program is inferred
+    literals: Vec<(i64, Type)>,
 }
 
 impl TypeInfer {
@@ -33,9 +64,345 @@ impl TypeInfer {
         Self {
             tvg: TypeVarGen::new(),
             instantiation_table: Vec::new(),
+            rec_groups: Vec::new(),
+            loops: Vec::new(),
+            type_defs: ir::TypeDefs::new(),
+            classes: HashMap::new(),
+            methods: HashMap::new(),
+            instances: Vec::new(),
+            constraints: Vec::new(),
+            exception: None,
+            literals: Vec::new(),
+        }
+    }
+
+    /// Type of the thrown values, which is `String` unless declared with `exception`.
+    fn exception_type(&mut self) -> Type {
+        self.exception.get_or_insert(Type::String).clone()
+    }
+
+    /// Generalize `ty` in `env` except for the types of number literals, which are fixed by
+    /// the uses after the binding.
+    fn generalize(&self, env: &TypeEnv, ty: &Type, subst: &Subst) -> PolyType {
+        let mut tp = env.generalize(ty);
+        let literal_vars = self
+            .literals
+            .iter()
+            .map(|(_, t)| t.apply(subst))
+            .collect::<Vec<_>>()
+            .ftv();
+        tp.vars.retain(|var| !literal_vars.contains(var));
+        tp
+    }
+
+    /// Whether `ty` is the type of a number literal which is not determined yet.
+    fn is_literal_type(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Variable(_) => self.literals.iter().any(|(_, t)| t == ty),
+            _ => false,
+        }
+    }
+
+    /// Whether `ident` refers to a method of a class in `env`.
+    fn is_method(&self, env: &TypeEnv, ident: &str) -> bool {
+        match (self.methods.get(ident), env.get(ident)) {
+            (Some((_, _, method_ty)), Some(s)) => &s.ty == method_ty,
+            _ => false,
+        }
+    }
+
+    /// Replace a reference to a method with the implementation in the instance for `ty`, once
+    /// the type is determined.
+    fn resolve_method(&self, value: ir::Value, ty: &Type) -> Result<ir::Value, Error> {
+        let (name, visible) = match &value {
+            ir::Value::Method(name, visible) if ty.ftv().is_empty() => (name, *visible),
+            _ => return Ok(value),
+        };
+        let (class, param, method_ty) = &self.methods[name];
+        let instance_ty = Type::Variable(*param).apply(&method_ty.mgu(ty)?);
+        if self.instances[..visible].contains(&(class.clone(), instance_ty.clone())) {
+            Ok(ir::Value::Variable(method_binding(
+                class,
+                &instance_ty,
+                name,
+            )))
+        } else {
+            Err(TypeInferError::NoInstance {
+                class: class.clone(),
+                ty: instance_ty,
+            }
+            .into())
         }
     }
 
+    /// Check that the types constrained in the annotations are instances of the classes, as
+    /// far as they are determined by `subst`.
+    fn check_constraints(&self, subst: &Subst) -> Result<(), Error> {
+        for (class, ty) in &self.constraints {
+            let ty = ty.apply(subst);
+            if ty.ftv().is_empty() && !self.instances.contains(&(class.clone(), ty.clone())) {
+                return Err(TypeInferError::NoInstance {
+                    class: class.clone(),
+                    ty,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    fn fresh_params(&mut self, params: &[ir::Identifier]) -> HashMap<ir::Identifier, Type> {
+        params
+            .iter()
+            .map(|p| (p.clone(), self.tvg.new_variable()))
+            .collect()
+    }
+
+    /// Determine the struct type of a value with `ty`, from which `field` is accessed.
+    fn struct_instance(
+        &mut self,
+        ty: &Type,
+        field: &str,
+    ) -> Result<(Subst, ir::StructType, HashMap<ir::Identifier, Type>), Error> {
+        match ty {
+            Type::Data(name, args) => match self.type_defs.struct_type(name) {
+                Some(struct_type) => {
+                    let vars = struct_type
+                        .params
+                        .iter()
+                        .cloned()
+                        .zip(args.iter().cloned())
+                        .collect();
+                    Ok((Subst::new(), struct_type.clone(), vars))
+                }
+                None => Err(TypeInferError::NotStruct { ty: ty.clone() }.into()),
+            },
+            Type::Variable(_) => {
+                let struct_type = match self.type_defs.find_struct_with_field(field).as_slice() {
+                    [struct_type] => (*struct_type).clone(),
+                    _ => {
+                        return Err(TypeInferError::AmbiguousField {
+                            field: field.to_string(),
+                        }
+                        .into());
+                    }
+                };
+                let vars = self.fresh_params(&struct_type.params);
+                let instance = Type::Data(
+                    struct_type.name.clone(),
+                    struct_type.params.iter().map(|p| vars[p].clone()).collect(),
+                );
+                let s = ty.mgu(&instance)?;
+                Ok((s, struct_type, vars))
+            }
+            _ => Err(TypeInferError::NotStruct { ty: ty.clone() }.into()),
+        }
+    }
+
+    /// Infer the types of `fields` and unify them with the declared ones.
+    fn transform_fields(
+        &mut self,
+        struct_type: &ir::StructType,
+        vars: &HashMap<ir::Identifier, Type>,
+        fields: &[(ir::Identifier, ir::Node)],
+        env: &TypeEnv,
+        mut subst: Subst,
+    ) -> Result<(Subst, Vec<(ir::Identifier, ir::Node)>), Error> {
+        let mut new_fields = Vec::new();
+        for (i, (field, value)) in fields.iter().enumerate() {
+            if fields[..i].iter().any(|(f, _)| f == field) {
+                return Err(TypeInferError::DuplicateField {
+                    field: field.clone(),
+                }
+                .into());
+            }
+            let (_, decl) =
+                struct_type
+                    .field(field)
+                    .ok_or_else(|| TypeInferError::UnknownField {
+                        ty: struct_type.name.clone(),
+                        field: field.clone(),
+                    })?;
+            let field_ty = self.type_defs.resolve(&decl.ty, vars)?;
+
+            let (s, v) = self.transform_with_env(value, &mut env.apply(&subst))?;
+            subst = s.compose(&subst);
+            let value_ty = v.type_().unwrap();
+            let s_field = field_ty.apply(&subst).mgu(&value_ty.apply(&subst))?;
+            subst = s_field.compose(&subst);
+            new_fields.push((field.clone(), v));
+        }
+        Ok((subst, new_fields))
+    }
+
+    /// Infer the type of `pattern`, collecting the variables bound by it into `bindings`.
+    /// Number literals are typed with fresh variables collected into `literals`, which are
+    /// fixed by `resolve_literals` once the type of the matched value is known.
+    fn infer_pattern(
+        &mut self,
+        pattern: &ir::Pattern,
+        bindings: &mut Vec<(ir::Identifier, Type)>,
+        literals: &mut Vec<(i64, Type)>,
+    ) -> Result<(Subst, Type, ir::Pattern), Error> {
+        Ok(match pattern {
+            ir::Pattern::Wildcard => (Subst::new(), self.tvg.new_variable(), pattern.clone()),
+            ir::Pattern::Number(number) => {
+                let tv = self.tvg.new_variable();
+                literals.push((*number, tv.clone()));
+                (Subst::new(), tv, pattern.clone())
+            }
+            ir::Pattern::Boolean(_) => (Subst::new(), Type::Boolean, pattern.clone()),
+            ir::Pattern::Variable(name) => {
+                if bindings.iter().any(|(n, _)| n == name) {
+                    return Err(TypeInferError::DuplicatePatternVariable {
+                        ident: name.clone(),
+                    }
+                    .into());
+                }
+                let tv = self.tvg.new_variable();
+                bindings.push((name.clone(), tv.clone()));
+                (Subst::new(), tv, pattern.clone())
+            }
+            ir::Pattern::Tuple(elems) => {
+                let mut subst = Subst::new();
+                let mut tys = Vec::new();
+                let mut new_elems = Vec::new();
+                for elem in elems {
+                    let (s, t, p) = self.infer_pattern(elem, bindings, literals)?;
+                    subst = s.compose(&subst);
+                    tys.push(t);
+                    new_elems.push(p);
+                }
+
+                let ty = Type::Tuple(tys.iter().map(|t| t.apply(&subst)).collect());
+                (subst, ty, ir::Pattern::Tuple(new_elems))
+            }
+            ir::Pattern::Constructor(name, args) => {
+                let (data_type, tag) = self
+                    .type_defs
+                    .find_constructor(name)
+                    .map(|(data_type, tag)| (data_type.clone(), tag))
+                    .ok_or_else(|| TypeInferError::UndeclaredIdentifier {
+                        ident: name.clone(),
+                    })?;
+                let fields = &data_type.constructors[tag].fields;
+                if fields.len() != args.len() {
+                    return Err(TypeInferError::PatternArityMismatch {
+                        name: name.clone(),
+                        expected: fields.len(),
+                        found: args.len(),
+                    }
+                    .into());
+                }
+
+                let vars = self.fresh_params(&data_type.params);
+                let ty = Type::Data(
+                    data_type.name.clone(),
+                    data_type.params.iter().map(|p| vars[p].clone()).collect(),
+                );
+
+                let mut subst = Subst::new();
+                let mut new_args = Vec::new();
+                for (arg, field) in args.iter().zip(fields) {
+                    let field_ty = self.type_defs.resolve(field, &vars)?;
+                    let (s, t, p) = self.infer_pattern(arg, bindings, literals)?;
+                    subst = s.compose(&subst);
+                    let s_arg = field_ty.apply(&subst).mgu(&t.apply(&subst))?;
+                    subst = s_arg.compose(&subst);
+                    new_args.push(p);
+                }
+
+                let ty = ty.apply(&subst);
+                (subst, ty, ir::Pattern::Constructor(name.clone(), new_args))
+            }
+        })
+    }
+
+    /// Fix the types of the number literals, which are sized integers if they are inferred to
+    /// be one of them and `Number` otherwise.
+    fn resolve_literals(
+        &mut self,
+        literals: &[(i64, Type)],
+        subst: &Subst,
+    ) -> Result<Subst, Error> {
+        let mut s = Subst::new();
+        for (number, ty) in literals {
+            match ty.apply(subst).apply(&s) {
+                Type::Int(t) if !t.contains(i128::from(*number)) => {
+                    return Err(TranslationError::IntegerOutOfRange {
+                        value: i128::from(*number),
+                        ty: Type::Int(t),
+                    }
+                    .into());
+                }
+                Type::Int(_) => {}
+                ty => s = ty.mgu(&Type::Number)?.compose(&s),
+            }
+        }
+        Ok(s)
+    }
+
+    /// Infer the type of the result of `op`, unifying the operands with the types it accepts.
+    fn infer_binop(
+        &mut self,
+        op: Operator,
+        lhs_ty: &Type,
+        rhs_ty: &Type,
+    ) -> Result<(Subst, Type), Error> {
+        if let Operator::Index = op {
+            let (base_ty, elem_ty) = match lhs_ty {
+                Type::String => (Type::String, Type::Int(ir::IntType::U8)),
+                _ => {
+                    let tv = self.tvg.new_variable();
+                    (Type::Array(box tv.clone()), tv)
+                }
+            };
+            let s = unify_operands(lhs_ty, &base_ty, rhs_ty, &Type::Number)?;
+            return Ok((s.clone(), elem_ty.apply(&s)));
+        }
+
+        // The operands are numbers unless the type of either of them is known otherwise
+        let known_ty = match lhs_ty {
+            Type::Variable(_) => rhs_ty,
+            _ => lhs_ty,
+        };
+        let is_comparison = match op {
+            Operator::Lt
+            | Operator::Gt
+            | Operator::Le
+            | Operator::Ge
+            | Operator::Eq
+            | Operator::Ne => true,
+            _ => false,
+        };
+        let operand_ty = match (known_ty, op) {
+            (_, Operator::And) | (_, Operator::Or) => Type::Boolean,
+            (Type::Boolean, Operator::BitAnd)
+            | (Type::Boolean, Operator::BitXor)
+            | (Type::Boolean, Operator::BitOr)
+            | (Type::Boolean, Operator::Eq)
+            | (Type::Boolean, Operator::Ne) => Type::Boolean,
+            (Type::String, Operator::Add) => Type::String,
+            (Type::Float, Operator::Add)
+            | (Type::Float, Operator::Sub)
+            | (Type::Float, Operator::Mul)
+            | (Type::Float, Operator::Div) => Type::Float,
+            (Type::String, _) | (Type::Char, _) | (Type::Float, _) if is_comparison => {
+                known_ty.clone()
+            }
+            (Type::Int(_), _) => known_ty.clone(),
+            _ => Type::Number,
+        };
+
+        let s = unify_operands(lhs_ty, &operand_ty, rhs_ty, &operand_ty)?;
+        let ty = if is_comparison {
+            Type::Boolean
+        } else {
+            operand_ty
+        };
+        Ok((s, ty))
+    }
+
     fn transform_with_env(
         &mut self,
         eir: &ir::Node,
@@ -58,7 +425,11 @@ impl TypeInfer {
                             ty: tv.clone(),
                         },
                     );
-                    let (s1, v) = self.transform_with_env(body, &mut new_env)?;
+                    // `break` and `continue` cannot escape from the function
+                    let loops = mem::replace(&mut self.loops, Vec::new());
+                    let result = self.transform_with_env(body, &mut new_env);
+                    self.loops = loops;
+                    let (s1, v) = result?;
                     let t1 = v.type_().unwrap();
                     let new_type = Type::Function(box tv.apply(&s1), box t1.clone());
                     let lit =
@@ -66,16 +437,41 @@ impl TypeInfer {
                     let new_node = ir::Value::Literal(lit);
                     Ok((s1.clone(), new_node.typed_node(new_type)))
                 }
-                ir::Literal::Number(_) => Ok((Subst::new(), eir.clone().with_type(Type::Number)?)),
+                ir::Literal::Number(number) => {
+                    let tv = self.tvg.new_variable();
+                    self.literals.push((*number, tv.clone()));
+                    Ok((Subst::new(), eir.clone().with_type(tv)?))
+                }
+                ir::Literal::Int(_, t) => Ok((Subst::new(), eir.clone().with_type(Type::Int(*t))?)),
+                ir::Literal::Float(_) => Ok((Subst::new(), eir.clone().with_type(Type::Float)?)),
                 ir::Literal::Boolean(_) => {
                     Ok((Subst::new(), eir.clone().with_type(Type::Boolean)?))
                 }
+                ir::Literal::String(_) => Ok((Subst::new(), eir.clone().with_type(Type::String)?)),
+                ir::Literal::Char(_) => Ok((Subst::new(), eir.clone().with_type(Type::Char)?)),
                 ir::Literal::Empty => Ok((Subst::new(), eir.clone().with_type(Type::Empty)?)),
             },
             ir::Value::Variable(ident) => match env.get(ident) {
                 Some(s) => {
                     let (subst, instance) = s.instantiate(&mut self.tvg);
-                    self.instantiation_table.push((s.ty.clone(), subst));
+                    if self.is_method(env, ident) {
+                        // Resolved to the implementation once the type of the use is determined
+                        let new_node = ir::Value::Method(ident.clone(), self.instances.len());
+                        return Ok((Subst::new(), new_node.typed_node(instance)));
+                    }
+                    if !s.vars.is_empty() {
+                        // Functions in the same recursive group are instantiated together
+                        let siblings = self
+                            .rec_groups
+                            .iter()
+                            .filter(|(ty, _)| ty == &s.ty)
+                            .flat_map(|(_, siblings)| siblings.clone())
+                            .collect::<Vec<_>>();
+                        for sibling in siblings {
+                            self.instantiation_table.push((sibling, subst.clone()));
+                        }
+                        self.instantiation_table.push((s.ty.clone(), subst));
+                    }
                     Ok((Subst::new(), eir.clone().with_type(instance)?))
                 }
                 None => Err(TypeInferError::UndeclaredIdentifier {
@@ -104,7 +500,7 @@ impl TypeInfer {
                 let (s1, v1) = self.transform_with_env(value, env)?;
                 let t1 = v1.type_().unwrap();
 
-                let tp = env.apply(&s1).generalize(&t1);
+                let tp = self.generalize(&env.apply(&s1), &t1, &s1);
                 env.insert(ident.clone(), tp);
 
                 let (s2, v2) = self.transform_with_env(&body, &mut env.apply(&s1))?;
@@ -113,6 +509,69 @@ impl TypeInfer {
                 let new_node = ir::Value::Let(*kind, ident.clone(), box v1.clone(), box v2.clone());
                 Ok((s2.compose(&s1), new_node.typed_node(t2.clone())))
             }
+            ir::Value::LetTuple(kind, idents, box value, box body) => {
+                let (s1, v1) = self.transform_with_env(value, env)?;
+                let t1 = v1.type_().unwrap();
+
+                let tvs: Vec<_> = idents.iter().map(|_| self.tvg.new_variable()).collect();
+                let s2 = t1.mgu(&Type::Tuple(tvs.clone()))?;
+                let s = s2.compose(&s1);
+
+                let applied_env = env.apply(&s);
+                for (ident, tv) in idents.iter().zip(&tvs) {
+                    let tp = self.generalize(&applied_env, &tv.apply(&s), &s);
+                    env.insert(ident.clone(), tp);
+                }
+
+                let (s3, v2) = self.transform_with_env(&body, &mut env.apply(&s))?;
+                let t2 = v2.type_().unwrap();
+
+                let new_node =
+                    ir::Value::LetTuple(*kind, idents.clone(), box v1.clone(), box v2.clone());
+                Ok((s3.compose(&s), new_node.typed_node(t2.clone())))
+            }
+            ir::Value::LetRec(bindings, box body) => {
+                // The functions are monomorphic in their own definitions
+                let tvs: Vec<_> = bindings.iter().map(|_| self.tvg.new_variable()).collect();
+                let mut rec_env = env.clone();
+                for ((ident, _), tv) in bindings.iter().zip(&tvs) {
+                    let tp = PolyType {
+                        vars: Vec::new(),
+                        ty: tv.clone(),
+                    };
+                    rec_env.insert(ident.clone(), tp);
+                }
+
+                let mut subst = Subst::new();
+                let mut new_bindings = Vec::new();
+                for ((ident, value), tv) in bindings.iter().zip(&tvs) {
+                    let (s1, v) = self.transform_with_env(value, &mut rec_env.apply(&subst))?;
+                    subst = s1.compose(&subst);
+                    let s2 = tv.apply(&subst).mgu(&v.type_().unwrap().apply(&subst))?;
+                    subst = s2.compose(&subst);
+                    new_bindings.push((ident.clone(), v));
+                }
+
+                let types: Vec<_> = tvs.iter().map(|tv| tv.apply(&subst)).collect();
+                let applied_env = env.apply(&subst);
+                for ((ident, _), ty) in bindings.iter().zip(&types) {
+                    let tp = self.generalize(&applied_env, ty, &subst);
+                    env.insert(ident.clone(), tp);
+                }
+                if types.len() > 1 {
+                    for (i, ty) in types.iter().enumerate() {
+                        let mut siblings = types.clone();
+                        siblings.remove(i);
+                        self.rec_groups.push((ty.clone(), siblings));
+                    }
+                }
+
+                let (s3, v2) = self.transform_with_env(body, &mut env.apply(&subst))?;
+                let t2 = v2.type_().unwrap();
+
+                let new_node = ir::Value::LetRec(new_bindings, box v2.clone());
+                Ok((s3.compose(&subst), new_node.typed_node(t2.clone())))
+            }
             ir::Value::Follow(box lhs, box rhs) => {
                 let (s1, v1) = self.transform_with_env(lhs, env)?;
                 let (s2, v2) = self.transform_with_env(rhs, env)?;
@@ -123,35 +582,109 @@ impl TypeInfer {
             }
             ir::Value::BinOp(op, box lhs, box rhs) => {
                 let (s1, lhs) = self.transform_with_env(&lhs, env)?;
-                let lhs_ty = lhs.type_().unwrap();
                 let (s2, rhs) = self.transform_with_env(&rhs, env)?;
-                let rhs_ty = rhs.type_().unwrap();
+                let subst = s2.compose(&s1);
+                let lhs_ty = lhs.type_().unwrap().apply(&subst);
+                let rhs_ty = rhs.type_().unwrap().apply(&subst);
 
-                let new_node = ir::Value::BinOp(*op, box lhs.clone(), box rhs.clone());
-                Ok(match op {
-                    Operator::Index => unimplemented!(),
-                    Operator::Lt
-                    | Operator::Gt
-                    | Operator::Le
-                    | Operator::Ge
-                    | Operator::Eq
-                    | Operator::Ne => {
-                        let sl = lhs_ty.mgu(&Type::Number)?;
-                        let sr = rhs_ty.mgu(&Type::Number)?;
-                        (
-                            s1.compose(&s2.compose(&sl.compose(&sr))),
-                            new_node.typed_node(Type::Boolean),
-                        )
+                // Operators on the other types than the builtin ones are methods of classes.
+                // The method is also used if the type is not known yet, which is turned back
+                // into the builtin operator in the specialization if it is a builtin type.
+                let known_ty = match lhs_ty {
+                    Type::Variable(_) => &rhs_ty,
+                    _ => &lhs_ty,
+                };
+                if !is_builtin_operator(*op, known_ty)
+                    && !self.is_literal_type(known_ty)
+                    && self.is_method(env, op.symbol())
+                {
+                    let method = ir::Value::Variable(op.symbol().to_string()).untyped_node();
+                    let lhs = ir::Node::new(lhs.value().clone(), lhs_ty, HashMap::new());
+                    let rhs = ir::Node::new(rhs.value().clone(), rhs_ty, HashMap::new());
+                    let apply = ir::Value::Apply(
+                        box ir::Value::Apply(box method, box lhs).untyped_node(),
+                        box rhs,
+                    );
+                    let (s3, v) = self.transform_with_env(&apply.untyped_node(), env)?;
+                    return Ok((s3.compose(&subst), v));
+                }
+
+                let (s3, ty) = self.infer_binop(*op, &lhs_ty, &rhs_ty)?;
+
+                let new_node = ir::Value::BinOp(*op, box lhs, box rhs);
+                Ok((s3.compose(&subst), new_node.typed_node(ty)))
+            }
+            ir::Value::UnaryOp(op, box value) => {
+                let (s1, v) = self.transform_with_env(value, env)?;
+                let operand_ty = v.type_().unwrap().apply(&s1);
+                let ty = match (op, &operand_ty) {
+                    (UnaryOperator::Not, _) => Type::Boolean,
+                    (UnaryOperator::Neg, Type::Float) => Type::Float,
+                    (_, Type::Int(t)) => Type::Int(*t),
+                    _ => Type::Number,
+                };
+                let s2 = operand_ty.mgu(&ty)?;
+
+                let new_node = ir::Value::UnaryOp(*op, box v);
+                Ok((s2.compose(&s1), new_node.typed_node(ty)))
+            }
+            ir::Value::Cast(box value, ty) => {
+                let (s1, v) = self.transform_with_env(value, env)?;
+                let to = self.type_defs.resolve(ty, &HashMap::new())?;
+                let from = v.type_().unwrap().apply(&s1);
+                // A value whose type is not known yet is cast only to its own type, except for
+                // number literals which are cast from `Number`
+                let s2 = match (&from, &to) {
+                    _ if self.is_literal_type(&from) && is_castable(&Type::Number, &to) => {
+                        from.mgu(&Type::Number)?
                     }
-                    _ => {
-                        let sl = lhs_ty.mgu(&Type::Number)?;
-                        let sr = rhs_ty.mgu(&Type::Number)?;
-                        (
-                            s1.compose(&s2.compose(&sl.compose(&sr))),
-                            new_node.typed_node(Type::Number),
-                        )
+                    (Type::Variable(_), _) => from.mgu(&to)?,
+                    _ if is_castable(&from, &to) => Subst::new(),
+                    _ => return Err(TypeInferError::InvalidCast { from, to }.into()),
+                };
+
+                let new_node = ir::Value::Cast(box v, ty.clone());
+                Ok((s2.compose(&s1), new_node.typed_node(to)))
+            }
+            ir::Value::Ascribe(box value, ty) => {
+                let (s1, v) = self.transform_with_env(value, env)?;
+                let vars = self.fresh_params(&ty.free_vars());
+                let declared = self.type_defs.resolve(ty, &vars)?;
+                let found = v.type_().unwrap().apply(&s1);
+                let s2 = found.mgu(&declared)?;
+
+                // Type variables in the annotation must not be fixed by the value
+                let mut instances: Vec<Type> = Vec::new();
+                for name in ty.free_vars() {
+                    let instance = vars[&name].apply(&s2);
+                    match instance {
+                        Type::Variable(_) if !instances.contains(&instance) => {
+                            instances.push(instance)
+                        }
+                        _ => {
+                            return Err(TypeInferError::NotPolymorphic {
+                                var: name,
+                                ty: instance,
+                            }
+                            .into());
+                        }
                     }
-                })
+                }
+
+                if let ir::TypeExpr::Constrained(constraints, _) = ty {
+                    for (class, var) in constraints {
+                        if !self.classes.contains_key(class) {
+                            return Err(TypeInferError::UndeclaredClass {
+                                name: class.clone(),
+                            }
+                            .into());
+                        }
+                        self.constraints.push((class.clone(), vars[var].apply(&s2)));
+                    }
+                }
+
+                let new_node = ir::Value::Ascribe(box v, ty.clone());
+                Ok((s2.compose(&s1), new_node.typed_node(declared.apply(&s2))))
             }
             ir::Value::IfElse(box cond, box then_body, box else_body) => {
                 let (s1, cond_v) = self.transform_with_env(&cond, env)?;
@@ -171,6 +704,562 @@ impl TypeInfer {
                     new_node.typed_node(then_ty.clone()),
                 ))
             }
+            ir::Value::Match(box value, arms) => {
+                let (mut subst, v) = self.transform_with_env(value, env)?;
+                let value_ty = v.type_().unwrap().clone();
+                let result_ty = self.tvg.new_variable();
+
+                let mut new_arms = Vec::new();
+                for arm in arms {
+                    let mut bindings = Vec::new();
+                    let mut literals = Vec::new();
+                    let (s, pattern_ty, pattern) =
+                        self.infer_pattern(&arm.pattern, &mut bindings, &mut literals)?;
+                    subst = s.compose(&subst);
+                    let s_pattern = value_ty.apply(&subst).mgu(&pattern_ty.apply(&subst))?;
+                    subst = s_pattern.compose(&subst);
+                    let s_literals = self.resolve_literals(&literals, &subst)?;
+                    subst = s_literals.compose(&subst);
+
+                    let mut arm_env = env.apply(&subst);
+                    for (ident, ty) in bindings {
+                        arm_env.insert(
+                            ident,
+                            PolyType {
+                                vars: Vec::new(),
+                                ty: ty.apply(&subst),
+                            },
+                        );
+                    }
+
+                    let guard = match &arm.guard {
+                        Some(guard) => {
+                            let (s, g) = self.transform_with_env(guard, &mut arm_env)?;
+                            subst = s.compose(&subst);
+                            let s_guard = g.type_().unwrap().apply(&subst).mgu(&Type::Boolean)?;
+                            subst = s_guard.compose(&subst);
+                            Some(g)
+                        }
+                        None => None,
+                    };
+
+                    let (s, body) =
+                        self.transform_with_env(&arm.body, &mut arm_env.apply(&subst))?;
+                    subst = s.compose(&subst);
+                    let body_ty = body.type_().unwrap();
+                    let s_body = result_ty.apply(&subst).mgu(&body_ty.apply(&subst))?;
+                    subst = s_body.compose(&subst);
+
+                    new_arms.push(ir::MatchArm {
+                        pattern,
+                        guard,
+                        body,
+                    });
+                }
+
+                let patterns: Vec<_> = new_arms
+                    .iter()
+                    .map(|arm| (&arm.pattern, arm.guard.is_some()))
+                    .collect();
+                exhaustiveness::check_match(&self.type_defs, &value_ty.apply(&subst), &patterns)?;
+
+                let ty = result_ty.apply(&subst);
+                let new_node = ir::Value::Match(box v, new_arms);
+                Ok((subst, new_node.typed_node(ty)))
+            }
+            ir::Value::While(box cond, box body) => {
+                let (s1, cond_v) = self.transform_with_env(cond, env)?;
+                let s_cond = cond_v.type_().unwrap().apply(&s1).mgu(&Type::Boolean)?;
+                let subst = s_cond.compose(&s1);
+
+                self.loops.push(LoopContext {
+                    break_ty: self.tvg.new_variable(),
+                    has_value: false,
+                });
+                let result = self.transform_with_env(body, &mut env.apply(&subst));
+                let context = self.loops.pop().unwrap();
+                let (s2, body_v) = result?;
+                let subst = s2.compose(&subst);
+
+                let ty = if context.has_value {
+                    match cond_v.value() {
+                        ir::Value::Literal(ir::Literal::Boolean(true)) => {
+                            context.break_ty.apply(&subst)
+                        }
+                        _ => return Err(TypeInferError::ConditionalLoopValue.into()),
+                    }
+                } else {
+                    Type::Empty
+                };
+
+                let new_node = ir::Value::While(box cond_v, box body_v);
+                Ok((subst, new_node.typed_node(ty)))
+            }
+            ir::Value::Break(value) => {
+                let (subst, new_value, value_ty) = match value {
+                    Some(box value) => {
+                        let (s, v) = self.transform_with_env(value, env)?;
+                        let ty = v.type_().unwrap().apply(&s);
+                        (s, Some(box v), ty)
+                    }
+                    None => (Subst::new(), None, Type::Empty),
+                };
+
+                let has_value = new_value.is_some();
+                let context = self
+                    .loops
+                    .last_mut()
+                    .ok_or(TypeInferError::BreakOutsideLoop)?;
+                let s_break = context.break_ty.apply(&subst).mgu(&value_ty)?;
+                let subst = s_break.compose(&subst);
+                context.break_ty = value_ty.apply(&subst);
+                context.has_value |= has_value;
+
+                // `break` never produces its value in place
+                let new_node = ir::Value::Break(new_value);
+                Ok((subst, new_node.typed_node(self.tvg.new_variable())))
+            }
+            ir::Value::Continue => {
+                if self.loops.is_empty() {
+                    return Err(TypeInferError::ContinueOutsideLoop.into());
+                }
+                Ok((
+                    Subst::new(),
+                    ir::Value::Continue.typed_node(self.tvg.new_variable()),
+                ))
+            }
+            ir::Value::Throw(box value) => {
+                let (s1, v) = self.transform_with_env(value, env)?;
+                let exception_ty = self.exception_type();
+                let s2 = v.type_().unwrap().apply(&s1).mgu(&exception_ty)?;
+
+                // `throw` never produces its value in place
+                let new_node = ir::Value::Throw(box v);
+                Ok((
+                    s2.compose(&s1),
+                    new_node.typed_node(self.tvg.new_variable()),
+                ))
+            }
+            ir::Value::Try(box body, ident, box handler) => {
+                // `break` and `continue` cannot escape from the body, in which the handler is set
+                let loops = mem::replace(&mut self.loops, Vec::new());
+                let result = self.transform_with_env(body, env);
+                self.loops = loops;
+                let (s1, body_v) = result?;
+
+                let mut handler_env = env.apply(&s1);
+                handler_env.insert(
+                    ident.clone(),
+                    PolyType {
+                        vars: Vec::new(),
+                        ty: self.exception_type(),
+                    },
+                );
+                let (s2, handler_v) = self.transform_with_env(handler, &mut handler_env)?;
+                let subst = s2.compose(&s1);
+
+                let body_ty = body_v.type_().unwrap().apply(&subst);
+                let s3 = body_ty.mgu(&handler_v.type_().unwrap().apply(&subst))?;
+                let subst = s3.compose(&subst);
+
+                let ty = body_ty.apply(&subst);
+                let new_node = ir::Value::Try(box body_v, ident.clone(), box handler_v);
+                Ok((subst, new_node.typed_node(ty)))
+            }
+            ir::Value::Array(elems) => {
+                let tv = self.tvg.new_variable();
+                let mut subst = Subst::new();
+                let mut new_elems = Vec::new();
+                for elem in elems {
+                    let (s, v) = self.transform_with_env(elem, &mut env.apply(&subst))?;
+                    subst = s.compose(&subst);
+                    let elem_ty = v.type_().unwrap();
+                    let s_elem = tv.apply(&subst).mgu(&elem_ty.apply(&subst))?;
+                    subst = s_elem.compose(&subst);
+                    new_elems.push(v);
+                }
+
+                let ty = Type::Array(box tv.apply(&subst));
+                let new_node = ir::Value::Array(new_elems);
+                Ok((subst, new_node.typed_node(ty)))
+            }
+            ir::Value::Tuple(elems) => {
+                let mut subst = Subst::new();
+                let mut new_elems = Vec::new();
+                for elem in elems {
+                    let (s, v) = self.transform_with_env(elem, &mut env.apply(&subst))?;
+                    subst = s.compose(&subst);
+                    new_elems.push(v);
+                }
+
+                let ty = Type::Tuple(
+                    new_elems
+                        .iter()
+                        .map(|v| v.type_().unwrap().apply(&subst))
+                        .collect(),
+                );
+                let new_node = ir::Value::Tuple(new_elems);
+                Ok((subst, new_node.typed_node(ty)))
+            }
+            ir::Value::TupleIndex(box tuple, index) => {
+                let (s, v) = self.transform_with_env(tuple, env)?;
+                let ty = match v.type_().unwrap().apply(&s) {
+                    Type::Tuple(elems) => match elems.get(*index) {
+                        Some(t) => t.clone(),
+                        None => {
+                            return Err(TypeInferError::TupleIndexOutOfRange {
+                                index: *index,
+                                ty: Type::Tuple(elems),
+                            }
+                            .into());
+                        }
+                    },
+                    ty => return Err(TypeInferError::NotTuple { ty }.into()),
+                };
+
+                let new_node = ir::Value::TupleIndex(box v, *index);
+                Ok((s, new_node.typed_node(ty)))
+            }
+            ir::Value::Range(box start, box end, kind) => {
+                let (s1, start) = self.transform_with_env(start, env)?;
+                let (s2, end) = self.transform_with_env(end, &mut env.apply(&s1))?;
+                let subst = s2.compose(&s1);
+                let s_start = start.type_().unwrap().apply(&subst).mgu(&Type::Number)?;
+                let subst = s_start.compose(&subst);
+                let s_end = end.type_().unwrap().apply(&subst).mgu(&Type::Number)?;
+
+                let new_node = ir::Value::Range(box start, box end, *kind);
+                Ok((s_end.compose(&subst), new_node.typed_node(Type::Range)))
+            }
+            ir::Value::DefineType(data_type, box body) => {
+                self.type_defs.define_data_type(data_type.clone())?;
+                let vars = self.fresh_params(&data_type.params);
+                for ctor in &data_type.constructors {
+                    for field in &ctor.fields {
+                        self.type_defs.resolve(field, &vars)?;
+                    }
+                }
+
+                let (s, v) = self.transform_with_env(body, env)?;
+                let t = v.type_().unwrap().clone();
+
+                let new_node = ir::Value::DefineType(data_type.clone(), box v);
+                Ok((s, new_node.typed_node(t)))
+            }
+            ir::Value::Construct(name, args) => {
+                let (data_type, tag) = self
+                    .type_defs
+                    .find_constructor(name)
+                    .map(|(data_type, tag)| (data_type.clone(), tag))
+                    .ok_or_else(|| TypeInferError::UndeclaredIdentifier {
+                        ident: name.clone(),
+                    })?;
+
+                let vars = self.fresh_params(&data_type.params);
+                let ty = Type::Data(
+                    data_type.name.clone(),
+                    data_type.params.iter().map(|p| vars[p].clone()).collect(),
+                );
+
+                let mut subst = Subst::new();
+                let mut new_args = Vec::new();
+                for (arg, field) in args.iter().zip(&data_type.constructors[tag].fields) {
+                    let field_ty = self.type_defs.resolve(field, &vars)?;
+                    let (s, v) = self.transform_with_env(arg, &mut env.apply(&subst))?;
+                    subst = s.compose(&subst);
+                    let arg_ty = v.type_().unwrap();
+                    let s_arg = field_ty.apply(&subst).mgu(&arg_ty.apply(&subst))?;
+                    subst = s_arg.compose(&subst);
+                    new_args.push(v);
+                }
+
+                let ty = ty.apply(&subst);
+                let new_node = ir::Value::Construct(name.clone(), new_args);
+                Ok((subst, new_node.typed_node(ty)))
+            }
+            ir::Value::CallBuiltin(builtin, args) => {
+                let (param_types, ty) = builtin.signature();
+
+                let mut subst = Subst::new();
+                let mut new_args = Vec::new();
+                for (arg, param_ty) in args.iter().zip(&param_types) {
+                    let (s, v) = self.transform_with_env(arg, &mut env.apply(&subst))?;
+                    subst = s.compose(&subst);
+                    if let Some(param_ty) = param_ty {
+                        let s_arg = v.type_().unwrap().apply(&subst).mgu(param_ty)?;
+                        subst = s_arg.compose(&subst);
+                    }
+                    new_args.push(v);
+                }
+
+                let new_node = ir::Value::CallBuiltin(*builtin, new_args);
+                Ok((subst, new_node.typed_node(ty)))
+            }
+            ir::Value::CallExtern(name, ty_expr, args) => {
+                let vars = self.fresh_params(&ty_expr.free_vars());
+                let mut ty = self.type_defs.resolve(ty_expr, &vars)?;
+
+                let mut subst = Subst::new();
+                let mut new_args = Vec::new();
+                for arg in args {
+                    let (param_ty, ret_ty) = match ty {
+                        Type::Function(box param_ty, box ret_ty) => (param_ty, ret_ty),
+                        ty => {
+                            return Err(TypeInferError::InvalidExternType {
+                                name: name.clone(),
+                                ty,
+                            }
+                            .into())
+                        }
+                    };
+                    if !is_c_type(&param_ty) {
+                        return Err(TypeInferError::InvalidExternType {
+                            name: name.clone(),
+                            ty: param_ty,
+                        }
+                        .into());
+                    }
+
+                    let (s, v) = self.transform_with_env(arg, &mut env.apply(&subst))?;
+                    subst = s.compose(&subst);
+                    let s_arg = v.type_().unwrap().apply(&subst).mgu(&param_ty)?;
+                    subst = s_arg.compose(&subst);
+                    new_args.push(v);
+                    ty = ret_ty;
+                }
+                if !is_c_type(&ty) {
+                    return Err(TypeInferError::InvalidExternType {
+                        name: name.clone(),
+                        ty,
+                    }
+                    .into());
+                }
+
+                let new_node = ir::Value::CallExtern(name.clone(), ty_expr.clone(), new_args);
+                Ok((subst, new_node.typed_node(ty)))
+            }
+            ir::Value::DefineStruct(struct_type, box body) => {
+                self.type_defs.define_struct_type(struct_type.clone());
+                let vars = self.fresh_params(&struct_type.params);
+                for field in &struct_type.fields {
+                    self.type_defs.resolve(&field.ty, &vars)?;
+                }
+
+                let (s, v) = self.transform_with_env(body, env)?;
+                let t = v.type_().unwrap().clone();
+
+                let new_node = ir::Value::DefineStruct(struct_type.clone(), box v);
+                Ok((s, new_node.typed_node(t)))
+            }
+            ir::Value::DefineAlias(alias, box body) => {
+                self.type_defs.define_alias(alias.clone());
+                let vars = self.fresh_params(&alias.params);
+                self.type_defs.resolve(&alias.ty, &vars)?;
+
+                let (s, v) = self.transform_with_env(body, env)?;
+                let t = v.type_().unwrap().clone();
+
+                let new_node = ir::Value::DefineAlias(alias.clone(), box v);
+                Ok((s, new_node.typed_node(t)))
+            }
+            ir::Value::DefineException(ty_expr, box body) => {
+                let ty = self.type_defs.resolve(ty_expr, &HashMap::new())?;
+                match &self.exception {
+                    Some(used) if used != &ty => {
+                        return Err(TypeInferError::ConflictingException {
+                            declared: ty,
+                            used: used.clone(),
+                        }
+                        .into());
+                    }
+                    _ => self.exception = Some(ty),
+                }
+
+                let (s, v) = self.transform_with_env(body, env)?;
+                let t = v.type_().unwrap().clone();
+
+                let new_node = ir::Value::DefineException(ty_expr.clone(), box v);
+                Ok((s, new_node.typed_node(t)))
+            }
+            ir::Value::DefineClass(class, box body) => {
+                let param = self.tvg.next_id();
+                let mut vars = HashMap::new();
+                vars.insert(class.param.clone(), Type::Variable(param));
+                for (name, ty) in &class.methods {
+                    if ty.free_vars() != vec![class.param.clone()] {
+                        return Err(TypeInferError::InvalidMethodType {
+                            method: name.clone(),
+                            param: class.param.clone(),
+                        }
+                        .into());
+                    }
+                    let method_ty = self.type_defs.resolve(ty, &vars)?;
+                    self.methods
+                        .insert(name.clone(), (class.name.clone(), param, method_ty.clone()));
+                    env.insert(
+                        name.clone(),
+                        PolyType {
+                            vars: vec![param],
+                            ty: method_ty,
+                        },
+                    );
+                }
+                self.classes.insert(class.name.clone(), class.clone());
+
+                let (s, v) = self.transform_with_env(body, env)?;
+                let t = v.type_().unwrap().clone();
+
+                let new_node = ir::Value::DefineClass(class.clone(), box v);
+                Ok((s, new_node.typed_node(t)))
+            }
+            ir::Value::DefineInstance(instance, box body) => {
+                let class = self.classes.get(&instance.class).cloned().ok_or_else(|| {
+                    TypeInferError::UndeclaredClass {
+                        name: instance.class.clone(),
+                    }
+                })?;
+                if !instance.ty.free_vars().is_empty() {
+                    return Err(TypeInferError::PolymorphicInstance {
+                        class: class.name.clone(),
+                        ty: instance.ty.to_string(),
+                    }
+                    .into());
+                }
+                let ty = self.type_defs.resolve(&instance.ty, &HashMap::new())?;
+                if self.instances.contains(&(class.name.clone(), ty.clone())) {
+                    return Err(TypeInferError::DuplicateInstance {
+                        class: class.name.clone(),
+                        ty,
+                    }
+                    .into());
+                }
+                if let Some((missing, _)) = class
+                    .methods
+                    .iter()
+                    .find(|(method, _)| instance.methods.iter().all(|(name, _)| name != method))
+                {
+                    return Err(TypeInferError::MissingMethod {
+                        class: class.name.clone(),
+                        method: missing.clone(),
+                    }
+                    .into());
+                }
+                // Declared before the methods, which may use the instance recursively
+                self.instances.push((class.name.clone(), ty.clone()));
+
+                let mut subst = Subst::new();
+                let mut bindings = Vec::new();
+                for (name, value) in &instance.methods {
+                    let method_ty = class
+                        .method(name)
+                        .ok_or_else(|| TypeInferError::UnknownMethod {
+                            class: class.name.clone(),
+                            method: name.clone(),
+                        })?
+                        .substitute(&class.param, &instance.ty);
+                    let expected = self.type_defs.resolve(&method_ty, &HashMap::new())?;
+                    let value = annotate_params(value, &method_ty);
+                    let (s, v) = self.transform_with_env(&value, &mut env.apply(&subst))?;
+                    subst = s.compose(&subst);
+                    let s_method = v.type_().unwrap().apply(&subst).mgu(&expected)?;
+                    subst = s_method.compose(&subst);
+                    bindings.push((method_binding(&class.name, &ty, name), v));
+                }
+
+                let (s, v) = self.transform_with_env(body, &mut env.apply(&subst))?;
+                let subst = s.compose(&subst);
+                let t = v.type_().unwrap().clone();
+
+                // The methods are bound with the names specific to the instance
+                let new_node = ir::Value::LetRec(bindings, box v);
+                Ok((subst, new_node.typed_node(t)))
+            }
+            ir::Value::Method(..) => unreachable!(),
+            ir::Value::Struct(name, fields) => {
+                let struct_type = self.type_defs.struct_type(name).cloned().ok_or_else(|| {
+                    TypeInferError::UndeclaredIdentifier {
+                        ident: name.clone(),
+                    }
+                })?;
+                if let Some(missing) = struct_type
+                    .fields
+                    .iter()
+                    .find(|decl| fields.iter().all(|(field, _)| field != &decl.name))
+                {
+                    return Err(TypeInferError::MissingField {
+                        ty: name.clone(),
+                        field: missing.name.clone(),
+                    }
+                    .into());
+                }
+
+                let vars = self.fresh_params(&struct_type.params);
+                let ty = Type::Data(
+                    name.clone(),
+                    struct_type.params.iter().map(|p| vars[p].clone()).collect(),
+                );
+
+                let (subst, new_fields) =
+                    self.transform_fields(&struct_type, &vars, fields, env, Subst::new())?;
+
+                let new_node = ir::Value::Struct(name.clone(), new_fields);
+                Ok((subst.clone(), new_node.typed_node(ty.apply(&subst))))
+            }
+            ir::Value::Field(box value, field) => {
+                let (s1, v) = self.transform_with_env(value, env)?;
+                let value_ty = v.type_().unwrap().apply(&s1);
+                let s1 = match value_ty {
+                    // Fields of builtin types are taken over by user-defined structs
+                    Type::Variable(_)
+                        if self.type_defs.find_struct_with_field(field).is_empty() =>
+                    {
+                        let builtins: Vec<_> = BUILTIN_STRUCTS
+                            .iter()
+                            .filter(|ty| ty.builtin_field(field).is_some())
+                            .collect();
+                        match builtins.as_slice() {
+                            [builtin] => value_ty.mgu(builtin)?.compose(&s1),
+                            _ => s1,
+                        }
+                    }
+                    _ => s1,
+                };
+                let value_ty = value_ty.apply(&s1);
+                if BUILTIN_STRUCTS.contains(&value_ty) || value_ty.builtin_field(field).is_some() {
+                    let (_, ty) = value_ty.builtin_field(field).ok_or_else(|| {
+                        TypeInferError::UnknownField {
+                            ty: value_ty.to_string(),
+                            field: field.clone(),
+                        }
+                    })?;
+                    let new_node = ir::Value::Field(box v, field.clone());
+                    return Ok((s1, new_node.typed_node(ty)));
+                }
+
+                let (s2, struct_type, vars) = self.struct_instance(&value_ty, field)?;
+                let (_, decl) =
+                    struct_type
+                        .field(field)
+                        .ok_or_else(|| TypeInferError::UnknownField {
+                            ty: value_ty.to_string(),
+                            field: field.clone(),
+                        })?;
+                let ty = self.type_defs.resolve(&decl.ty, &vars)?.apply(&s2);
+
+                let new_node = ir::Value::Field(box v, field.clone());
+                Ok((s2.compose(&s1), new_node.typed_node(ty)))
+            }
+            ir::Value::StructUpdate(box value, fields) => {
+                let (s1, v) = self.transform_with_env(value, env)?;
+                let value_ty = v.type_().unwrap().apply(&s1);
+                let (s2, struct_type, vars) = self.struct_instance(&value_ty, &fields[0].0)?;
+
+                let (subst, new_fields) =
+                    self.transform_fields(&struct_type, &vars, fields, env, s2.compose(&s1))?;
+
+                let new_node = ir::Value::StructUpdate(box v, new_fields);
+                Ok((subst.clone(), new_node.typed_node(value_ty.apply(&subst))))
+            }
             ir::Value::Assign(box lhs, box rhs) => {
                 let (s1, lhs) = self.transform_with_env(&lhs, env)?;
                 let lhs_ty = lhs.type_().unwrap();
@@ -200,13 +1289,26 @@ impl TypeInfer {
                 }
                 _ => value.clone(),
             },
-            ir::Value::Variable(..) => value.clone(),
+            ir::Value::Variable(..) | ir::Value::Method(..) => value.clone(),
             ir::Value::Let(kind, ident, box value, box body) => ir::Value::Let(
                 *kind,
                 ident.clone(),
                 self.apply_subst_all(value, subst)?,
                 self.apply_subst_all(body, subst)?,
             ),
+            ir::Value::LetTuple(kind, idents, box value, box body) => ir::Value::LetTuple(
+                *kind,
+                idents.clone(),
+                self.apply_subst_all(value, subst)?,
+                self.apply_subst_all(body, subst)?,
+            ),
+            ir::Value::LetRec(bindings, box body) => ir::Value::LetRec(
+                bindings
+                    .iter()
+                    .map(|(ident, value)| Ok((ident.clone(), *self.apply_subst_all(value, subst)?)))
+                    .collect::<Result<_, Error>>()?,
+                self.apply_subst_all(body, subst)?,
+            ),
             ir::Value::Assign(box lhs, box rhs) => ir::Value::Assign(
                 self.apply_subst_all(lhs, subst)?,
                 self.apply_subst_all(rhs, subst)?,
@@ -215,43 +1317,195 @@ impl TypeInfer {
                 self.apply_subst_all(lhs, subst)?,
                 self.apply_subst_all(rhs, subst)?,
             ),
-            ir::Value::Apply(box lhs, box rhs) => ir::Value::Apply(
-                self.apply_subst_all(lhs, subst)?,
-                self.apply_subst_all(rhs, subst)?,
-            ),
+            ir::Value::Apply(box lhs, box rhs) => {
+                match self.resolve_operator_method(lhs, rhs, subst)? {
+                    Some(binop) => binop,
+                    None => ir::Value::Apply(
+                        self.apply_subst_all(lhs, subst)?,
+                        self.apply_subst_all(rhs, subst)?,
+                    ),
+                }
+            }
             ir::Value::BinOp(op, box lhs, box rhs) => ir::Value::BinOp(
                 *op,
                 self.apply_subst_all(lhs, subst)?,
                 self.apply_subst_all(rhs, subst)?,
             ),
+            ir::Value::UnaryOp(op, box value) => {
+                ir::Value::UnaryOp(*op, self.apply_subst_all(value, subst)?)
+            }
+            ir::Value::Cast(box value, ty) => {
+                ir::Value::Cast(self.apply_subst_all(value, subst)?, ty.clone())
+            }
+            ir::Value::Ascribe(box value, ty) => {
+                ir::Value::Ascribe(self.apply_subst_all(value, subst)?, ty.clone())
+            }
             ir::Value::IfElse(box cond, box then_v, box else_v) => ir::Value::IfElse(
                 self.apply_subst_all(cond, subst)?,
                 self.apply_subst_all(then_v, subst)?,
                 self.apply_subst_all(else_v, subst)?,
             ),
+            ir::Value::Match(box value, arms) => ir::Value::Match(
+                self.apply_subst_all(value, subst)?,
+                arms.iter()
+                    .map(|arm| {
+                        Ok(ir::MatchArm {
+                            pattern: arm.pattern.clone(),
+                            guard: match &arm.guard {
+                                Some(guard) => Some(*self.apply_subst_all(guard, subst)?),
+                                None => None,
+                            },
+                            body: *self.apply_subst_all(&arm.body, subst)?,
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?,
+            ),
+            ir::Value::While(box cond, box body) => ir::Value::While(
+                self.apply_subst_all(cond, subst)?,
+                self.apply_subst_all(body, subst)?,
+            ),
+            ir::Value::Break(Some(box value)) => {
+                ir::Value::Break(Some(self.apply_subst_all(value, subst)?))
+            }
+            ir::Value::Break(None) => ir::Value::Break(None),
+            ir::Value::Continue => ir::Value::Continue,
+            ir::Value::Throw(box value) => ir::Value::Throw(self.apply_subst_all(value, subst)?),
+            ir::Value::Try(box body, ident, box handler) => ir::Value::Try(
+                self.apply_subst_all(body, subst)?,
+                ident.clone(),
+                self.apply_subst_all(handler, subst)?,
+            ),
+            ir::Value::Array(elems) => ir::Value::Array(
+                elems
+                    .iter()
+                    .map(|e| self.apply_subst_all(e, subst).map(|e| *e))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ir::Value::Tuple(elems) => ir::Value::Tuple(
+                elems
+                    .iter()
+                    .map(|e| self.apply_subst_all(e, subst).map(|e| *e))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ir::Value::TupleIndex(box tuple, index) => {
+                ir::Value::TupleIndex(self.apply_subst_all(tuple, subst)?, *index)
+            }
+            ir::Value::Range(box start, box end, kind) => ir::Value::Range(
+                self.apply_subst_all(start, subst)?,
+                self.apply_subst_all(end, subst)?,
+                *kind,
+            ),
+            ir::Value::DefineType(data_type, box body) => {
+                ir::Value::DefineType(data_type.clone(), self.apply_subst_all(body, subst)?)
+            }
+            ir::Value::Construct(name, args) => ir::Value::Construct(
+                name.clone(),
+                args.iter()
+                    .map(|e| self.apply_subst_all(e, subst).map(|e| *e))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ir::Value::CallBuiltin(builtin, args) => ir::Value::CallBuiltin(
+                *builtin,
+                args.iter()
+                    .map(|e| self.apply_subst_all(e, subst).map(|e| *e))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ir::Value::CallExtern(name, ty, args) => ir::Value::CallExtern(
+                name.clone(),
+                ty.clone(),
+                args.iter()
+                    .map(|e| self.apply_subst_all(e, subst).map(|e| *e))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ir::Value::DefineStruct(struct_type, box body) => {
+                ir::Value::DefineStruct(struct_type.clone(), self.apply_subst_all(body, subst)?)
+            }
+            ir::Value::DefineAlias(alias, box body) => {
+                ir::Value::DefineAlias(alias.clone(), self.apply_subst_all(body, subst)?)
+            }
+            ir::Value::DefineClass(class, box body) => {
+                ir::Value::DefineClass(class.clone(), self.apply_subst_all(body, subst)?)
+            }
+            ir::Value::DefineException(ty, box body) => {
+                ir::Value::DefineException(ty.clone(), self.apply_subst_all(body, subst)?)
+            }
+            // Instances are replaced with the bindings of the methods in the inference
+            ir::Value::DefineInstance(..) => unreachable!(),
+            ir::Value::Struct(name, fields) => ir::Value::Struct(
+                name.clone(),
+                fields
+                    .iter()
+                    .map(|(field, e)| Ok((field.clone(), *self.apply_subst_all(e, subst)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
+            ir::Value::Field(box value, field) => {
+                ir::Value::Field(self.apply_subst_all(value, subst)?, field.clone())
+            }
+            ir::Value::StructUpdate(box value, fields) => ir::Value::StructUpdate(
+                self.apply_subst_all(value, subst)?,
+                fields
+                    .iter()
+                    .map(|(field, e)| Ok((field.clone(), *self.apply_subst_all(e, subst)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
         })
     }
 
+    /// Turn the call of the method for an operator on the operands whose type was not known
+    /// back into the builtin operator, if the type turns out to be a builtin one.
+    fn resolve_operator_method(
+        &self,
+        f: &ir::Node,
+        rhs: &ir::Node,
+        subst: &Subst,
+    ) -> Result<Option<ir::Value>, Error> {
+        let (method, lhs) = match f.value() {
+            ir::Value::Apply(box method, box lhs) => (method, lhs),
+            _ => return Ok(None),
+        };
+        let op = match method.value() {
+            ir::Value::Method(name, _) => match name.parse::<Operator>() {
+                Ok(op) => op,
+                Err(_) => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let lhs_ty = lhs.type_().ok_or(TypeInferError::NotTyped)?.apply(subst);
+        if !is_builtin_operator(op, &lhs_ty) {
+            return Ok(None);
+        }
+        Ok(Some(ir::Value::BinOp(
+            op,
+            self.apply_subst_all(lhs, subst)?,
+            self.apply_subst_all(rhs, subst)?,
+        )))
+    }
+
     fn apply_subst_all(&self, eir: &ir::Node, subst: &Subst) -> Result<Box<ir::Node>, Error> {
         let ty = eir.type_().ok_or(TypeInferError::NotTyped)?;
         let value = eir.value();
 
         let new_ty = ty.apply(subst);
+        // A method may have the same type as the function using it, but it is resolved in each
+        // instance of the function instead
+        let is_method = match value {
+            ir::Value::Method(..) => true,
+            _ => false,
+        };
         let instantiation_table = self
             .instantiation_table
             .iter()
-            .filter_map(|(k, v)| {
-                if k == &new_ty {
-                    let c = subst.compose(v).remove_indirection();
-                    let instance_value = self.inner_apply_subst_all(value, &c).unwrap();
-                    let applied_ty = ty.apply(&c);
-                    Some((applied_ty.clone(), instance_value.typed_node(applied_ty)))
-                } else {
-                    None
-                }
+            .filter(|(k, _)| !is_method && k == &new_ty)
+            .map(|(_, v)| {
+                let c = subst.compose(v).remove_indirection();
+                self.check_constraints(&c)?;
+                let applied_ty = ty.apply(&c);
+                let instance_value =
+                    self.resolve_method(self.inner_apply_subst_all(value, &c)?, &applied_ty)?;
+                Ok((applied_ty.clone(), instance_value.typed_node(applied_ty)))
             })
-            .collect();
-        let new_v = self.inner_apply_subst_all(value, subst)?;
+            .collect::<Result<_, Error>>()?;
+        let new_v = self.resolve_method(self.inner_apply_subst_all(value, subst)?, &new_ty)?;
         Ok(box ir::Node::new(new_v, new_ty, instantiation_table))
     }
 }
@@ -259,7 +1513,122 @@ impl TypeInfer {
 impl Transform for TypeInfer {
     fn transform(&mut self, eir: &ir::Node) -> Result<ir::Node, Error> {
         let (subst, v) = self.transform_with_env(eir, &mut TypeEnv::new())?;
+        let literals = mem::replace(&mut self.literals, Vec::new());
+        let subst = self.resolve_literals(&literals, &subst)?.compose(&subst);
+        self.check_constraints(&subst)?;
         let box v = self.apply_subst_all(&v, &subst)?;
         Ok(v)
     }
 }
+
+/// Annotate the parameters of the function `func` with the types in `ty` as `(x: T) -> ...`
+/// does, so that they are known in the body.
+fn annotate_params(func: &ir::Node, ty: &ir::TypeExpr) -> ir::Node {
+    match (func.value(), ty) {
+        (
+            ir::Value::Literal(ir::Literal::Function(param, box body, captures)),
+            ir::TypeExpr::Function(box param_ty, box ret_ty),
+        ) => {
+            let var = ir::Value::Variable(param.clone()).untyped_node();
+            let check = ir::Value::Ascribe(box var, param_ty.clone()).untyped_node();
+            let body = ir::Value::Let(
+                ir::BindingKind::Immutable,
+                param.clone(),
+                box check,
+                box annotate_params(body, ret_ty),
+            );
+            let lit =
+                ir::Literal::Function(param.clone(), box body.untyped_node(), captures.clone());
+            ir::Value::Literal(lit).untyped_node()
+        }
+        _ => func.clone(),
+    }
+}
+
+/// Whether `op` on the operands of `ty` is the builtin one rather than a method of a class.
+fn is_builtin_operator(op: Operator, ty: &Type) -> bool {
+    match (ty, op) {
+        (_, Operator::And) | (_, Operator::Or) | (_, Operator::Index) => true,
+        (Type::Data(..), _)
+        | (Type::Tuple(_), _)
+        | (Type::Array(_), _)
+        | (Type::Function(..), _)
+        | (Type::Variable(_), _) => false,
+        _ => true,
+    }
+}
+
+/// Name of the binding of the implementation of `method` in the instance of `class` for `ty`.
+fn method_binding(class: &str, ty: &Type, method: &str) -> ir::Identifier {
+    format!("_{}_{}_{}", class, ty, method)
+}
+
+/// Unify `lhs` with `lhs_expected` and `rhs` with `rhs_expected`.
+fn unify_operands(
+    lhs: &Type,
+    lhs_expected: &Type,
+    rhs: &Type,
+    rhs_expected: &Type,
+) -> Result<Subst, Error> {
+    let sl = lhs.mgu(lhs_expected)?;
+    let sr = rhs.apply(&sl).mgu(&rhs_expected.apply(&sl))?;
+    Ok(sr.compose(&sl))
+}
+
+/// Whether values of `ty` can be passed to and returned from C functions as they are.
+fn is_c_type(ty: &Type) -> bool {
+    match ty {
+        Type::Number | Type::Int(_) | Type::Float | Type::Boolean | Type::Char | Type::Empty => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Whether a value of type `from` can be converted to `to` with `as`.
+fn is_castable(from: &Type, to: &Type) -> bool {
+    match (from, to) {
+        (Type::Number, Type::Boolean)
+        | (Type::Boolean, Type::Number)
+        | (Type::Number, Type::Char)
+        | (Type::Char, Type::Number)
+        | (Type::Number, Type::Float)
+        | (Type::Float, Type::Number) => true,
+        // Sized integers are converted from and to any type represented with a number
+        (Type::Int(_), other) | (other, Type::Int(_)) => match other {
+            Type::Number | Type::Int(_) | Type::Float | Type::Boolean | Type::Char => true,
+            _ => false,
+        },
+        _ => from == to,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TypeInfer;
+    use crate::parser::parse;
+    use crate::transform::Transform;
+    use crate::translator::translate_ast;
+
+    fn infer(source: &str) -> String {
+        let eir = translate_ast(parse(source).unwrap()).unwrap();
+        let eir = TypeInfer::new().transform(&eir).unwrap();
+        eir.type_().unwrap().to_string()
+    }
+
+    #[test]
+    fn cast_applies_both_substitutions() {
+        // The type of `f` is bound while inferring the value, and its result while casting it
+        assert_eq!(
+            infer("f -> f(1) as Number"),
+            "((Number -> Number) -> Number)"
+        );
+    }
+
+    #[test]
+    fn literal_takes_sized_type() {
+        assert_eq!(infer("(x: U8) -> x + 1"), "(U8 -> U8)");
+        assert_eq!(infer("let n = 1; n + 2u16"), "U16");
+        assert_eq!(infer("1 + 2"), "Number");
+    }
+}
@@ -1,3 +1,4 @@
+pub mod exhaustiveness;
 pub mod inference;
 pub mod poly_type;
 pub mod subst;
@@ -31,6 +31,21 @@ impl Unify for Type {
                 Ok(sub1.compose(&sub2))
             }
 
+            // Arrays unify when their element types do.
+            (Type::Array(box t1), Type::Array(box t2)) => t1.mgu(&t2),
+
+            // Tuples unify when they have the same length and their elements unify.
+            (Type::Tuple(elems1), Type::Tuple(elems2)) if elems1.len() == elems2.len() => {
+                mgu_all(elems1, elems2)
+            }
+
+            // Data types unify when they have the same name and their arguments unify.
+            (Type::Data(name1, args1), Type::Data(name2, args2))
+                if name1 == name2 && args1.len() == args2.len() =>
+            {
+                mgu_all(args1, args2)
+            }
+
             // If one of the types is variable, we can bind the variable to the type.
             // This also handles the case where they are both variables.
             (&Type::Variable(ref v), t) => v.bind(t),
@@ -38,8 +53,14 @@ impl Unify for Type {
 
             // If they are both primitives, no substitution needs to be done.
             (&Type::Number, &Type::Number)
+            | (&Type::Float, &Type::Float)
             | (&Type::Boolean, &Type::Boolean)
-            | (&Type::Empty, &Type::Empty) => Ok(Subst::new()),
+            | (&Type::Empty, &Type::Empty)
+            | (&Type::Range, &Type::Range)
+            | (&Type::File, &Type::File)
+            | (&Type::String, &Type::String)
+            | (&Type::Char, &Type::Char) => Ok(Subst::new()),
+            (&Type::Int(t1), &Type::Int(t2)) if t1 == t2 => Ok(Subst::new()),
 
             // Otherwise, the types cannot be unified.
             (t1, t2) => Err(TypeInferError::MismatchedTypes {
@@ -51,6 +72,15 @@ impl Unify for Type {
     }
 }
 
+/// Unify each pair of the types in order, threading the substitution.
+fn mgu_all(ts1: &[Type], ts2: &[Type]) -> Result<Subst, Error> {
+    ts1.iter()
+        .zip(ts2.iter())
+        .try_fold(Subst::new(), |s, (t1, t2)| {
+            Ok(t1.apply(&s).mgu(&t2.apply(&s))?.compose(&s))
+        })
+}
+
 impl Bind for TypeVarID {
     /// Attempt to bind a type variable to a type, returning an appropriate substitution.
     fn bind(self, ty: &Type) -> Result<Subst, Error> {
@@ -83,10 +113,27 @@ impl Types for Type {
             &Type::Variable(ref s) => [*s].iter().cloned().collect(),
 
             // Primitive types have no free variables
-            &Type::Number | &Type::Boolean | &Type::Empty => HashSet::new(),
+            &Type::Number
+            | &Type::Int(_)
+            | &Type::Float
+            | &Type::Boolean
+            | &Type::Empty
+            | &Type::Range
+            | &Type::File
+            | &Type::String
+            | &Type::Char => HashSet::new(),
 
             // For functions, we take the union of the free type variables of the input and output.
             Type::Function(box i, box o) => i.ftv().union(&o.ftv()).cloned().collect(),
+
+            // For arrays, the free type variables are the ones of the element type.
+            Type::Array(box t) => t.ftv(),
+
+            // For tuples, we take the union of the free type variables of the elements.
+            Type::Tuple(elems) => elems.ftv(),
+
+            // For data types, we take the union of the free type variables of the arguments.
+            Type::Data(_, args) => args.ftv(),
         }
     }
 
@@ -99,6 +146,15 @@ impl Types for Type {
             // To apply to a function, we simply apply to each of the input and output.
             Type::Function(box t1, box t2) => Type::Function(box t1.apply(s), box t2.apply(s)),
 
+            // To apply to an array, we apply to the element type.
+            Type::Array(box t) => Type::Array(box t.apply(s)),
+
+            // To apply to a tuple, we apply to each of the elements.
+            Type::Tuple(elems) => Type::Tuple(elems.apply(s)),
+
+            // To apply to a data type, we apply to each of the arguments.
+            Type::Data(name, args) => Type::Data(name.clone(), args.apply(s)),
+
             // A primitive type is changed by a substitution.
             _ => self.clone(),
         }
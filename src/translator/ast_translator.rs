@@ -1,43 +1,452 @@
-use crate::expression::Expression;
-use crate::ir::{Literal, Node, Value};
+use crate::error::TranslationError;
+use crate::expression::{Expression, Operator};
+use crate::ir::{
+    BindingKind, Class, Constructor, DataType, Identifier, Instance, Literal, MatchArm, Node,
+    StructField, StructType, Type, TypeAlias, TypeExpr, Value,
+};
 
 use failure::Error;
 
 use std::collections::HashMap;
 
+fn translate_type_expr(expr: Expression) -> Result<TypeExpr, Error> {
+    Ok(match expr {
+        Expression::TypeIdentifier(name) => {
+            if name.starts_with(char::is_lowercase) {
+                TypeExpr::Variable(name)
+            } else {
+                TypeExpr::Named(name, Vec::new())
+            }
+        }
+        Expression::TypeApply(name, args) => TypeExpr::Named(
+            name,
+            args.into_iter()
+                .map(translate_type_expr)
+                .collect::<Result<_, _>>()?,
+        ),
+        Expression::FunctionType(box param, box ret) => TypeExpr::Function(
+            box translate_type_expr(param)?,
+            box translate_type_expr(ret)?,
+        ),
+        Expression::TupleType(elems) => TypeExpr::Tuple(
+            elems
+                .into_iter()
+                .map(translate_type_expr)
+                .collect::<Result<_, _>>()?,
+        ),
+        Expression::ConstrainedType(constraints, box ty) => {
+            let ty = translate_type_expr(ty)?;
+            let vars = ty.free_vars();
+            if constraints.iter().any(|(_, var)| !vars.contains(var)) {
+                return Err(TranslationError::UndeclaredType.into());
+            }
+            TypeExpr::Constrained(constraints, box ty)
+        }
+        _ => return Err(TranslationError::InvalidType.into()),
+    })
+}
+
+fn translate_data_type(
+    name: Identifier,
+    elems: Vec<(Expression, Vec<Expression>)>,
+) -> Result<DataType, Error> {
+    let constructors = elems
+        .into_iter()
+        .map(|(ctor, fields)| match ctor {
+            Expression::TypeIdentifier(name) => Ok(Constructor {
+                name,
+                fields: fields
+                    .into_iter()
+                    .map(translate_type_expr)
+                    .collect::<Result<_, _>>()?,
+            }),
+            _ => Err(TranslationError::InvalidType.into()),
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut params = Vec::new();
+    for field in constructors.iter().flat_map(|ctor| ctor.fields.iter()) {
+        for var in field.free_vars() {
+            if !params.contains(&var) {
+                params.push(var);
+            }
+        }
+    }
+
+    Ok(DataType {
+        name,
+        params,
+        constructors,
+    })
+}
+
+fn translate_struct_type(
+    name: Identifier,
+    fields: Vec<(BindingKind, String, Expression)>,
+) -> Result<StructType, Error> {
+    let fields = fields
+        .into_iter()
+        .map(|(kind, field_name, ty)| {
+            let ty = translate_type_expr(ty)?;
+            if ty.mentions(&name) {
+                return Err(TranslationError::RecursiveStruct { name: name.clone() }.into());
+            }
+            Ok(StructField {
+                kind,
+                name: field_name,
+                ty,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut params = Vec::new();
+    for var in fields.iter().flat_map(|field| field.ty.free_vars()) {
+        if !params.contains(&var) {
+            params.push(var);
+        }
+    }
+
+    Ok(StructType {
+        name,
+        params,
+        fields,
+    })
+}
+
+fn translate_type_alias(
+    name: Identifier,
+    params: Vec<Identifier>,
+    ty: Expression,
+) -> Result<TypeAlias, Error> {
+    let ty = translate_type_expr(ty)?;
+    if ty.mentions(&name) {
+        return Err(TranslationError::RecursiveTypeAlias { name }.into());
+    }
+    Ok(TypeAlias { name, params, ty })
+}
+
+fn translate_class(
+    name: Identifier,
+    param: Identifier,
+    methods: Vec<(String, Expression)>,
+) -> Result<Class, Error> {
+    Ok(Class {
+        name,
+        param,
+        methods: methods
+            .into_iter()
+            .map(|(method, ty)| Ok((method, translate_type_expr(ty)?)))
+            .collect::<Result<_, Error>>()?,
+    })
+}
+
+fn translate_instance(
+    class: Identifier,
+    ty: Expression,
+    methods: Vec<(String, Expression)>,
+) -> Result<Instance, Error> {
+    Ok(Instance {
+        class,
+        ty: translate_type_expr(ty)?,
+        // Methods are bound recursively, so that they can refer to themselves
+        methods: methods
+            .into_iter()
+            .map(|(name, value)| match value {
+                Expression::Function(..) => Ok((name, translate_ast(value)?)),
+                _ => Err(TranslationError::InvalidMethod { name }.into()),
+            })
+            .collect::<Result<_, Error>>()?,
+    })
+}
+
+fn translate_fields(fields: Vec<(String, Expression)>) -> Result<Vec<(Identifier, Node)>, Error> {
+    fields
+        .into_iter()
+        .map(|(name, e)| Ok((name, translate_ast(e)?)))
+        .collect()
+}
+
+fn translate_rec_bindings(
+    bindings: Vec<(String, Expression)>,
+) -> Result<Vec<(Identifier, Node)>, Error> {
+    bindings
+        .into_iter()
+        .map(|(name, value)| match value {
+            Expression::Function(..) => Ok((name, translate_ast(value)?)),
+            _ => Err(TranslationError::RecursiveValue { name }.into()),
+        })
+        .collect()
+}
+
+fn function(param: &str, body: Node) -> Node {
+    Value::Literal(Literal::Function(
+        param.to_string(),
+        box body,
+        HashMap::new(),
+    ))
+    .untyped_node()
+}
+
+/// Desugar an operator section to a curried function, evaluating the given operand in advance.
+fn translate_section(
+    op: String,
+    lhs: Option<Box<Expression>>,
+    rhs: Option<Box<Expression>>,
+) -> Result<Value, Error> {
+    let var = |name: &str| Value::Variable(name.to_string()).untyped_node();
+    let apply = match op.parse::<Operator>() {
+        Ok(op) => Value::BinOp(op, box var("_lhs"), box var("_rhs")).untyped_node(),
+        Err(_) => {
+            let partial = Value::Apply(box Value::Variable(op).untyped_node(), box var("_lhs"));
+            Value::Apply(box partial.untyped_node(), box var("_rhs")).untyped_node()
+        }
+    };
+
+    let (operand, value) = match (lhs, rhs) {
+        (None, None) => return Ok(function("_lhs", function("_rhs", apply)).value),
+        (Some(box lhs), None) => ("_lhs", translate_ast(lhs)?),
+        (None, Some(box rhs)) => ("_rhs", translate_ast(rhs)?),
+        (Some(_), Some(_)) => unreachable!(),
+    };
+    let param = if operand == "_lhs" { "_rhs" } else { "_lhs" };
+    Ok(Value::Let(
+        BindingKind::Immutable,
+        operand.to_string(),
+        box value,
+        box function(param, apply),
+    ))
+}
+
+/// Desugar application with placeholders such as `f(_, 2)` to a function like `_0 -> f(_0, 2)`.
+fn translate_partial_apply(func: Expression, args: Vec<Expression>) -> Result<Value, Error> {
+    let mut params = Vec::new();
+    let mut applied = translate_ast(func)?;
+    for arg in args {
+        let arg = match arg {
+            Expression::Placeholder => {
+                let param = format!("_{}", params.len());
+                params.push(param.clone());
+                Value::Variable(param).untyped_node()
+            }
+            arg => translate_ast(arg)?,
+        };
+        applied = Value::Apply(box applied, box arg).untyped_node();
+    }
+
+    Ok(params
+        .iter()
+        .rev()
+        .fold(applied, |body, param| function(param, body))
+        .value)
+}
+
+/// Desugar a C function to a curried function which calls it once all the arguments are given.
+fn translate_extern(name: Identifier, ty: Expression) -> Result<Value, Error> {
+    let ty = translate_type_expr(ty)?;
+    let mut params = Vec::new();
+    let mut ret = &ty;
+    while let TypeExpr::Function(_, box next) = ret {
+        params.push(format!("_{}", params.len()));
+        ret = next;
+    }
+    if params.is_empty() {
+        return Err(TranslationError::InvalidExtern { name }.into());
+    }
+
+    let args = params
+        .iter()
+        .map(|param| Value::Variable(param.clone()).untyped_node())
+        .collect();
+    let call = Value::CallExtern(name, ty, args).untyped_node();
+    Ok(params
+        .iter()
+        .rev()
+        .fold(call, |body, param| function(param, body))
+        .value)
+}
+
+/// Desugar `for name in range { body }` to a `while` loop over an index variable.
+fn translate_for(name: Identifier, range: Expression, body: Expression) -> Result<Value, Error> {
+    let var = |name: &str| Value::Variable(name.to_string()).untyped_node();
+    let bound = |field: &str| Value::Field(box var("_range"), field.to_string()).untyped_node();
+    let binop = |op, lhs, rhs| Value::BinOp(op, box lhs, box rhs).untyped_node();
+    let one = || Value::Literal(Literal::Number(1)).untyped_node();
+
+    // The loop goes on while the index is less than the last one, which is only used once the
+    // range is known to be non-empty, so that the index never needs to go past the end
+    let first_and_last =
+        |op, last| Value::Tuple(vec![binop(op, var("_index"), bound("end")), last]);
+    let bounds = Value::IfElse(
+        box bound("inclusive"),
+        box first_and_last(Operator::Le, bound("end")).untyped_node(),
+        box first_and_last(Operator::Lt, binop(Operator::Sub, bound("end"), one())).untyped_node(),
+    );
+    let has_next = Value::Assign(
+        box var("_more"),
+        box binop(Operator::Lt, var("_index"), var("_last")),
+    );
+    let increment = Value::Assign(
+        box var("_index"),
+        box binop(Operator::Add, var("_index"), one()),
+    );
+    // The index is advanced before the body so that `continue` does not skip it
+    let body = Value::Let(
+        BindingKind::Immutable,
+        name,
+        box var("_index"),
+        box Value::Follow(
+            box has_next.untyped_node(),
+            box Value::Follow(box increment.untyped_node(), box translate_ast(body)?)
+                .untyped_node(),
+        )
+        .untyped_node(),
+    );
+    let loop_ = Value::While(box var("_more"), box body.untyped_node());
+
+    Ok(Value::Let(
+        BindingKind::Immutable,
+        "_range".to_string(),
+        box translate_ast(range)?,
+        box Value::Let(
+            BindingKind::Mutable,
+            "_index".to_string(),
+            box bound("start"),
+            box Value::LetTuple(
+                BindingKind::Mutable,
+                vec!["_more".to_string(), "_last".to_string()],
+                box bounds.untyped_node(),
+                box loop_.untyped_node(),
+            )
+            .untyped_node(),
+        )
+        .untyped_node(),
+    ))
+}
+
 pub fn translate_ast_value(expr: Expression) -> Result<Value, Error> {
     Ok(match expr {
         Expression::Number(number) => Value::Literal(Literal::Number(number)),
+        Expression::SizedNumber(value, ty) => {
+            let fits = match &ty {
+                Type::Int(t) => t.contains(value),
+                _ => i128::from(i64::min_value()) <= value && value <= i128::from(i64::max_value()),
+            };
+            if !fits {
+                return Err(TranslationError::IntegerOutOfRange { value, ty }.into());
+            }
+            match ty {
+                Type::Int(t) => Value::Literal(Literal::Int(value as i64, t)),
+                _ => Value::Literal(Literal::Number(value as i64)),
+            }
+        }
+        Expression::Float(number) => Value::Literal(Literal::Float(number)),
         Expression::Boolean(value) => Value::Literal(Literal::Boolean(value)),
+        Expression::String(value) => Value::Literal(Literal::String(value)),
+        Expression::Char(value) => Value::Literal(Literal::Char(value)),
         Expression::Empty => Value::Literal(Literal::Empty),
         Expression::Function(ident, body) => {
             let body = translate_ast(*body)?;
             Value::Literal(Literal::Function(ident, Box::new(body), HashMap::new()))
         }
-        Expression::Array(_) => unimplemented!(),
-        Expression::Type(_) => unimplemented!(),
+        Expression::Array(elems) => Value::Array(
+            elems
+                .into_iter()
+                .map(translate_ast)
+                .collect::<Result<_, _>>()?,
+        ),
+        Expression::Tuple(elems) => Value::Tuple(
+            elems
+                .into_iter()
+                .map(translate_ast)
+                .collect::<Result<_, _>>()?,
+        ),
+        Expression::TupleIndex(box tuple, index) => {
+            Value::TupleIndex(box translate_ast(tuple)?, index)
+        }
+        Expression::Type(_) | Expression::StructType(_) => {
+            return Err(TranslationError::UnboundTypeDefinition.into());
+        }
+        Expression::Struct(name, fields) => Value::Struct(name, translate_fields(fields)?),
+        Expression::Field(box value, field) => Value::Field(box translate_ast(value)?, field),
+        Expression::StructUpdate(box value, fields) => {
+            Value::StructUpdate(box translate_ast(value)?, translate_fields(fields)?)
+        }
         Expression::BinOp(op, lhs, rhs) => {
             let lhs = translate_ast(*lhs)?;
             let rhs = translate_ast(*rhs)?;
             Value::BinOp(op, Box::new(lhs), Box::new(rhs))
         }
+        Expression::UnaryOp(op, box value) => Value::UnaryOp(op, box translate_ast(value)?),
         Expression::Apply(lhs, rhs) => {
             let rhs_value = translate_ast(*rhs)?;
             let lhs_value = translate_ast(*lhs)?;
             Value::Apply(Box::new(lhs_value), Box::new(rhs_value))
         }
+        Expression::Section(op, lhs, rhs) => translate_section(op, lhs, rhs)?,
+        Expression::PartialApply(box func, args) => translate_partial_apply(func, args)?,
+        Expression::Placeholder => return Err(TranslationError::InvalidPlaceholder.into()),
+        Expression::Extern(name, box ty) => translate_extern(name, ty)?,
+        // Resolved to applications of operators by the parser
+        Expression::Infix(..) | Expression::BindOperator(..) => unreachable!(),
+        // Linked by the module loader
+        Expression::Import(..) | Expression::Export(..) => unreachable!(),
         Expression::Follow(box lhs, box rhs) => match lhs {
+            Expression::Bind(_, name, box Expression::Type(elems)) => {
+                let data_type = translate_data_type(name, elems)?;
+                let body = data_type.bind_constructors(translate_ast(rhs)?);
+                Value::DefineType(data_type, box body)
+            }
+            Expression::Bind(_, name, box Expression::StructType(fields)) => {
+                let struct_type = translate_struct_type(name, fields)?;
+                Value::DefineStruct(struct_type, box translate_ast(rhs)?)
+            }
+            Expression::TypeAlias(name, params, box ty) => {
+                let alias = translate_type_alias(name, params, ty)?;
+                Value::DefineAlias(alias, box translate_ast(rhs)?)
+            }
+            Expression::Class(name, param, methods) => {
+                let class = translate_class(name, param, methods)?;
+                Value::DefineClass(class, box translate_ast(rhs)?)
+            }
+            Expression::Instance(class, box ty, methods) => {
+                let instance = translate_instance(class, ty, methods)?;
+                Value::DefineInstance(instance, box translate_ast(rhs)?)
+            }
+            Expression::Exception(box ty) => {
+                Value::DefineException(translate_type_expr(ty)?, box translate_ast(rhs)?)
+            }
             Expression::Bind(kind, name, box bound_value) => {
                 let bound_value = translate_ast(bound_value)?;
                 let body = translate_ast(rhs)?;
                 Value::Let(kind, name, box bound_value, box body)
             }
+            Expression::BindTuple(kind, names, box bound_value) => {
+                let bound_value = translate_ast(bound_value)?;
+                let body = translate_ast(rhs)?;
+                Value::LetTuple(kind, names, box bound_value, box body)
+            }
+            Expression::BindRec(bindings) => {
+                let bindings = translate_rec_bindings(bindings)?;
+                Value::LetRec(bindings, box translate_ast(rhs)?)
+            }
             _ => {
                 let lhs = translate_ast(lhs)?;
                 let rhs = translate_ast(rhs)?;
                 Value::Follow(box lhs, box rhs)
             }
         },
+        Expression::Bind(_, name, box Expression::Type(elems)) => {
+            let data_type = translate_data_type(name, elems)?;
+            let body = data_type.bind_constructors(Value::Literal(Literal::Empty).untyped_node());
+            Value::DefineType(data_type, box body)
+        }
+        Expression::Bind(_, name, box Expression::StructType(fields)) => {
+            let struct_type = translate_struct_type(name, fields)?;
+            Value::DefineStruct(
+                struct_type,
+                box Value::Literal(Literal::Empty).untyped_node(),
+            )
+        }
         Expression::Bind(kind, name, box rhs) => {
             let rhs = translate_ast(rhs)?;
             Value::Let(
@@ -47,14 +456,63 @@ pub fn translate_ast_value(expr: Expression) -> Result<Value, Error> {
                 box Value::Variable(name).untyped_node(),
             )
         }
+        Expression::TypeAlias(name, params, box ty) => {
+            let alias = translate_type_alias(name, params, ty)?;
+            Value::DefineAlias(alias, box Value::Literal(Literal::Empty).untyped_node())
+        }
+        Expression::Class(name, param, methods) => {
+            let class = translate_class(name, param, methods)?;
+            Value::DefineClass(class, box Value::Literal(Literal::Empty).untyped_node())
+        }
+        Expression::Instance(class, box ty, methods) => {
+            let instance = translate_instance(class, ty, methods)?;
+            Value::DefineInstance(instance, box Value::Literal(Literal::Empty).untyped_node())
+        }
+        Expression::Exception(box ty) => Value::DefineException(
+            translate_type_expr(ty)?,
+            box Value::Literal(Literal::Empty).untyped_node(),
+        ),
+        Expression::BindTuple(kind, names, box rhs) => {
+            let rhs = translate_ast(rhs)?;
+            let body = Value::Tuple(
+                names
+                    .iter()
+                    .map(|name| Value::Variable(name.clone()).untyped_node())
+                    .collect(),
+            );
+            Value::LetTuple(kind, names, box rhs, box body.untyped_node())
+        }
+        Expression::BindRec(bindings) => {
+            let body = match bindings.as_slice() {
+                [(name, _)] => Value::Variable(name.clone()),
+                _ => Value::Tuple(
+                    bindings
+                        .iter()
+                        .map(|(name, _)| Value::Variable(name.clone()).untyped_node())
+                        .collect(),
+                ),
+            };
+            Value::LetRec(translate_rec_bindings(bindings)?, box body.untyped_node())
+        }
         Expression::Assign(lhs, rhs) => {
             let rhs_value = translate_ast(*rhs)?;
             let lhs_value = translate_ast(*lhs)?;
             Value::Assign(Box::new(lhs_value), Box::new(rhs_value))
         }
-        Expression::TypeIdentifier(_) => unimplemented!(),
+        Expression::TypeIdentifier(_)
+        | Expression::TypeApply(..)
+        | Expression::FunctionType(..)
+        | Expression::TupleType(..)
+        | Expression::ConstrainedType(..) => {
+            return Err(TranslationError::TypeInValuePosition.into())
+        }
         Expression::Identifier(name) => Value::Variable(name),
-        Expression::Cast(_lhs, _rhs) => unimplemented!(),
+        Expression::Cast(box lhs, box rhs) => {
+            Value::Cast(box translate_ast(lhs)?, translate_type_expr(rhs)?)
+        }
+        Expression::Ascribe(box value, box ty) => {
+            Value::Ascribe(box translate_ast(value)?, translate_type_expr(ty)?)
+        }
         Expression::Scope(box expr) => translate_ast_value(expr)?,
         Expression::IfElse(cond_expr, then_expr, else_expr) => {
             let cond_value = translate_ast(*cond_expr)?;
@@ -66,6 +524,37 @@ pub fn translate_ast_value(expr: Expression) -> Result<Value, Error> {
                 Box::new(else_value),
             )
         }
+        Expression::While(box cond, box body) => {
+            Value::While(box translate_ast(cond)?, box translate_ast(body)?)
+        }
+        Expression::Break(value) => Value::Break(match value {
+            Some(box value) => Some(box translate_ast(value)?),
+            None => None,
+        }),
+        Expression::Continue => Value::Continue,
+        Expression::Throw(box value) => Value::Throw(box translate_ast(value)?),
+        Expression::Try(box body, name, box handler) => {
+            Value::Try(box translate_ast(body)?, name, box translate_ast(handler)?)
+        }
+        Expression::Range(box start, box end, kind) => {
+            Value::Range(box translate_ast(start)?, box translate_ast(end)?, kind)
+        }
+        Expression::For(name, box range, box body) => translate_for(name, range, body)?,
+        Expression::Match(box value, arms) => Value::Match(
+            box translate_ast(value)?,
+            arms.into_iter()
+                .map(|(pattern, guard, body)| {
+                    Ok(MatchArm {
+                        pattern,
+                        guard: match guard {
+                            Some(guard) => Some(translate_ast(guard)?),
+                            None => None,
+                        },
+                        body: translate_ast(body)?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+        ),
     })
 }
 
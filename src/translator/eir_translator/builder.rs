@@ -1,14 +1,20 @@
 use super::atom::Atom;
 use super::BoundPointer;
 use crate::error::TranslationError;
-use crate::expression::Operator;
-use crate::ir::{BindingKind, Type};
+use crate::expression::{Operator, UnaryOperator};
+use crate::ir::{
+    BindingKind, Builtin, DataType, IntType, StructType, Type, TypeAlias, TypeDefs, TypeExpr,
+};
 use crate::scope::{Env, Scope, ScopedEnv};
 
 use failure::Error;
 
+use inkwell::attributes::{Attribute, AttributeLoc};
+use inkwell::module::Linkage;
 use inkwell::types::BasicType;
-use inkwell::{basic_block, builder, module, types, values, AddressSpace, IntPredicate};
+use inkwell::{
+    basic_block, builder, module, types, values, AddressSpace, FloatPredicate, IntPredicate,
+};
 
 use std::collections::{BTreeMap, HashMap};
 use std::mem;
@@ -33,18 +39,42 @@ impl Block {
     }
 }
 
+/// A loop being translated, which `break` and `continue` branch out of
+struct Loop {
+    header: Block,
+    exit: Block,
+    result: Option<values::PointerValue>,
+}
+
 pub struct Builder {
     inst_builder: builder::Builder,
+    /// Positioned in the entry block of the current function to emit stack allocations
+    alloca_builder: builder::Builder,
     module: module::Module,
     env: ScopedEnv<BoundPointer>,
+    type_defs: TypeDefs,
+    loops: Vec<Loop>,
+    /// Type of the thrown values, which is `String` unless declared
+    exception: Type,
+    /// Loads and stores of the mutable variables in the current function, which are made
+    /// volatile if the function contains `try`
+    var_accesses: Vec<values::InstructionValue>,
+    /// Whether `try` is entered in the current function
+    has_try: bool,
 }
 
 impl Builder {
     pub fn new(inst_builder: builder::Builder, module: module::Module) -> Self {
         Builder {
             inst_builder,
+            alloca_builder: module.get_context().create_builder(),
             module,
             env: ScopedEnv::new(),
+            type_defs: TypeDefs::new(),
+            loops: Vec::new(),
+            exception: Type::String,
+            var_accesses: Vec::new(),
+            has_try: false,
         }
     }
 
@@ -60,6 +90,71 @@ impl Builder {
         self.module
     }
 
+    pub fn define_type(&mut self, data_type: DataType) -> Result<(), Error> {
+        self.type_defs.define_data_type(data_type)
+    }
+
+    pub fn define_struct(&mut self, struct_type: StructType) {
+        self.type_defs.define_struct_type(struct_type);
+    }
+
+    pub fn define_alias(&mut self, alias: TypeAlias) {
+        self.type_defs.define_alias(alias);
+    }
+
+    pub fn define_exception(&mut self, ty: &TypeExpr) -> Result<(), Error> {
+        self.exception = self.type_defs.resolve(ty, &HashMap::new())?;
+        Ok(())
+    }
+
+    /// Find the index and the mutability of `field` in the struct type `ty`.
+    pub fn struct_field(&self, ty: &Type, field: &str) -> Result<(u32, BindingKind), Error> {
+        if let Some((index, _)) = ty.builtin_field(field) {
+            return Ok((index, BindingKind::Immutable));
+        }
+
+        let struct_type = match ty {
+            Type::Data(name, _) => self.type_defs.struct_type(name),
+            _ => None,
+        }
+        .ok_or(TranslationError::InvalidType)?;
+        let (index, field) = struct_type
+            .field(field)
+            .ok_or(TranslationError::InvalidType)?;
+        Ok((index as u32, field.kind))
+    }
+
+    /// Find the tag and the field types of the constructor `name` of the data type `ty`.
+    pub fn constructor_fields(&self, ty: &Type, name: &str) -> Result<(u64, Vec<Type>), Error> {
+        let (data_type, tag) = self
+            .type_defs
+            .find_constructor(name)
+            .ok_or(TranslationError::UndeclaredVariable)?;
+        let args = match ty {
+            Type::Data(ty_name, args) if ty_name == &data_type.name => args,
+            _ => return Err(TranslationError::InvalidType.into()),
+        };
+        let vars: HashMap<_, _> = data_type
+            .params
+            .iter()
+            .cloned()
+            .zip(args.iter().cloned())
+            .collect();
+        let fields = data_type.constructors[tag]
+            .fields
+            .iter()
+            .map(|field| self.type_defs.resolve(field, &vars))
+            .collect::<Result<_, _>>()?;
+        Ok((tag as u64, fields))
+    }
+
+    pub fn constructor_tag(&self, name: &str) -> Result<u64, Error> {
+        self.type_defs
+            .find_constructor(name)
+            .map(|(_, tag)| tag as u64)
+            .ok_or_else(|| TranslationError::UndeclaredVariable.into())
+    }
+
     pub fn type_of(&self, v: values::BasicValueEnum) -> types::BasicTypeEnum {
         match v {
             values::BasicValueEnum::ArrayValue(v) => v.get_type().into(),
@@ -74,6 +169,8 @@ impl Builder {
     pub fn llvm_type(&self, ty: &Type) -> Result<types::BasicTypeEnum, Error> {
         Ok(match ty {
             Type::Number => types::IntType::i64_type().into(),
+            Type::Int(t) => int_type(*t).into(),
+            Type::Float => types::FloatType::f64_type().into(),
             Type::Boolean => types::IntType::bool_type().into(),
             Type::Empty => types::VoidType::void_type()
                 .ptr_type(AddressSpace::Generic)
@@ -89,16 +186,77 @@ impl Builder {
                     .ptr_type(AddressSpace::Generic);
                 types::StructType::struct_type(&[void_ptr.into(), fn_type.into()], false).into()
             }
+            Type::String => self.string_type().into(),
+            Type::Char => types::IntType::i32_type().into(),
+            // Pointer to `FILE` in libc
+            Type::File => self.byte_ptr_type().into(),
+            // The start, the end and whether the end is included
+            Type::Range => {
+                let number_type = types::IntType::i64_type().into();
+                let bool_type = types::IntType::bool_type().into();
+                types::StructType::struct_type(&[number_type, number_type, bool_type], false).into()
+            }
+            Type::Tuple(elems) => {
+                let elem_types = elems
+                    .iter()
+                    .map(|t| self.llvm_type(t))
+                    .collect::<Result<Vec<_>, _>>()?;
+                types::StructType::struct_type(&elem_types, false).into()
+            }
+            Type::Data(name, args) if self.type_defs.struct_type(name).is_some() => {
+                let struct_type = self.type_defs.struct_type(name).unwrap();
+                let vars: HashMap<_, _> = struct_type
+                    .params
+                    .iter()
+                    .cloned()
+                    .zip(args.iter().cloned())
+                    .collect();
+                let field_types = struct_type
+                    .fields
+                    .iter()
+                    .map(|field| self.llvm_type(&self.type_defs.resolve(&field.ty, &vars)?))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                types::StructType::struct_type(&field_types, false).into()
+            }
+            Type::Data(name, _) => {
+                self.type_defs
+                    .data_type(name)
+                    .ok_or(TranslationError::UndeclaredType)?;
+                // tag and pointer to the payload
+                let void_ptr = types::VoidType::void_type().ptr_type(AddressSpace::Generic);
+                types::StructType::struct_type(
+                    &[types::IntType::i64_type().into(), void_ptr.into()],
+                    false,
+                )
+                .into()
+            }
+            Type::Array(box elem) => {
+                let data_ptr = self.llvm_type(elem)?.ptr_type(AddressSpace::Generic);
+                types::StructType::struct_type(
+                    &[data_ptr.into(), types::IntType::i64_type().into()],
+                    false,
+                )
+                .into()
+            }
         })
     }
 
     pub fn number_constant(&mut self, v: i64) -> Result<values::BasicValueEnum, Error> {
         let t = types::IntType::i64_type();
         Ok(values::BasicValueEnum::IntValue(
-            t.const_int(v.abs() as u64, v < 0),
+            t.const_int(v as u64, true),
         ))
     }
 
+    pub fn int_constant(&mut self, t: IntType, bits: i64) -> values::BasicValueEnum {
+        int_type(t).const_int(bits as u64, true).into()
+    }
+
+    pub fn float_constant(&mut self, v: f64) -> Result<values::BasicValueEnum, Error> {
+        let t = types::FloatType::f64_type();
+        Ok(values::BasicValueEnum::FloatValue(t.const_float(v)))
+    }
+
     pub fn boolean_constant(&mut self, v: bool) -> Result<values::BasicValueEnum, Error> {
         let t = types::IntType::bool_type();
         Ok(values::BasicValueEnum::IntValue(
@@ -106,11 +264,209 @@ impl Builder {
         ))
     }
 
+    pub fn string_constant(&mut self, v: &str) -> Result<values::BasicValueEnum, Error> {
+        // The data is not NUL-terminated, as strings may contain NUL
+        let i8_type = types::IntType::i8_type();
+        let bytes: Vec<_> = v
+            .bytes()
+            .map(|b| i8_type.const_int(u64::from(b), false))
+            .collect();
+        let data = i8_type.const_array(&bytes);
+        let global = self.module.add_global(data.get_type(), None, "str");
+        global.set_linkage(Linkage::Private);
+        global.set_constant(true);
+        global.set_initializer(&data);
+        let data_ptr = self.inst_builder.build_pointer_cast(
+            global.as_pointer_value(),
+            self.byte_ptr_type(),
+            "str",
+        );
+        let len = types::IntType::i64_type().const_int(v.len() as u64, false);
+        Ok(self.string_value(data_ptr, len))
+    }
+
+    pub fn char_constant(&mut self, v: char) -> Result<values::BasicValueEnum, Error> {
+        let t = types::IntType::i32_type();
+        Ok(values::BasicValueEnum::IntValue(
+            t.const_int(u64::from(u32::from(v)), false),
+        ))
+    }
+
     pub fn empty_constant(&self) -> Result<values::BasicValueEnum, Error> {
         let t = types::VoidType::void_type().ptr_type(AddressSpace::Generic);
         Ok(values::BasicValueEnum::PointerValue(t.const_null()))
     }
 
+    pub fn undef_value(&self, t: types::BasicTypeEnum) -> values::BasicValueEnum {
+        match t {
+            types::BasicTypeEnum::ArrayType(t) => t.get_undef().into(),
+            types::BasicTypeEnum::FloatType(t) => t.get_undef().into(),
+            types::BasicTypeEnum::IntType(t) => t.get_undef().into(),
+            types::BasicTypeEnum::PointerType(t) => t.get_undef().into(),
+            types::BasicTypeEnum::StructType(t) => t.get_undef().into(),
+            types::BasicTypeEnum::VectorType(t) => t.get_undef().into(),
+        }
+    }
+
+    pub fn array_constant(
+        &mut self,
+        elem_type: types::BasicTypeEnum,
+        elems: &[values::BasicValueEnum],
+    ) -> Result<values::BasicValueEnum, Error> {
+        let len = elems.len() as u32;
+        let data_ptr = self.array_alloc(elem_type, len)?;
+        for (i, elem) in elems.iter().enumerate() {
+            let offset = types::IntType::i64_type().const_int(i as u64, false);
+            self.store(*elem, data_ptr, offset)?;
+        }
+
+        let array_type = types::StructType::struct_type(
+            &[
+                data_ptr.get_type().into(),
+                types::IntType::i64_type().into(),
+            ],
+            false,
+        );
+        let len_value = types::IntType::i64_type().const_int(u64::from(len), false);
+        let array = self.insert_value(array_type.get_undef(), data_ptr.into(), 0);
+        let array = self.insert_value(array, len_value.into(), 1);
+        Ok(array.into())
+    }
+
+    pub fn tuple_constant(
+        &mut self,
+        elems: &[values::BasicValueEnum],
+    ) -> Result<values::BasicValueEnum, Error> {
+        let elem_types: Vec<_> = elems.iter().map(|v| self.type_of(*v)).collect();
+        let tuple_type = types::StructType::struct_type(&elem_types, false);
+        let tuple = elems
+            .iter()
+            .enumerate()
+            .fold(tuple_type.get_undef(), |tuple, (i, elem)| {
+                self.insert_value(tuple, *elem, i as u32)
+            });
+        Ok(tuple.into())
+    }
+
+    /// Extract the element at `index` from a tuple or a struct.
+    pub fn extract_element(
+        &mut self,
+        aggregate: values::BasicValueEnum,
+        index: u32,
+    ) -> Result<values::BasicValueEnum, Error> {
+        match aggregate {
+            values::BasicValueEnum::StructValue(aggregate) => Ok(self
+                .inst_builder
+                .build_extract_value(aggregate, index, "elem")
+                .unwrap()),
+            _ => Err(TranslationError::InvalidType.into()),
+        }
+    }
+
+    /// Replace the fields at the given indices of `base`.
+    pub fn update_struct(
+        &mut self,
+        base: values::BasicValueEnum,
+        fields: &[(u32, values::BasicValueEnum)],
+    ) -> Result<values::BasicValueEnum, Error> {
+        match base {
+            values::BasicValueEnum::StructValue(base) => Ok(fields
+                .iter()
+                .fold(base, |agg, (index, v)| self.insert_value(agg, *v, *index))
+                .into()),
+            _ => Err(TranslationError::InvalidType.into()),
+        }
+    }
+
+    pub fn struct_constant(
+        &mut self,
+        ty: &Type,
+        fields: &[(u32, values::BasicValueEnum)],
+    ) -> Result<values::BasicValueEnum, Error> {
+        let struct_type = self.llvm_type(ty)?.into_struct_type();
+        self.update_struct(struct_type.get_undef().into(), fields)
+    }
+
+    pub fn field_ptr(
+        &mut self,
+        ptr: values::PointerValue,
+        index: u32,
+    ) -> Result<values::PointerValue, Error> {
+        Ok(unsafe { self.inst_builder.build_struct_gep(ptr, index, "field_ptr") })
+    }
+
+    pub fn data_constant(
+        &mut self,
+        ty: &Type,
+        tag: u64,
+        fields: &[values::BasicValueEnum],
+    ) -> Result<values::BasicValueEnum, Error> {
+        let data_type = self.llvm_type(ty)?.into_struct_type();
+        let void_ptr_ty = types::VoidType::void_type().ptr_type(AddressSpace::Generic);
+
+        let payload_ptr = if fields.is_empty() {
+            void_ptr_ty.const_null()
+        } else {
+            let field_types: Vec<_> = fields.iter().map(|v| self.type_of(*v)).collect();
+            let payload_type = types::StructType::struct_type(&field_types, false);
+            // TODO: Fix memory leak
+            let ptr = self.inst_builder.build_malloc(payload_type, "payload_ptr");
+            for (i, field) in fields.iter().enumerate() {
+                let field_ptr = unsafe { self.inst_builder.build_struct_gep(ptr, i as u32, "") };
+                self.inst_builder.build_store(field_ptr, *field);
+            }
+            self.inst_builder
+                .build_pointer_cast(ptr, void_ptr_ty, "payload_erase")
+        };
+
+        let tag_value = types::IntType::i64_type().const_int(tag, false);
+        let data = self.insert_value(data_type.get_undef(), tag_value.into(), 0);
+        let data = self.insert_value(data, payload_ptr.into(), 1);
+        Ok(data.into())
+    }
+
+    /// Load the fields of a value of a data type, assuming it has fields of `field_types`.
+    pub fn data_fields(
+        &mut self,
+        data: values::BasicValueEnum,
+        field_types: &[Type],
+    ) -> Result<Vec<values::BasicValueEnum>, Error> {
+        let field_types = field_types
+            .iter()
+            .map(|t| self.llvm_type(t))
+            .collect::<Result<Vec<_>, _>>()?;
+        let payload_type = types::StructType::struct_type(&field_types, false);
+        let payload_ptr = self.extract_element(data, 1)?.into_pointer_value();
+        let payload_ptr = self.inst_builder.build_pointer_cast(
+            payload_ptr,
+            payload_type.ptr_type(AddressSpace::Generic),
+            "payload",
+        );
+        Ok((0..field_types.len())
+            .map(|i| {
+                let field_ptr = unsafe {
+                    self.inst_builder
+                        .build_struct_gep(payload_ptr, i as u32, "")
+                };
+                self.inst_builder.build_load(field_ptr, "field")
+            })
+            .collect())
+    }
+
+    // TODO: Fix this dangerous implementation
+    fn insert_value(
+        &self,
+        agg: values::StructValue,
+        v: values::BasicValueEnum,
+        index: u32,
+    ) -> values::StructValue {
+        let ret = self
+            .inst_builder
+            .build_insert_value(agg, v, index, "")
+            .unwrap();
+        unsafe { mem::transmute(ret) }
+    }
+
     pub fn function_constant<F>(
         &mut self,
         ty: &Type,
@@ -121,28 +477,105 @@ impl Builder {
     where
         F: FnOnce(&mut Self) -> Result<values::BasicValueEnum, Error>,
     {
-        let previous_block = self.inst_builder().get_insert_block().unwrap();
-        self.enter_new_scope();
-
         // TODO: Remove this insufficient copy
         let capture_list: BTreeMap<_, _> = capture_list.iter().collect();
 
-        let capture_types: Vec<types::BasicTypeEnum> = capture_list
-            .iter()
-            .map(|(_, ty)| self.llvm_type(ty))
-            .collect::<Result<_, _>>()?;
-        let capture_type = types::StructType::struct_type(&capture_types, false);
+        let fn_type = self.closure_function_type(ty)?;
+        let function = self.module.add_function("", fn_type, None);
+        self.build_function_body(function, &param_name, &capture_list, eval)?;
+
+        let capture_ptr = self.build_capture(&capture_list)?;
+        Ok(self.closure_value(capture_ptr, function))
+    }
+
+    /// Build the closures of mutually recursive functions, which share one set of captured values.
+    ///
+    /// Each function is given as its name, its parameter and the types it is instantiated at.
+    /// `eval` emits the body of the `j`th instance of the `i`th function, in which all the
+    /// functions in the group are bound.
+    pub fn recursive_function_constants<F>(
+        &mut self,
+        functions: &[(String, String, Vec<Type>)],
+        capture_list: &HashMap<String, Type>,
+        mut eval: F,
+    ) -> Result<Vec<Atom<values::BasicValueEnum>>, Error>
+    where
+        F: FnMut(&mut Self, usize, usize) -> Result<values::BasicValueEnum, Error>,
+    {
+        let capture_list: BTreeMap<_, _> = capture_list.iter().collect();
+
+        // Declare all the functions first so that their bodies can refer to each other
+        let mut declared = Vec::new();
+        for (_, _, types) in functions {
+            let mut instances = Vec::new();
+            for ty in types {
+                let fn_type = self.closure_function_type(ty)?;
+                instances.push((ty.clone(), self.module.add_function("", fn_type, None)));
+            }
+            declared.push(instances);
+        }
+
+        for (i, (_, param_name, _)) in functions.iter().enumerate() {
+            for (j, &(_, function)) in declared[i].iter().enumerate() {
+                let declared = &declared;
+                let eval = &mut eval;
+                self.build_function_body(function, param_name, &capture_list, |builder| {
+                    let capture_ptr = function.get_nth_param(0).unwrap().into_pointer_value();
+                    for ((name, _, _), instances) in functions.iter().zip(declared) {
+                        let closures = builder.closures(capture_ptr, instances);
+                        builder.bind_var(name, &closures, BindingKind::Immutable)?;
+                    }
+                    eval(builder, i, j)
+                })?;
+            }
+        }
 
-        let fn_concrete_type = self.llvm_type(ty)?.into_struct_type();
+        let capture_ptr = self.build_capture(&capture_list)?;
+        Ok(declared
+            .iter()
+            .map(|instances| self.closures(capture_ptr, instances))
+            .collect())
+    }
 
-        let fn_type = fn_concrete_type
+    /// The type of the code of closures whose type is `ty`.
+    fn closure_function_type(&self, ty: &Type) -> Result<types::FunctionType, Error> {
+        Ok(self
+            .llvm_type(ty)?
+            .into_struct_type()
             .get_field_type_at_index(1)
             .unwrap()
             .into_pointer_type()
             .get_element_type()
-            .into_function_type();
+            .into_function_type())
+    }
+
+    fn capture_type(
+        &self,
+        capture_list: &BTreeMap<&String, &Type>,
+    ) -> Result<types::StructType, Error> {
+        let capture_types: Vec<types::BasicTypeEnum> = capture_list
+            .iter()
+            .map(|(_, ty)| self.llvm_type(ty))
+            .collect::<Result<_, _>>()?;
+        Ok(types::StructType::struct_type(&capture_types, false))
+    }
+
+    /// Emit the body of `function`, in which the parameter and the captured variables are bound.
+    fn build_function_body<F>(
+        &mut self,
+        function: values::FunctionValue,
+        param_name: &str,
+        capture_list: &BTreeMap<&String, &Type>,
+        eval: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Self) -> Result<values::BasicValueEnum, Error>,
+    {
+        let previous_block = self.inst_builder().get_insert_block().unwrap();
+        self.enter_new_scope();
+
+        let capture_type = self.capture_type(capture_list)?;
 
-        let function = self.module.add_function("", fn_type, None);
         let basic_block = self
             .module
             .get_context()
@@ -150,11 +583,11 @@ impl Builder {
         self.inst_builder.position_at_end(&basic_block);
         let arg_ptr = self
             .inst_builder
-            .build_alloca(fn_type.get_param_types()[1], "");
+            .build_alloca(function.get_type().get_param_types()[1], "");
         self.inst_builder
             .build_store(arg_ptr, function.get_nth_param(1).unwrap());
         self.env.insert(
-            &param_name,
+            param_name,
             BoundPointer::new(BindingKind::Immutable, arg_ptr.into()),
         );
 
@@ -172,59 +605,87 @@ impl Builder {
                 .insert(&name, BoundPointer::new(BindingKind::Immutable, ptr.into()));
         }
 
+        let var_accesses = mem::replace(&mut self.var_accesses, Vec::new());
+        let has_try = mem::replace(&mut self.has_try, false);
         let ret = eval(self)?;
+        self.finish_var_accesses();
+        self.var_accesses = var_accesses;
+        self.has_try = has_try;
 
         self.exit_scope()?;
         self.inst_builder().build_return(Some(&ret));
         self.inst_builder().position_at_end(&previous_block);
+        Ok(())
+    }
+
+    /// Copy the current values of the captured variables into a newly allocated memory and
+    /// return the type-erased pointer to it.
+    fn build_capture(
+        &mut self,
+        capture_list: &BTreeMap<&String, &Type>,
+    ) -> Result<values::PointerValue, Error> {
+        let capture_type = self.capture_type(capture_list)?;
 
         // TODO: Fix memory leak
         let capture_ptr = self
             .inst_builder
             .build_malloc(capture_type, "eval_capture_ptr");
-        for (i, (name, _)) in capture_list.iter().enumerate() {
+        for (i, (name, ty)) in capture_list.iter().enumerate() {
             let ptr = unsafe {
                 self.inst_builder
                     .build_struct_gep(capture_ptr, i as u32, "")
             };
-            let var_ptr = self
-                .env
-                .get(&name)
-                .unwrap()
-                .ptr_value()
-                .clone()
-                .expect_value()?;
+            let var_ptr = match self.env.get(&name).unwrap().ptr_value() {
+                Atom::LLVMValue(var_ptr) => *var_ptr,
+                // Capture the instance of the polymorphic value which is used in the closure
+                Atom::PolyValue(var_table) => {
+                    *var_table.get(ty).ok_or(TranslationError::UnresolvedType)?
+                }
+            };
             self.inst_builder
                 .build_store(ptr, self.inst_builder.build_load(var_ptr, ""));
         }
 
+        let void_ptr_ty = types::VoidType::void_type().ptr_type(AddressSpace::Generic);
+        Ok(self
+            .inst_builder
+            .build_pointer_cast(capture_ptr, void_ptr_ty, "capture_ptr_erase"))
+    }
+
+    fn closure_value(
+        &self,
+        capture_ptr: values::PointerValue,
+        function: values::FunctionValue,
+    ) -> values::BasicValueEnum {
         let void_ptr_ty = types::VoidType::void_type().ptr_type(AddressSpace::Generic);
         let ret_type = types::StructType::struct_type(
             &[
                 void_ptr_ty.into(),
-                fn_type.ptr_type(AddressSpace::Generic).into(),
+                function.get_type().ptr_type(AddressSpace::Generic).into(),
             ],
             false,
         );
 
-        let capture_ptr_erased =
-            self.inst_builder
-                .build_pointer_cast(capture_ptr, void_ptr_ty, "capture_ptr_erase");
-        let ptr: values::PointerValue = unsafe { mem::transmute(function) };
-
         // TODO: Fix this dangerous implementation
-        let real_ret = self
-            .inst_builder
-            .build_insert_value(ret_type.get_undef(), capture_ptr_erased, 0, "")
-            .unwrap();
-        let real_ret: values::StructValue = unsafe { mem::transmute(real_ret) };
-        let real_ret = self
-            .inst_builder
-            .build_insert_value(real_ret, ptr, 1, "")
-            .unwrap();
-        let real_ret: values::StructValue = unsafe { mem::transmute(real_ret) };
+        let ptr: values::PointerValue = unsafe { mem::transmute(function) };
+        let closure = self.insert_value(ret_type.get_undef(), capture_ptr.into(), 0);
+        self.insert_value(closure, ptr.into(), 1).into()
+    }
 
-        Ok(real_ret.into())
+    /// Closures of the instances of a function, which are polymorphic unless exactly one exists.
+    fn closures(
+        &self,
+        capture_ptr: values::PointerValue,
+        instances: &[(Type, values::FunctionValue)],
+    ) -> Atom<values::BasicValueEnum> {
+        match instances {
+            [(_, function)] => self.closure_value(capture_ptr, *function).into(),
+            _ => instances
+                .iter()
+                .map(|(ty, function)| (ty.clone(), self.closure_value(capture_ptr, *function)))
+                .collect::<HashMap<_, _>>()
+                .into(),
+        }
     }
 
     pub fn call(
@@ -250,114 +711,1497 @@ impl Builder {
         Ok(call_inst.try_as_basic_value().left().unwrap())
     }
 
+    /// Apply `op` to the operands, which are taken as unsigned integers unless `signed`.
     pub fn apply_op(
         &mut self,
         op: Operator,
         lhs: values::BasicValueEnum,
         rhs: values::BasicValueEnum,
+        signed: bool,
     ) -> Result<values::BasicValueEnum, Error> {
+        if let Operator::Index = op {
+            return self.index(lhs, rhs);
+        }
+        if let values::BasicValueEnum::FloatValue(lhs) = lhs {
+            return self.float_op(op, lhs, rhs.into_float_value());
+        }
+
         let lhs_int = lhs.into_int_value();
         let rhs_int = rhs.into_int_value();
         Ok(match op {
             Operator::Add => self.inst_builder.build_int_add(lhs_int, rhs_int, "add"),
             Operator::Sub => self.inst_builder.build_int_sub(lhs_int, rhs_int, "sub"),
             Operator::Mul => self.inst_builder.build_int_mul(lhs_int, rhs_int, "mul"),
-            Operator::Div => self
-                .inst_builder
-                .build_int_unsigned_div(lhs_int, rhs_int, "div"),
+            Operator::Div | Operator::Rem => self.checked_div(op, lhs_int, rhs_int, signed),
+            Operator::Shl => {
+                let rhs_int = self.shift_amount(rhs_int);
+                self.inst_builder.build_left_shift(lhs_int, rhs_int, "shl")
+            }
+            Operator::Shr => {
+                let rhs_int = self.shift_amount(rhs_int);
+                self.inst_builder
+                    .build_right_shift(lhs_int, rhs_int, signed, "shr")
+            }
+            Operator::UShr => {
+                let rhs_int = self.shift_amount(rhs_int);
+                self.inst_builder
+                    .build_right_shift(lhs_int, rhs_int, false, "ushr")
+            }
             Operator::BitAnd => self.inst_builder.build_and(lhs_int, rhs_int, "add"),
             Operator::BitXor => self.inst_builder.build_xor(lhs_int, rhs_int, "xor"),
             Operator::BitOr => self.inst_builder.build_or(lhs_int, rhs_int, "or"),
-            Operator::Lt => self.cmp(CondCode::LessThan, lhs_int, rhs_int),
-            Operator::Gt => self.cmp(CondCode::GreaterThan, lhs_int, rhs_int),
-            Operator::Le => self.cmp(CondCode::LessThanOrEqual, lhs_int, rhs_int),
-            Operator::Ge => self.cmp(CondCode::GreaterThanOrEqual, lhs_int, rhs_int),
+            Operator::Lt => self.int_cmp(CondCode::LessThan, lhs_int, rhs_int, signed),
+            Operator::Gt => self.int_cmp(CondCode::GreaterThan, lhs_int, rhs_int, signed),
+            Operator::Le => self.int_cmp(CondCode::LessThanOrEqual, lhs_int, rhs_int, signed),
+            Operator::Ge => self.int_cmp(CondCode::GreaterThanOrEqual, lhs_int, rhs_int, signed),
             Operator::Eq => self.cmp(CondCode::Equal, lhs_int, rhs_int),
             Operator::Ne => self.cmp(CondCode::NotEqual, lhs_int, rhs_int),
-            Operator::Index => self.index(lhs, rhs),
+            // Short-circuit operators are lowered to branches in the translator
+            Operator::And | Operator::Or | Operator::Index => unreachable!(),
         }
         .into())
     }
 
-    pub fn cmp(
+    pub fn apply_unary_op(
         &mut self,
-        cmp_type: CondCode,
+        op: UnaryOperator,
+        value: values::BasicValueEnum,
+    ) -> values::BasicValueEnum {
+        match (op, value) {
+            (UnaryOperator::Neg, values::BasicValueEnum::FloatValue(value)) => {
+                self.inst_builder.build_float_neg(value, "neg").into()
+            }
+            (UnaryOperator::Neg, value) => self
+                .inst_builder
+                .build_int_neg(value.into_int_value(), "neg")
+                .into(),
+            // `!` on a Boolean is bitwise as well, since it is represented in `i1`
+            (UnaryOperator::Not, value) | (UnaryOperator::BitNot, value) => self
+                .inst_builder
+                .build_not(value.into_int_value(), "not")
+                .into(),
+        }
+    }
+
+    /// Division and remainder, which are defined for every operand unlike in LLVM:
+    /// `x / 0` is `0` and `x % 0` is `x`, and `MIN / -1` of a signed type wraps around to `MIN`.
+    fn checked_div(
+        &mut self,
+        op: Operator,
         lhs: values::IntValue,
         rhs: values::IntValue,
+        signed: bool,
     ) -> values::IntValue {
-        let cc = match cmp_type {
-            CondCode::Equal => IntPredicate::EQ,
-            CondCode::NotEqual => IntPredicate::NE,
-            CondCode::LessThan => IntPredicate::SLT,
-            CondCode::GreaterThanOrEqual => IntPredicate::SGE,
-            CondCode::GreaterThan => IntPredicate::SGT,
-            CondCode::LessThanOrEqual => IntPredicate::SLE,
+        let t = lhs.get_type();
+        let zero = t.const_int(0, false);
+        let one = t.const_int(1, false);
+
+        let is_zero = self.cmp(CondCode::Equal, rhs, zero);
+        let is_invalid = if signed {
+            let minus_one = t.const_int(u64::max_value(), false);
+            let min = t.const_int(1 << (t.get_bit_width() - 1), false);
+            let lhs_is_min = self.cmp(CondCode::Equal, lhs, min);
+            let rhs_is_minus_one = self.cmp(CondCode::Equal, rhs, minus_one);
+            let overflows = self
+                .inst_builder
+                .build_and(lhs_is_min, rhs_is_minus_one, "overflows");
+            self.inst_builder.build_or(is_zero, overflows, "invalid")
+        } else {
+            is_zero
         };
+        // Dividing by one instead gives the wrapped results in case of overflow
+        let divisor = self
+            .inst_builder
+            .build_select(is_invalid, one, rhs, "divisor")
+            .into_int_value();
 
-        self.inst_builder.build_int_compare(cc, lhs, rhs, "cmp")
+        let (result, by_zero) = match (op, signed) {
+            (Operator::Div, true) => (
+                self.inst_builder.build_int_signed_div(lhs, divisor, "div"),
+                zero,
+            ),
+            (Operator::Div, false) => (
+                self.inst_builder
+                    .build_int_unsigned_div(lhs, divisor, "div"),
+                zero,
+            ),
+            (Operator::Rem, true) => (
+                self.inst_builder.build_int_signed_rem(lhs, divisor, "rem"),
+                lhs,
+            ),
+            (Operator::Rem, false) => (
+                self.inst_builder
+                    .build_int_unsigned_rem(lhs, divisor, "rem"),
+                lhs,
+            ),
+            _ => unreachable!(),
+        };
+        self.inst_builder
+            .build_select(is_zero, by_zero, result, "")
+            .into_int_value()
     }
 
-    pub fn index(
-        &mut self,
-        _lhs: values::BasicValueEnum,
-        _rhs: values::BasicValueEnum,
-    ) -> values::IntValue {
-        unimplemented!()
+    /// Shift amounts are taken modulo the bit width, since shifting by it or more is undefined.
+    fn shift_amount(&mut self, amount: values::IntValue) -> values::IntValue {
+        let t = amount.get_type();
+        let mask = t.const_int(u64::from(t.get_bit_width() - 1), false);
+        self.inst_builder.build_and(amount, mask, "shift_amount")
     }
 
-    pub(crate) fn declare_mut_var(
+    fn float_op(
         &mut self,
-        name: &str,
-        base_value: &Atom<values::BasicValueEnum>,
-        unique: bool,
-    ) -> Result<String, Error> {
-        let real_name = if unique {
-            self.env.unique_name(name)
-        } else {
-            name.to_string()
+        op: Operator,
+        lhs: values::FloatValue,
+        rhs: values::FloatValue,
+    ) -> Result<values::BasicValueEnum, Error> {
+        let cc = match op {
+            Operator::Add => return Ok(self.inst_builder.build_float_add(lhs, rhs, "fadd").into()),
+            Operator::Sub => return Ok(self.inst_builder.build_float_sub(lhs, rhs, "fsub").into()),
+            Operator::Mul => return Ok(self.inst_builder.build_float_mul(lhs, rhs, "fmul").into()),
+            Operator::Div => return Ok(self.inst_builder.build_float_div(lhs, rhs, "fdiv").into()),
+            Operator::Lt => FloatPredicate::OLT,
+            Operator::Gt => FloatPredicate::OGT,
+            Operator::Le => FloatPredicate::OLE,
+            Operator::Ge => FloatPredicate::OGE,
+            Operator::Eq => FloatPredicate::OEQ,
+            // NaN is not equal to anything
+            Operator::Ne => FloatPredicate::UNE,
+            _ => return Err(TranslationError::InvalidType.into()),
         };
-        let ptr = match base_value {
-            Atom::LLVMValue(val) => {
-                let t = self.type_of(*val);
-                self.inst_builder.build_alloca(t, &real_name).into()
+        Ok(self
+            .inst_builder
+            .build_float_compare(cc, lhs, rhs, "fcmp")
+            .into())
+    }
+
+    pub fn call_builtin(
+        &mut self,
+        builtin: Builtin,
+        arg_types: &[Type],
+        args: &[values::BasicValueEnum],
+        ret: &Type,
+    ) -> Result<values::BasicValueEnum, Error> {
+        let intrinsic = match builtin {
+            Builtin::OpenFile
+            | Builtin::CloseFile
+            | Builtin::ReadFrom
+            | Builtin::WriteTo
+            | Builtin::ReadFile
+            | Builtin::WriteFile => return self.call_io(builtin, args, ret),
+            Builtin::Args => return self.args(),
+            Builtin::GetEnv => {
+                let name = self.c_string(args[0])?;
+                let message = Some("Environment variable is not set");
+                return self.io_result(ret, message, |builder, error_block| {
+                    let byte_ptr_type = builder.byte_ptr_type();
+                    let value = builder
+                        .call_external(
+                            "getenv",
+                            byte_ptr_type.fn_type(&[byte_ptr_type.into()], false),
+                            &[name.into()],
+                        )
+                        .into_pointer_value();
+                    let failed = builder.is_null(value);
+                    builder.branch_on_failure(failed, error_block)?;
+                    let succeeded = types::IntType::bool_type().const_int(0, false);
+                    Ok((succeeded, builder.c_string_value(value)))
+                });
             }
-            Atom::PolyValue(val_table) => val_table
-                .iter()
-                .map(|(k, v)| {
-                    let t = self.type_of(*v);
-                    (k.clone(), self.inst_builder.build_alloca(t, &real_name))
-                })
-                .collect::<HashMap<_, _>>()
-                .into(),
+            Builtin::Exit => {
+                let code = self.inst_builder.build_int_truncate(
+                    args[0].into_int_value(),
+                    types::IntType::i32_type(),
+                    "code",
+                );
+                self.terminate(code)?;
+                return self.empty_constant();
+            }
+            Builtin::Print => return self.print(&arg_types[0], args[0], false),
+            Builtin::Println => return self.print(&arg_types[0], args[0], true),
+            Builtin::ReadLine => {
+                let (data_ptr, len) = self.read_line()?;
+                return Ok(self.string_value(data_ptr, len));
+            }
+            Builtin::ReadNumber => {
+                let (data_ptr, len) = self.read_line()?;
+                let message = Some("Input is not a number");
+                return self.io_result(ret, message, |builder, error_block| {
+                    let i64_type = types::IntType::i64_type();
+                    let byte_ptr_type = builder.byte_ptr_type();
+                    // The line is empty at the end of the input
+                    let is_eof = builder.cmp(CondCode::Equal, len, i64_type.const_int(0, false));
+                    builder.branch_on_failure(is_eof, error_block)?;
+
+                    let end_ptr = builder.entry_alloca(byte_ptr_type, "end_ptr")?;
+                    let errno_ptr = builder.errno_ptr();
+                    builder
+                        .inst_builder
+                        .build_store(errno_ptr, types::IntType::i32_type().const_int(0, false));
+                    let number = builder.call_external(
+                        "strtoll",
+                        i64_type.fn_type(
+                            &[
+                                byte_ptr_type.into(),
+                                byte_ptr_type.ptr_type(AddressSpace::Generic).into(),
+                                types::IntType::i32_type().into(),
+                            ],
+                            false,
+                        ),
+                        &[
+                            data_ptr.into(),
+                            end_ptr.into(),
+                            types::IntType::i32_type().const_int(10, false).into(),
+                        ],
+                    );
+                    // `errno` is set if the number is out of range
+                    let errno = builder
+                        .inst_builder
+                        .build_load(errno_ptr, "errno")
+                        .into_int_value();
+                    let out_of_range = builder.cmp(
+                        CondCode::NotEqual,
+                        errno,
+                        types::IntType::i32_type().const_int(0, false),
+                    );
+                    builder.branch_on_failure(out_of_range, error_block)?;
+
+                    // The whole line has to be consumed, which also means that it advanced
+                    let end = builder
+                        .inst_builder
+                        .build_load(end_ptr, "end")
+                        .into_pointer_value();
+                    let line_end =
+                        unsafe { builder.inst_builder.build_gep(data_ptr, &[len], "line_end") };
+                    let end = builder.inst_builder.build_ptr_to_int(end, i64_type, "end");
+                    let line_end = builder
+                        .inst_builder
+                        .build_ptr_to_int(line_end, i64_type, "line_end");
+                    let failed = builder.cmp(CondCode::NotEqual, end, line_end);
+                    Ok((failed, number))
+                });
+            }
+            Builtin::Sqrt => "llvm.sqrt.f64",
+            Builtin::Pow => "llvm.pow.f64",
+            Builtin::Floor => "llvm.floor.f64",
+            Builtin::Ceil => "llvm.ceil.f64",
+            Builtin::Exp => "llvm.exp.f64",
+            Builtin::Log => "llvm.log.f64",
+            Builtin::Sin => "llvm.sin.f64",
+            Builtin::Cos => "llvm.cos.f64",
         };
-        self.env
-            .insert(&real_name, BoundPointer::new(BindingKind::Mutable, ptr));
-        Ok(real_name)
+        let param_types: Vec<_> = args.iter().map(|arg| self.type_of(*arg)).collect();
+        let fn_type = types::FloatType::f64_type().fn_type(&param_types, false);
+        let function = self.external_function(intrinsic, fn_type);
+        Ok(self
+            .inst_builder
+            .build_call(function, args, builtin.name())
+            .try_as_basic_value()
+            .left()
+            .unwrap())
     }
 
-    pub fn bind_var(
+    /// Call the C function `name` with the native calling convention. Arguments of `Empty` are
+    /// not passed, and a function returning `Empty` is a `void` one.
+    pub fn call_extern(
         &mut self,
         name: &str,
-        val: &Atom<values::BasicValueEnum>,
-        kind: BindingKind,
-    ) -> Result<(), Error> {
+        arg_types: &[Type],
+        args: &[values::BasicValueEnum],
+        ret: &Type,
+    ) -> Result<values::BasicValueEnum, Error> {
+        let mut param_types = Vec::new();
+        let mut c_args = Vec::new();
+        for (ty, arg) in arg_types.iter().zip(args) {
+            if *ty != Type::Empty {
+                param_types.push(self.llvm_type(ty)?);
+                c_args.push(*arg);
+            }
+        }
+
+        let fn_type = match ret {
+            Type::Empty => types::VoidType::void_type().fn_type(&param_types, false),
+            _ => self.llvm_type(ret)?.fn_type(&param_types, false),
+        };
+        let function = self.external_function(name, fn_type);
+        if function.get_type() != fn_type {
+            return Err(TranslationError::ConflictingExtern {
+                name: name.to_string(),
+            }
+            .into());
+        }
+
+        let call_inst = self.inst_builder.build_call(function, &c_args, name);
+        match ret {
+            Type::Empty => self.empty_constant(),
+            _ => Ok(call_inst.try_as_basic_value().left().unwrap()),
+        }
+    }
+
+    /// Format of `printf` and the arguments for it to print a value of `ty`.
+    fn print_format(
+        &mut self,
+        ty: &Type,
+        v: values::BasicValueEnum,
+    ) -> Result<(&'static str, Vec<values::BasicValueEnum>), Error> {
+        Ok(match ty {
+            Type::Number => ("%lld", vec![v]),
+            Type::Int(t) => {
+                let v = self.cast_to(v, ty, &Type::Number)?;
+                (if t.is_signed() { "%lld" } else { "%llu" }, vec![v])
+            }
+            // Enough digits to read back the same value
+            Type::Float => ("%.17g", vec![v]),
+            Type::Boolean => {
+                let true_ptr = self
+                    .inst_builder
+                    .build_global_string_ptr("true", "true")
+                    .as_pointer_value();
+                let false_ptr = self
+                    .inst_builder
+                    .build_global_string_ptr("false", "false")
+                    .as_pointer_value();
+                let s = self.inst_builder.build_select(
+                    v.into_int_value(),
+                    true_ptr,
+                    false_ptr,
+                    "bool_str",
+                );
+                ("%s", vec![s])
+            }
+            Type::String => {
+                let (data_ptr, len) = self.string_parts(v)?;
+                let len =
+                    self.inst_builder
+                        .build_int_truncate(len, types::IntType::i32_type(), "len");
+                ("%.*s", vec![len.into(), data_ptr.into()])
+            }
+            _ => return Err(TranslationError::NotPrintable { ty: ty.clone() }.into()),
+        })
+    }
+
+    /// Write `v` to stdout with `printf`, followed by a newline if `newline` is set.
+    fn print(
+        &mut self,
+        ty: &Type,
+        v: values::BasicValueEnum,
+        newline: bool,
+    ) -> Result<values::BasicValueEnum, Error> {
+        let (format, args) = self.print_format(ty, v)?;
+        let format = if newline {
+            format!("{}\n", format)
+        } else {
+            format.to_string()
+        };
+        let format_ptr = self
+            .inst_builder
+            .build_global_string_ptr(&format, "format")
+            .as_pointer_value();
+        let printf = self.external_function(
+            "printf",
+            types::IntType::i32_type().fn_type(&[self.byte_ptr_type().into()], true),
+        );
+        let mut printf_args = vec![format_ptr.into()];
+        printf_args.extend(args);
+        self.inst_builder.build_call(printf, &printf_args, "");
+
+        // Flush the output so that it is not reordered with the one from the host
+        let fflush = self.external_function(
+            "fflush",
+            types::IntType::i32_type().fn_type(&[self.byte_ptr_type().into()], false),
+        );
+        self.inst_builder
+            .build_call(fflush, &[self.byte_ptr_type().const_null().into()], "");
+        self.empty_constant()
+    }
+
+    /// Call a builtin on files, which are `FILE` in libc. A failure is returned as `Err` with the
+    /// message of `errno`.
+    fn call_io(
+        &mut self,
+        builtin: Builtin,
+        args: &[values::BasicValueEnum],
+        ret: &Type,
+    ) -> Result<values::BasicValueEnum, Error> {
+        let i32_type = types::IntType::i32_type();
+        let i64_type = types::IntType::i64_type();
+        let byte_ptr_type = self.byte_ptr_type();
+        let fopen_type =
+            byte_ptr_type.fn_type(&[byte_ptr_type.into(), byte_ptr_type.into()], false);
+        let fread_type = i64_type.fn_type(
+            &[
+                byte_ptr_type.into(),
+                i64_type.into(),
+                i64_type.into(),
+                byte_ptr_type.into(),
+            ],
+            false,
+        );
+        let fclose_type = i32_type.fn_type(&[byte_ptr_type.into()], false);
+        let one = i64_type.const_int(1, false);
+
+        match builtin {
+            Builtin::OpenFile => {
+                let path = self.c_string(args[0])?;
+                let mode = self.c_string(args[1])?;
+                self.io_result(ret, None, |builder, _| {
+                    let file = builder
+                        .call_external("fopen", fopen_type, &[path.into(), mode.into()])
+                        .into_pointer_value();
+                    let failed = builder.is_null(file);
+                    Ok((failed, file.into()))
+                })
+            }
+            Builtin::CloseFile => self.io_result(ret, None, |builder, _| {
+                let status = builder
+                    .call_external("fclose", fclose_type, &[args[0]])
+                    .into_int_value();
+                let failed = builder.cmp(CondCode::NotEqual, status, i32_type.const_int(0, false));
+                Ok((failed, builder.empty_constant()?))
+            }),
+            Builtin::ReadFrom => self.io_result(ret, None, |builder, error_block| {
+                let size = args[1].into_int_value();
+                // A negative size is reported as an invalid argument
+                let is_negative =
+                    builder.cmp(CondCode::LessThan, size, i64_type.const_int(0, false));
+                let invalid_block = builder.create_block()?;
+                builder.branch_on_failure(is_negative, &invalid_block)?;
+                let read_block = builder.current_block()?;
+                builder.switch_to_block(&invalid_block);
+                let errno_ptr = builder.errno_ptr();
+                builder
+                    .inst_builder
+                    .build_store(errno_ptr, i32_type.const_int(EINVAL, false));
+                builder.jump(error_block);
+                builder.switch_to_block(&read_block);
+
+                let data_ptr = builder.inst_builder.build_array_malloc(
+                    types::IntType::i8_type(),
+                    size,
+                    "buffer",
+                );
+                let failed = builder.is_null(data_ptr);
+                builder.branch_on_failure(failed, error_block)?;
+                let len = builder
+                    .call_external(
+                        "fread",
+                        fread_type,
+                        &[data_ptr.into(), one.into(), size.into(), args[0]],
+                    )
+                    .into_int_value();
+                // A short read is either the end of the file or an error
+                let status = builder
+                    .call_external("ferror", fclose_type, &[args[0]])
+                    .into_int_value();
+                let failed = builder.cmp(CondCode::NotEqual, status, i32_type.const_int(0, false));
+                Ok((failed, builder.string_value(data_ptr, len)))
+            }),
+            Builtin::WriteTo => self.io_result(ret, None, |builder, _| {
+                let (data_ptr, len) = builder.string_parts(args[1])?;
+                let written = builder
+                    .call_external(
+                        "fwrite",
+                        fread_type,
+                        &[data_ptr.into(), one.into(), len.into(), args[0]],
+                    )
+                    .into_int_value();
+                let failed = builder.cmp(CondCode::NotEqual, written, len);
+                Ok((failed, builder.empty_constant()?))
+            }),
+            Builtin::ReadFile => {
+                let path = self.c_string(args[0])?;
+                self.io_result(ret, None, |builder, error_block| {
+                    let file = builder.open_file(path, "rb", error_block)?;
+                    // The file is closed before jumping to the error block once it is opened
+                    let close_block = builder.create_block()?;
+                    let seek_block = builder.create_block()?;
+                    builder.jump(&seek_block);
+                    builder.switch_to_block(&close_block);
+                    builder.call_external("fclose", fclose_type, &[file.into()]);
+                    builder.jump(error_block);
+                    builder.switch_to_block(&seek_block);
+
+                    // The size is found by seeking to the end
+                    let fseek_type = i32_type.fn_type(
+                        &[byte_ptr_type.into(), i64_type.into(), i32_type.into()],
+                        false,
+                    );
+                    let seek_end = i32_type.const_int(2, false);
+                    let seek_set = i32_type.const_int(0, false);
+                    let zero = i64_type.const_int(0, false);
+                    let status = builder
+                        .call_external(
+                            "fseek",
+                            fseek_type,
+                            &[file.into(), zero.into(), seek_end.into()],
+                        )
+                        .into_int_value();
+                    let failed =
+                        builder.cmp(CondCode::NotEqual, status, i32_type.const_int(0, false));
+                    builder.branch_on_failure(failed, &close_block)?;
+                    let size = builder
+                        .call_external(
+                            "ftell",
+                            i64_type.fn_type(&[byte_ptr_type.into()], false),
+                            &[file.into()],
+                        )
+                        .into_int_value();
+                    let failed = builder.cmp(CondCode::LessThan, size, zero);
+                    builder.branch_on_failure(failed, &close_block)?;
+                    let status = builder
+                        .call_external(
+                            "fseek",
+                            fseek_type,
+                            &[file.into(), zero.into(), seek_set.into()],
+                        )
+                        .into_int_value();
+                    let failed =
+                        builder.cmp(CondCode::NotEqual, status, i32_type.const_int(0, false));
+                    builder.branch_on_failure(failed, &close_block)?;
+
+                    let data_ptr = builder.inst_builder.build_array_malloc(
+                        types::IntType::i8_type(),
+                        size,
+                        "contents",
+                    );
+                    let failed = builder.is_null(data_ptr);
+                    builder.branch_on_failure(failed, &close_block)?;
+                    let len = builder
+                        .call_external(
+                            "fread",
+                            fread_type,
+                            &[data_ptr.into(), one.into(), size.into(), file.into()],
+                        )
+                        .into_int_value();
+                    builder.call_external("fclose", fclose_type, &[file.into()]);
+                    let failed = builder.cmp(CondCode::NotEqual, len, size);
+                    Ok((failed, builder.string_value(data_ptr, len)))
+                })
+            }
+            Builtin::WriteFile => {
+                let path = self.c_string(args[0])?;
+                self.io_result(ret, None, |builder, error_block| {
+                    let file = builder.open_file(path, "wb", error_block)?;
+                    let (data_ptr, len) = builder.string_parts(args[1])?;
+                    let written = builder
+                        .call_external(
+                            "fwrite",
+                            fread_type,
+                            &[data_ptr.into(), one.into(), len.into(), file.into()],
+                        )
+                        .into_int_value();
+                    let status = builder
+                        .call_external("fclose", fclose_type, &[file.into()])
+                        .into_int_value();
+                    let write_failed = builder.cmp(CondCode::NotEqual, written, len);
+                    let close_failed =
+                        builder.cmp(CondCode::NotEqual, status, i32_type.const_int(0, false));
+                    let failed =
+                        builder
+                            .inst_builder
+                            .build_or(write_failed, close_failed, "failed");
+                    Ok((failed, builder.empty_constant()?))
+                })
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Build `Ok` of the value from `eval`, or `Err` if it fails. `eval` returns whether it
+    /// failed with the value, and may also give up in the middle by jumping to the error block
+    /// given to it. The error is described with `message`, or with the one of `errno` if not
+    /// given.
+    fn io_result<F>(
+        &mut self,
+        ty: &Type,
+        message: Option<&str>,
+        eval: F,
+    ) -> Result<values::BasicValueEnum, Error>
+    where
+        F: FnOnce(&mut Self, &Block) -> Result<(values::IntValue, values::BasicValueEnum), Error>,
+    {
+        let result_ptr = self.entry_alloca(self.llvm_type(ty)?, "io_result")?;
+        let error_block = self.create_block()?;
+        let done_block = self.create_block()?;
+
+        let (failed, value) = eval(self, &error_block)?;
+        self.branch_on_failure(failed, &error_block)?;
+        let tag = self.constructor_tag("Ok")?;
+        let ok = self.data_constant(ty, tag, &[value])?;
+        self.inst_builder.build_store(result_ptr, ok);
+        self.jump(&done_block);
+
+        self.switch_to_block(&error_block);
+        let message = match message {
+            Some(message) => self.string_constant(message)?,
+            None => self.errno_message(),
+        };
+        let tag = self.constructor_tag("Err")?;
+        let err = self.data_constant(ty, tag, &[message])?;
+        self.inst_builder.build_store(result_ptr, err);
+        self.jump(&done_block);
+
+        self.switch_to_block(&done_block);
+        Ok(self.inst_builder.build_load(result_ptr, "io_result"))
+    }
+
+    /// Description of the last error in libc with `strerror`.
+    fn errno_message(&mut self) -> values::BasicValueEnum {
+        let errno_ptr = self.errno_ptr();
+        let errno = self.inst_builder.build_load(errno_ptr, "errno");
+        let message = self
+            .call_external(
+                "strerror",
+                self.byte_ptr_type()
+                    .fn_type(&[types::IntType::i32_type().into()], false),
+                &[errno],
+            )
+            .into_pointer_value();
+        self.c_string_value(message)
+    }
+
+    /// Pointer to `errno` of the current thread.
+    fn errno_ptr(&mut self) -> values::PointerValue {
+        self.call_external(
+            libc_symbol("__errno_location"),
+            types::IntType::i32_type()
+                .ptr_type(AddressSpace::Generic)
+                .fn_type(&[], false),
+            &[],
+        )
+        .into_pointer_value()
+    }
+
+    /// String with the contents of the NUL-terminated `ptr`, which is not copied.
+    fn c_string_value(&mut self, ptr: values::PointerValue) -> values::BasicValueEnum {
+        let len = self
+            .call_external(
+                "strlen",
+                types::IntType::i64_type().fn_type(&[self.byte_ptr_type().into()], false),
+                &[ptr.into()],
+            )
+            .into_int_value();
+        self.string_value(ptr, len)
+    }
+
+    /// Declare a global variable used only in the module, which starts with `initial`.
+    fn private_global(&self, name: &str, initial: values::BasicValueEnum) -> values::PointerValue {
+        self.module
+            .get_global(name)
+            .unwrap_or_else(|| {
+                let global = self.module.add_global(self.type_of(initial), None, name);
+                global.set_linkage(Linkage::Private);
+                global.set_initializer(&initial);
+                global
+            })
+            .as_pointer_value()
+    }
+
+    /// Global variables of the command-line arguments, which are set by `set_args`.
+    fn args_globals(&self) -> (values::PointerValue, values::PointerValue) {
+        let argc = types::IntType::i32_type().const_int(0, false);
+        let argv = self
+            .byte_ptr_type()
+            .ptr_type(AddressSpace::Generic)
+            .const_null();
+        (
+            self.private_global("_argc", argc.into()),
+            self.private_global("_argv", argv.into()),
+        )
+    }
+
+    /// Global variables of the `jmp_buf` to which `terminate` jumps, the exit status and the
+    /// flag which is set when the program is terminated.
+    fn program_globals(
+        &self,
+    ) -> (
+        values::PointerValue,
+        values::PointerValue,
+        values::PointerValue,
+    ) {
+        let zero = types::IntType::i32_type().const_int(0, false);
+        (
+            self.private_global("_top", self.byte_ptr_type().const_null().into()),
+            self.private_global("_status", zero.into()),
+            self.private_global("_terminated", zero.into()),
+        )
+    }
+
+    /// Save the context of the compiled function for `terminate`, and return the block which is
+    /// continued from when the program is terminated.
+    pub fn enter_program(&mut self) -> Result<Block, Error> {
+        let (top_ptr, _, _) = self.program_globals();
+        let buf = self.jmp_buf()?;
+        self.inst_builder.build_store(top_ptr, buf);
+        // A handler may be left by the previous run which is terminated inside of `try`
+        let (handler_ptr, _) = self.exception_globals();
+        self.inst_builder
+            .build_store(handler_ptr, self.byte_ptr_type().const_null());
+
+        let terminated = self.setjmp(buf);
+        let terminate_block = self.create_block()?;
+        let body_block = self.create_block()?;
+        self.brz(terminated.into(), &terminate_block, &body_block)?;
+        self.switch_to_block(&body_block);
+        Ok(terminate_block)
+    }
+
+    /// Return the exit status from `terminate_block`, and define the function named
+    /// `terminated_name` which tells whether the program is terminated.
+    pub fn exit_program(&mut self, terminate_block: &Block, terminated_name: &str) {
+        self.finish_var_accesses();
+        let (_, status_ptr, terminated_ptr) = self.program_globals();
+        let i32_type = types::IntType::i32_type();
+        self.switch_to_block(terminate_block);
+        self.inst_builder
+            .build_store(terminated_ptr, i32_type.const_int(1, false));
+        let status = self
+            .inst_builder
+            .build_load(status_ptr, "status")
+            .into_int_value();
+        let status =
+            self.inst_builder
+                .build_int_s_extend(status, types::IntType::i64_type(), "status");
+        self.inst_builder.build_return(Some(&status));
+
+        let function =
+            self.module
+                .add_function(terminated_name, i32_type.fn_type(&[], false), None);
+        let basic_block = self
+            .module
+            .get_context()
+            .append_basic_block(&function, "entry");
+        self.inst_builder.position_at_end(&basic_block);
+        let terminated = self.inst_builder.build_load(terminated_ptr, "terminated");
+        self.inst_builder.build_return(Some(&terminated));
+    }
+
+    /// Terminate the program with `status`, returning it from the compiled function.
+    fn terminate(&mut self, status: values::IntValue) -> Result<(), Error> {
+        let (top_ptr, status_ptr, _) = self.program_globals();
+        self.inst_builder.build_store(status_ptr, status);
+        let top = self
+            .inst_builder
+            .build_load(top_ptr, "top")
+            .into_pointer_value();
+        self.longjmp(top);
+        self.start_dead_block()
+    }
+
+    /// Allocate a `jmp_buf`, which is large enough for the supported platforms.
+    fn jmp_buf(&mut self) -> Result<values::PointerValue, Error> {
+        let buf = self.entry_alloca(types::IntType::i64_type().array_type(64), "jmp_buf")?;
+        Ok(self
+            .inst_builder
+            .build_pointer_cast(buf, self.byte_ptr_type(), "jmp_buf_erase"))
+    }
+
+    /// Save the context to `buf` with `setjmp`. The result is true when `longjmp` jumps back.
+    fn setjmp(&mut self, buf: values::PointerValue) -> values::IntValue {
+        let i32_type = types::IntType::i32_type();
+        let setjmp = self.module.get_function("_setjmp").unwrap_or_else(|| {
+            let setjmp = self.external_function(
+                "_setjmp",
+                i32_type.fn_type(&[self.byte_ptr_type().into()], false),
+            );
+            // Otherwise the values in registers may be assumed to be kept across `longjmp`
+            let kind = Attribute::get_named_enum_kind_id("returns_twice");
+            let attribute = self.module.get_context().create_enum_attribute(kind, 0);
+            setjmp.add_attribute(AttributeLoc::Function, attribute);
+            setjmp
+        });
+        let jumped = self
+            .inst_builder
+            .build_call(setjmp, &[buf.into()], "jumped")
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        self.cmp(CondCode::NotEqual, jumped, i32_type.const_int(0, false))
+    }
+
+    /// Jump to the context saved in `buf` with `longjmp`.
+    fn longjmp(&mut self, buf: values::PointerValue) {
+        let i32_type = types::IntType::i32_type();
+        let longjmp = self.external_function(
+            "_longjmp",
+            types::VoidType::void_type()
+                .fn_type(&[self.byte_ptr_type().into(), i32_type.into()], false),
+        );
+        self.inst_builder.build_call(
+            longjmp,
+            &[buf.into(), i32_type.const_int(1, false).into()],
+            "",
+        );
+        self.unreachable();
+    }
+
+    /// Global variables of the `jmp_buf` of the innermost `try` and the thrown value, which
+    /// are null while they are not set.
+    fn exception_globals(&self) -> (values::PointerValue, values::PointerValue) {
+        let null = self.byte_ptr_type().const_null();
+        (
+            self.private_global("_handler", null.into()),
+            self.private_global("_exception", null.into()),
+        )
+    }
+
+    /// Set a handler to which the values thrown in the following code are passed, jumping to
+    /// `catch_block`. The previous handler is returned to be restored with `exit_try`.
+    pub fn enter_try(&mut self, catch_block: &Block) -> Result<values::BasicValueEnum, Error> {
+        let (handler_ptr, _) = self.exception_globals();
+        let previous = self
+            .inst_builder
+            .build_load(handler_ptr, "previous_handler");
+        let buf = self.jmp_buf()?;
+        self.inst_builder.build_store(handler_ptr, buf);
+        self.has_try = true;
+
+        let thrown = self.setjmp(buf);
+        let body_block = self.create_block()?;
+        self.brz(thrown.into(), catch_block, &body_block)?;
+        self.switch_to_block(&body_block);
+        Ok(previous)
+    }
+
+    pub fn exit_try(&mut self, previous: values::BasicValueEnum) {
+        let (handler_ptr, _) = self.exception_globals();
+        self.inst_builder.build_store(handler_ptr, previous);
+    }
+
+    /// The value passed to the handler by `throw`.
+    pub fn caught_value(&mut self) -> Result<values::BasicValueEnum, Error> {
+        let (_, exception_ptr) = self.exception_globals();
+        let payload = self
+            .inst_builder
+            .build_load(exception_ptr, "exception")
+            .into_pointer_value();
+        let value_type = self.llvm_type(&self.exception)?;
+        let payload = self.inst_builder.build_pointer_cast(
+            payload,
+            value_type.ptr_type(AddressSpace::Generic),
+            "exception_ptr",
+        );
+        Ok(self.inst_builder.build_load(payload, "exception"))
+    }
+
+    /// Pass `value` to the innermost handler with `longjmp`. Without handlers, the program is
+    /// terminated with a failure status after printing the value if possible.
+    pub fn throw(&mut self, value: values::BasicValueEnum) -> Result<(), Error> {
+        let (handler_ptr, exception_ptr) = self.exception_globals();
+        // TODO: Fix memory leak
+        let payload = self
+            .inst_builder
+            .build_malloc(self.type_of(value), "exception");
+        self.inst_builder.build_store(payload, value);
+        let payload =
+            self.inst_builder
+                .build_pointer_cast(payload, self.byte_ptr_type(), "exception_erase");
+        self.inst_builder.build_store(exception_ptr, payload);
+
+        let handler = self
+            .inst_builder
+            .build_load(handler_ptr, "handler")
+            .into_pointer_value();
+        let uncaught_block = self.create_block()?;
+        let jump_block = self.create_block()?;
+        let uncaught = self.is_null(handler);
+        self.brz(uncaught.into(), &uncaught_block, &jump_block)?;
+
+        self.switch_to_block(&jump_block);
+        self.longjmp(handler);
+
+        self.switch_to_block(&uncaught_block);
+        let i32_type = types::IntType::i32_type();
+        let exception = self.exception.clone();
+        let (format, args) = if Builtin::is_printable(&exception) {
+            let (format, args) = self.print_format(&exception, value)?;
+            (format!("Uncaught exception: {}\n", format), args)
+        } else {
+            ("Uncaught exception\n".to_string(), Vec::new())
+        };
+        self.print_error(&format, &args);
+        self.terminate(i32_type.const_int(1, false))
+    }
+
+    /// Print a message to stderr with `fprintf`, formatting `args` with `format`.
+    fn print_error(&mut self, format: &str, args: &[values::BasicValueEnum]) {
+        let format_ptr = self
+            .inst_builder
+            .build_global_string_ptr(format, "format")
+            .as_pointer_value();
+        let stderr_ptr = self.external_global(libc_symbol("stderr"), self.byte_ptr_type().into());
+        let stderr = self.inst_builder.build_load(stderr_ptr, "stderr");
+        let fprintf = self.external_function(
+            "fprintf",
+            types::IntType::i32_type().fn_type(
+                &[self.byte_ptr_type().into(), self.byte_ptr_type().into()],
+                true,
+            ),
+        );
+        let mut fprintf_args = vec![stderr, format_ptr.into()];
+        fprintf_args.extend(args);
+        self.inst_builder.build_call(fprintf, &fprintf_args, "");
+    }
+
+    /// Keep the command-line arguments given to the compiled function for `args`.
+    pub fn set_args(&mut self, argc: values::IntValue, argv: values::PointerValue) {
+        let (argc_ptr, argv_ptr) = self.args_globals();
+        self.inst_builder.build_store(argc_ptr, argc);
+        self.inst_builder.build_store(argv_ptr, argv);
+    }
+
+    /// Build an array of the command-line arguments, which are copied to strings one by one.
+    /// The array is empty unless the arguments are given with `set_args`.
+    fn args(&mut self) -> Result<values::BasicValueEnum, Error> {
+        let i64_type = types::IntType::i64_type();
+        let (argc_ptr, argv_ptr) = self.args_globals();
+        let argc = self
+            .inst_builder
+            .build_load(argc_ptr, "argc")
+            .into_int_value();
+        let len = self.inst_builder.build_int_s_extend(argc, i64_type, "len");
+        let argv = self
+            .inst_builder
+            .build_load(argv_ptr, "argv")
+            .into_pointer_value();
+        // TODO: Fix memory leak
+        let data_ptr = self
+            .inst_builder
+            .build_array_malloc(self.string_type(), len, "args");
+
+        let index_ptr = self.entry_alloca(i64_type, "index")?;
+        self.inst_builder
+            .build_store(index_ptr, i64_type.const_int(0, false));
+        let header_block = self.create_block()?;
+        let body_block = self.create_block()?;
+        let exit_block = self.create_block()?;
+        self.jump(&header_block);
+
+        self.switch_to_block(&header_block);
+        let index = self
+            .inst_builder
+            .build_load(index_ptr, "index")
+            .into_int_value();
+        let cond = self.cmp(CondCode::LessThan, index, len);
+        self.brz(cond.into(), &body_block, &exit_block)?;
+
+        self.switch_to_block(&body_block);
+        let arg = self.load(argv, index)?.into_pointer_value();
+        let arg = self.c_string_value(arg);
+        self.store(arg, data_ptr, index)?;
+        let next = self
+            .inst_builder
+            .build_int_add(index, i64_type.const_int(1, false), "next");
+        self.inst_builder.build_store(index_ptr, next);
+        self.jump(&header_block);
+
+        self.switch_to_block(&exit_block);
+        let array_type =
+            types::StructType::struct_type(&[data_ptr.get_type().into(), i64_type.into()], false);
+        let array = self.insert_value(array_type.get_undef(), data_ptr.into(), 0);
+        Ok(self.insert_value(array, len.into(), 1).into())
+    }
+
+    /// Jump to `error_block` if `failed` is set, and continue in a new block otherwise.
+    fn branch_on_failure(
+        &mut self,
+        failed: values::IntValue,
+        error_block: &Block,
+    ) -> Result<(), Error> {
+        let continue_block = self.create_block()?;
+        self.brz(failed.into(), error_block, &continue_block)?;
+        self.switch_to_block(&continue_block);
+        Ok(())
+    }
+
+    /// Open the file at the NUL-terminated `path`, jumping to the error block on failures.
+    fn open_file(
+        &mut self,
+        path: values::PointerValue,
+        mode: &str,
+        error_block: &Block,
+    ) -> Result<values::PointerValue, Error> {
+        let mode = self
+            .inst_builder
+            .build_global_string_ptr(mode, "mode")
+            .as_pointer_value();
+        let byte_ptr_type = self.byte_ptr_type();
+        let file = self
+            .call_external(
+                "fopen",
+                byte_ptr_type.fn_type(&[byte_ptr_type.into(), byte_ptr_type.into()], false),
+                &[path.into(), mode.into()],
+            )
+            .into_pointer_value();
+        let failed = self.is_null(file);
+        self.branch_on_failure(failed, error_block)?;
+        Ok(file)
+    }
+
+    fn is_null(&mut self, ptr: values::PointerValue) -> values::IntValue {
+        let i64_type = types::IntType::i64_type();
+        let addr = self.inst_builder.build_ptr_to_int(ptr, i64_type, "addr");
+        self.cmp(CondCode::Equal, addr, i64_type.const_int(0, false))
+    }
+
+    /// Copy `string` to a NUL-terminated one to be passed to libc.
+    fn c_string(&mut self, string: values::BasicValueEnum) -> Result<values::PointerValue, Error> {
+        let (data_ptr, len) = self.string_parts(string)?;
+        let size = self.inst_builder.build_int_add(
+            len,
+            types::IntType::i64_type().const_int(1, false),
+            "size",
+        );
+        // TODO: Fix memory leak
+        let ptr = self
+            .inst_builder
+            .build_array_malloc(types::IntType::i8_type(), size, "c_str");
+        self.call_external(
+            "memcpy",
+            self.byte_ptr_type().fn_type(
+                &[
+                    self.byte_ptr_type().into(),
+                    self.byte_ptr_type().into(),
+                    types::IntType::i64_type().into(),
+                ],
+                false,
+            ),
+            &[ptr.into(), data_ptr.into(), len.into()],
+        );
+        let end_ptr = unsafe { self.inst_builder.build_gep(ptr, &[len], "end") };
+        self.inst_builder
+            .build_store(end_ptr, types::IntType::i8_type().const_int(0, false));
+        Ok(ptr)
+    }
+
+    /// Call the function `name` defined outside of the module, which returns a value.
+    fn call_external(
+        &mut self,
+        name: &str,
+        ty: types::FunctionType,
+        args: &[values::BasicValueEnum],
+    ) -> values::BasicValueEnum {
+        let function = self.external_function(name, ty);
+        self.inst_builder
+            .build_call(function, args, name)
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+    }
+
+    /// Read a line from stdin with `getline`, without the trailing newline. The data is
+    /// NUL-terminated, and is empty at the end of the input.
+    fn read_line(&mut self) -> Result<(values::PointerValue, values::IntValue), Error> {
+        let i64_type = types::IntType::i64_type();
+        let byte_ptr_type = self.byte_ptr_type();
+
+        let line_ptr = self.entry_alloca(byte_ptr_type, "line_ptr")?;
+        self.inst_builder
+            .build_store(line_ptr, byte_ptr_type.const_null());
+        let capacity_ptr = self.entry_alloca(i64_type, "capacity")?;
+        self.inst_builder
+            .build_store(capacity_ptr, i64_type.const_int(0, false));
+        let stdin_ptr = self.external_global(libc_symbol("stdin"), byte_ptr_type.into());
+        let stdin = self.inst_builder.build_load(stdin_ptr, "stdin");
+
+        let getline = self.external_function(
+            "getline",
+            i64_type.fn_type(
+                &[
+                    byte_ptr_type.ptr_type(AddressSpace::Generic).into(),
+                    i64_type.ptr_type(AddressSpace::Generic).into(),
+                    byte_ptr_type.into(),
+                ],
+                false,
+            ),
+        );
+        let read = self
+            .inst_builder
+            .build_call(
+                getline,
+                &[line_ptr.into(), capacity_ptr.into(), stdin],
+                "read",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+
+        // `getline` returns -1 at the end of the input
+        let len_ptr = self.entry_alloca(i64_type, "len_ptr")?;
+        self.inst_builder
+            .build_store(len_ptr, i64_type.const_int(0, false));
+        let zero = i64_type.const_int(0, false);
+        let is_read = self.cmp(CondCode::GreaterThan, read, zero);
+        let strip_block = self.create_block()?;
+        let done_block = self.create_block()?;
+        self.brz(is_read.into(), &strip_block, &done_block)?;
+
+        self.switch_to_block(&strip_block);
+        let data_ptr = self
+            .inst_builder
+            .build_load(line_ptr, "line")
+            .into_pointer_value();
+        let last_index =
+            self.inst_builder
+                .build_int_sub(read, i64_type.const_int(1, false), "last_index");
+        let last = self.load(data_ptr, last_index)?.into_int_value();
+        let newline = types::IntType::i8_type().const_int(u64::from(b'\n'), false);
+        let is_newline = self.cmp(CondCode::Equal, last, newline);
+        let len = self
+            .inst_builder
+            .build_select(is_newline, last_index, read, "len");
+        self.inst_builder.build_store(len_ptr, len);
+        self.jump(&done_block);
+
+        self.switch_to_block(&done_block);
+        let len = self
+            .inst_builder
+            .build_load(len_ptr, "len")
+            .into_int_value();
+        let data_ptr = self
+            .inst_builder
+            .build_load(line_ptr, "line")
+            .into_pointer_value();
+        let empty_ptr = self
+            .inst_builder
+            .build_global_string_ptr("", "empty")
+            .as_pointer_value();
+        let data_ptr = self
+            .inst_builder
+            .build_select(is_read, data_ptr, empty_ptr, "data")
+            .into_pointer_value();
+        Ok((data_ptr, len))
+    }
+
+    /// Apply `op` to strings, which are UTF-8 bytes; `+` concatenates them, comparison is
+    /// lexicographic and indexing picks up a byte as `U8`.
+    pub fn string_op(
+        &mut self,
+        op: Operator,
+        lhs: values::BasicValueEnum,
+        rhs: values::BasicValueEnum,
+    ) -> Result<values::BasicValueEnum, Error> {
+        let (lhs_data, lhs_len) = self.string_parts(lhs)?;
+        if let Operator::Index = op {
+            self.check_bounds(rhs.into_int_value(), lhs_len)?;
+            return self.load(lhs_data, rhs.into_int_value());
+        }
+
+        let (rhs_data, rhs_len) = self.string_parts(rhs)?;
+        let cc = match op {
+            Operator::Add => return Ok(self.concat_strings(lhs_data, lhs_len, rhs_data, rhs_len)),
+            Operator::Lt => CondCode::LessThan,
+            Operator::Gt => CondCode::GreaterThan,
+            Operator::Le => CondCode::LessThanOrEqual,
+            Operator::Ge => CondCode::GreaterThanOrEqual,
+            Operator::Eq => CondCode::Equal,
+            Operator::Ne => CondCode::NotEqual,
+            _ => return Err(TranslationError::InvalidType.into()),
+        };
+
+        // Compare the common prefix first, and then the lengths
+        let i64_type = types::IntType::i64_type();
+        let is_shorter = self.cmp(CondCode::LessThan, lhs_len, rhs_len);
+        let common_len = self
+            .inst_builder
+            .build_select(is_shorter, lhs_len, rhs_len, "common_len")
+            .into_int_value();
+        let memcmp = self.external_function(
+            "memcmp",
+            types::IntType::i32_type().fn_type(
+                &[
+                    self.byte_ptr_type().into(),
+                    self.byte_ptr_type().into(),
+                    i64_type.into(),
+                ],
+                false,
+            ),
+        );
+        let prefix_order = self
+            .inst_builder
+            .build_call(
+                memcmp,
+                &[lhs_data.into(), rhs_data.into(), common_len.into()],
+                "memcmp",
+            )
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        let prefix_order =
+            self.inst_builder
+                .build_int_s_extend(prefix_order, i64_type, "prefix_order");
+        let len_order = self
+            .inst_builder
+            .build_int_sub(lhs_len, rhs_len, "len_order");
+        let zero = i64_type.const_int(0, false);
+        let is_same_prefix = self.cmp(CondCode::Equal, prefix_order, zero);
+        let order = self
+            .inst_builder
+            .build_select(is_same_prefix, len_order, prefix_order, "order")
+            .into_int_value();
+        Ok(self.cmp(cc, order, zero).into())
+    }
+
+    fn concat_strings(
+        &mut self,
+        lhs_data: values::PointerValue,
+        lhs_len: values::IntValue,
+        rhs_data: values::PointerValue,
+        rhs_len: values::IntValue,
+    ) -> values::BasicValueEnum {
+        let i64_type = types::IntType::i64_type();
+        let len = self.inst_builder.build_int_add(lhs_len, rhs_len, "len");
+        // TODO: Fix memory leak
+        let data_ptr = self
+            .inst_builder
+            .build_array_malloc(types::IntType::i8_type(), len, "str");
+        let memcpy = self.external_function(
+            "memcpy",
+            self.byte_ptr_type().fn_type(
+                &[
+                    self.byte_ptr_type().into(),
+                    self.byte_ptr_type().into(),
+                    i64_type.into(),
+                ],
+                false,
+            ),
+        );
+        self.inst_builder.build_call(
+            memcpy,
+            &[data_ptr.into(), lhs_data.into(), lhs_len.into()],
+            "",
+        );
+        let tail_ptr = unsafe { self.inst_builder.build_gep(data_ptr, &[lhs_len], "tail") };
+        self.inst_builder.build_call(
+            memcpy,
+            &[tail_ptr.into(), rhs_data.into(), rhs_len.into()],
+            "",
+        );
+        self.string_value(data_ptr, len)
+    }
+
+    fn string_type(&self) -> types::StructType {
+        types::StructType::struct_type(
+            &[
+                self.byte_ptr_type().into(),
+                types::IntType::i64_type().into(),
+            ],
+            false,
+        )
+    }
+
+    fn byte_ptr_type(&self) -> types::PointerType {
+        types::IntType::i8_type().ptr_type(AddressSpace::Generic)
+    }
+
+    fn string_value(
+        &self,
+        data_ptr: values::PointerValue,
+        len: values::IntValue,
+    ) -> values::BasicValueEnum {
+        let string = self.insert_value(self.string_type().get_undef(), data_ptr.into(), 0);
+        self.insert_value(string, len.into(), 1).into()
+    }
+
+    fn string_parts(
+        &mut self,
+        string: values::BasicValueEnum,
+    ) -> Result<(values::PointerValue, values::IntValue), Error> {
+        let data_ptr = self.array_data_ptr(string)?;
+        let len = self.extract_element(string, 1)?.into_int_value();
+        Ok((data_ptr, len))
+    }
+
+    /// Declare a function defined outside of the module, such as one in libc.
+    fn external_function(&self, name: &str, ty: types::FunctionType) -> values::FunctionValue {
+        self.module
+            .get_function(name)
+            .unwrap_or_else(|| self.module.add_function(name, ty, Some(Linkage::External)))
+    }
+
+    /// Declare a global variable defined outside of the module, such as `stdin` in libc.
+    fn external_global(&self, name: &str, ty: types::BasicTypeEnum) -> values::PointerValue {
+        self.module
+            .get_global(name)
+            .unwrap_or_else(|| self.module.add_global(ty, None, name))
+            .as_pointer_value()
+    }
+
+    pub fn cmp(
+        &mut self,
+        cmp_type: CondCode,
+        lhs: values::IntValue,
+        rhs: values::IntValue,
+    ) -> values::IntValue {
+        let cc = match cmp_type {
+            CondCode::Equal => IntPredicate::EQ,
+            CondCode::NotEqual => IntPredicate::NE,
+            CondCode::LessThan => IntPredicate::SLT,
+            CondCode::GreaterThanOrEqual => IntPredicate::SGE,
+            CondCode::GreaterThan => IntPredicate::SGT,
+            CondCode::LessThanOrEqual => IntPredicate::SLE,
+        };
+
+        self.inst_builder.build_int_compare(cc, lhs, rhs, "cmp")
+    }
+
+    /// Ordering comparison of integers, which are taken as unsigned ones unless `signed`.
+    fn int_cmp(
+        &mut self,
+        cmp_type: CondCode,
+        lhs: values::IntValue,
+        rhs: values::IntValue,
+        signed: bool,
+    ) -> values::IntValue {
+        if signed {
+            return self.cmp(cmp_type, lhs, rhs);
+        }
+
+        let cc = match cmp_type {
+            CondCode::Equal => IntPredicate::EQ,
+            CondCode::NotEqual => IntPredicate::NE,
+            CondCode::LessThan => IntPredicate::ULT,
+            CondCode::GreaterThanOrEqual => IntPredicate::UGE,
+            CondCode::GreaterThan => IntPredicate::UGT,
+            CondCode::LessThanOrEqual => IntPredicate::ULE,
+        };
+        self.inst_builder.build_int_compare(cc, lhs, rhs, "cmp")
+    }
+
+    pub fn index(
+        &mut self,
+        lhs: values::BasicValueEnum,
+        rhs: values::BasicValueEnum,
+    ) -> Result<values::BasicValueEnum, Error> {
+        let data_ptr = self.array_data_ptr(lhs)?;
+        let len = self.array_len(lhs)?;
+        self.check_bounds(rhs.into_int_value(), len)?;
+        self.load(data_ptr, rhs.into_int_value())
+    }
+
+    pub fn index_ptr(
+        &mut self,
+        array: values::BasicValueEnum,
+        index: values::BasicValueEnum,
+    ) -> Result<values::PointerValue, Error> {
+        let data_ptr = self.array_data_ptr(array)?;
+        let len = self.array_len(array)?;
+        self.check_bounds(index.into_int_value(), len)?;
+        Ok(unsafe {
+            self.inst_builder
+                .build_gep(data_ptr, &[index.into_int_value()], "elem_ptr")
+        })
+    }
+
+    fn array_data_ptr(
+        &mut self,
+        array: values::BasicValueEnum,
+    ) -> Result<values::PointerValue, Error> {
+        match array {
+            values::BasicValueEnum::StructValue(array) => Ok(self
+                .inst_builder
+                .build_extract_value(array, 0, "data_ptr")
+                .unwrap()
+                .into_pointer_value()),
+            _ => Err(TranslationError::InvalidType.into()),
+        }
+    }
+
+    fn array_len(&mut self, array: values::BasicValueEnum) -> Result<values::IntValue, Error> {
+        match array {
+            values::BasicValueEnum::StructValue(array) => Ok(self
+                .inst_builder
+                .build_extract_value(array, 1, "len")
+                .unwrap()
+                .into_int_value()),
+            _ => Err(TranslationError::InvalidType.into()),
+        }
+    }
+
+    /// Terminate the program with a failure status unless `0 <= index < len`.
+    fn check_bounds(
+        &mut self,
+        index: values::IntValue,
+        len: values::IntValue,
+    ) -> Result<(), Error> {
+        // Negative indices are greater than any length as unsigned integers
+        let in_bounds = self.int_cmp(CondCode::LessThan, index, len, false);
+        let error_block = self.create_block()?;
+        let ok_block = self.create_block()?;
+        self.brz(in_bounds.into(), &ok_block, &error_block)?;
+
+        self.switch_to_block(&error_block);
+        self.print_error(
+            "Index out of bounds: the length is %lld but the index is %lld\n",
+            &[len.into(), index.into()],
+        );
+        self.terminate(types::IntType::i32_type().const_int(1, false))?;
+        self.jump(&ok_block);
+
+        self.switch_to_block(&ok_block);
+        Ok(())
+    }
+
+    pub(crate) fn declare_mut_var(
+        &mut self,
+        name: &str,
+        base_value: &Atom<values::BasicValueEnum>,
+        unique: bool,
+    ) -> Result<String, Error> {
+        let real_name = if unique {
+            self.env.unique_name(name)
+        } else {
+            name.to_string()
+        };
+        let ptr = match base_value {
+            Atom::LLVMValue(val) => {
+                let t = self.type_of(*val);
+                self.entry_alloca(t, &real_name)?.into()
+            }
+            Atom::PolyValue(val_table) => val_table
+                .iter()
+                .map(|(k, v)| {
+                    let t = self.type_of(*v);
+                    Ok((k.clone(), self.entry_alloca(t, &real_name)?))
+                })
+                .collect::<Result<HashMap<_, _>, Error>>()?
+                .into(),
+        };
+        self.env
+            .insert(&real_name, BoundPointer::new(BindingKind::Mutable, ptr));
+        Ok(real_name)
+    }
+
+    pub fn bind_var(
+        &mut self,
+        name: &str,
+        val: &Atom<values::BasicValueEnum>,
+        kind: BindingKind,
+    ) -> Result<(), Error> {
         let ptr = match val {
-            Atom::LLVMValue(val) => self.store_mono_var(name, *val).into(),
+            Atom::LLVMValue(val) => self.store_mono_var(name, *val, kind)?.into(),
             Atom::PolyValue(val_table) => val_table
                 .iter()
-                .map(|(k, v)| (k.clone(), self.store_mono_var(name, *v)))
-                .collect::<HashMap<_, _>>()
+                .map(|(k, v)| Ok((k.clone(), self.store_mono_var(name, *v, kind)?)))
+                .collect::<Result<HashMap<_, _>, Error>>()?
                 .into(),
         };
         self.env.insert(name, BoundPointer::new(kind, ptr));
         Ok(())
     }
 
-    fn store_mono_var(&mut self, name: &str, val: values::BasicValueEnum) -> values::PointerValue {
+    fn store_mono_var(
+        &mut self,
+        name: &str,
+        val: values::BasicValueEnum,
+        kind: BindingKind,
+    ) -> Result<values::PointerValue, Error> {
         let llvm_type = self.type_of(val);
-        let variable = self.inst_builder.build_alloca(llvm_type, name);
-        self.inst_builder.build_store(variable, val);
-        variable
+        let variable = self.entry_alloca(llvm_type, name)?;
+        self.store_var(variable, val, kind);
+        Ok(variable)
     }
 
     pub fn assign_var(
@@ -374,17 +2218,16 @@ impl Builder {
             return Err(TranslationError::ImmutableAssign.into());
         }
 
-        match var.ptr_value() {
+        match var.ptr_value().clone() {
             Atom::LLVMValue(var) => {
-                self.inst_builder
-                    .build_store(*var, val.clone().expect_value()?);
+                self.store_var(var, val.clone().expect_value()?, BindingKind::Mutable);
             }
             Atom::PolyValue(var_table) => {
                 var_table
                     .iter()
                     .map(|(k, v)| {
-                        self.inst_builder
-                            .build_store(*v, val.clone().expect_poly_value()?[k]);
+                        let value = val.clone().expect_poly_value()?[k];
+                        self.store_var(*v, value, BindingKind::Mutable);
                         Ok(())
                     })
                     .collect::<Result<(), Error>>()?;
@@ -393,59 +2236,139 @@ impl Builder {
         Ok(())
     }
 
+    pub fn get_var_ptr(&mut self, name: &str) -> Result<values::PointerValue, Error> {
+        let var = self
+            .env
+            .get(name)
+            .ok_or(TranslationError::UndeclaredVariable)?;
+
+        if var.kind() != BindingKind::Mutable {
+            return Err(TranslationError::ImmutableAssign.into());
+        }
+
+        var.ptr_value().clone().expect_value()
+    }
+
     pub fn get_var(&mut self, name: &str) -> Result<Option<Atom<values::BasicValueEnum>>, Error> {
-        self.env.get(name).map_or(Ok(None), |var| {
-            Ok(Some(match var.ptr_value() {
-                Atom::LLVMValue(var) => self.inst_builder.build_load(var.clone(), name).into(),
-                Atom::PolyValue(var_table) => var_table
-                    .iter()
-                    .map(|(k, v)| (k.clone(), self.inst_builder.build_load(v.clone(), name)))
-                    .collect::<HashMap<_, _>>()
-                    .into(),
-            }))
-        })
+        let var = match self.env.get(name) {
+            Some(var) => var.clone(),
+            None => return Ok(None),
+        };
+        Ok(Some(match var.ptr_value() {
+            Atom::LLVMValue(ptr) => self.load_var(*ptr, var.kind(), name).into(),
+            Atom::PolyValue(ptr_table) => ptr_table
+                .iter()
+                .map(|(k, v)| (k.clone(), self.load_var(*v, var.kind(), name)))
+                .collect::<HashMap<_, _>>()
+                .into(),
+        }))
+    }
+
+    /// Load a value from `ptr` of a place to which values are assigned.
+    pub fn load_place(&mut self, ptr: values::PointerValue) -> values::BasicValueEnum {
+        self.load_var(ptr, BindingKind::Mutable, "place")
+    }
+
+    /// Store `value` to `ptr` of a place to which values are assigned.
+    pub fn store_place(&mut self, ptr: values::PointerValue, value: values::BasicValueEnum) {
+        self.store_var(ptr, value, BindingKind::Mutable);
+    }
+
+    fn load_var(
+        &mut self,
+        ptr: values::PointerValue,
+        kind: BindingKind,
+        name: &str,
+    ) -> values::BasicValueEnum {
+        let value = self.inst_builder.build_load(ptr, name);
+        if kind == BindingKind::Mutable {
+            let load = self
+                .inst_builder
+                .get_insert_block()
+                .and_then(|block| block.get_last_instruction())
+                .unwrap();
+            self.var_accesses.push(load);
+        }
+        value
+    }
+
+    fn store_var(
+        &mut self,
+        ptr: values::PointerValue,
+        value: values::BasicValueEnum,
+        kind: BindingKind,
+    ) {
+        let store = self.inst_builder.build_store(ptr, value);
+        if kind == BindingKind::Mutable {
+            self.var_accesses.push(store);
+        }
     }
 
+    /// Make the loads and stores of mutable variables in the current function volatile if it
+    /// contains `try`, as the values assigned before `longjmp` may otherwise be left in
+    /// registers, which are restored to the ones at `setjmp`.
+    fn finish_var_accesses(&mut self) {
+        let accesses = mem::replace(&mut self.var_accesses, Vec::new());
+        if mem::replace(&mut self.has_try, false) {
+            for access in accesses {
+                access.set_volatile(true);
+            }
+        }
+    }
+
+    /// Convert `v` of type `from` to `to`, which are integers, floats, booleans or characters.
     pub fn cast_to(
         &mut self,
         v: values::BasicValueEnum,
-        to_type: types::BasicTypeEnum,
+        from: &Type,
+        to: &Type,
     ) -> Result<values::BasicValueEnum, Error> {
-        let from_type = self.type_of(v);
-        if from_type == to_type {
-            return Err(TranslationError::InvalidCast {
-                from: format!("{:?}", from_type),
-                to: format!("{:?}", to_type),
-            }
-            .into());
+        let to_type = self.llvm_type(to)?;
+        if from == to {
+            return Ok(v);
         }
 
-        let number_type: types::BasicTypeEnum = types::IntType::i64_type().into();
-        let bool_type: types::BasicTypeEnum = types::IntType::bool_type().into();
-
-        // TODO: more elegant way to match types
-        if from_type == number_type {
-            if to_type == bool_type {
-                let zero = self.number_constant(0)?;
-                return Ok(self
-                    .cmp(
-                        CondCode::NotEqual,
-                        v.into_int_value(),
-                        zero.into_int_value(),
-                    )
-                    .into());
+        Ok(match (v, to_type) {
+            (values::BasicValueEnum::FloatValue(v), types::BasicTypeEnum::IntType(t)) => {
+                if to.is_signed() {
+                    self.inst_builder.build_float_to_signed_int(v, t, "f2i")
+                } else {
+                    self.inst_builder.build_float_to_unsigned_int(v, t, "f2i")
+                }
+                .into()
             }
-        } else if from_type == bool_type && to_type == number_type {
-            return Ok(self
-                .inst_builder
-                .build_int_z_extend(v.into_int_value(), to_type.into_int_type(), "b2i")
-                .into());
-        }
-        Err(TranslationError::InvalidCast {
-            from: format!("{:?}", from_type),
-            to: format!("{:?}", to_type),
-        }
-        .into())
+            (values::BasicValueEnum::IntValue(v), types::BasicTypeEnum::FloatType(t)) => {
+                if from.is_signed() {
+                    self.inst_builder.build_signed_int_to_float(v, t, "i2f")
+                } else {
+                    self.inst_builder.build_unsigned_int_to_float(v, t, "i2f")
+                }
+                .into()
+            }
+            (values::BasicValueEnum::IntValue(v), types::BasicTypeEnum::IntType(t)) => {
+                let from_width = v.get_type().get_bit_width();
+                if *to == Type::Boolean {
+                    let zero = v.get_type().const_int(0, false);
+                    self.cmp(CondCode::NotEqual, v, zero)
+                } else if t.get_bit_width() < from_width {
+                    self.inst_builder.build_int_truncate(v, t, "trunc")
+                } else if t.get_bit_width() == from_width {
+                    v
+                } else if from.is_signed() {
+                    self.inst_builder.build_int_s_extend(v, t, "sext")
+                } else {
+                    self.inst_builder.build_int_z_extend(v, t, "zext")
+                }
+                .into()
+            }
+            _ => {
+                return Err(TranslationError::InvalidCast {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                }
+                .into())
+            }
+        })
     }
 
     pub fn enter_new_scope(&mut self) {
@@ -463,28 +2386,56 @@ impl Builder {
 
     pub fn array_alloc(
         &mut self,
-        _t: types::BasicTypeEnum,
-        _size: u32,
+        t: types::BasicTypeEnum,
+        size: u32,
     ) -> Result<values::PointerValue, Error> {
-        unimplemented!()
+        // TODO: Fix memory leak
+        let ptr = self
+            .inst_builder
+            .build_malloc(t.array_type(size), "array_ptr");
+        Ok(self
+            .inst_builder
+            .build_pointer_cast(ptr, t.ptr_type(AddressSpace::Generic), "data_ptr"))
     }
 
     pub fn store(
         &mut self,
-        _v: values::BasicValueEnum,
-        _addr: values::PointerValue,
-        _offset: u32,
+        v: values::BasicValueEnum,
+        addr: values::PointerValue,
+        offset: values::IntValue,
     ) -> Result<(), Error> {
-        unimplemented!()
+        let ptr = unsafe { self.inst_builder.build_gep(addr, &[offset], "store_ptr") };
+        self.inst_builder.build_store(ptr, v);
+        Ok(())
     }
 
     pub fn load(
         &mut self,
-        _t: &Type,
-        _addr: values::PointerValue,
-        _offset: u32,
+        addr: values::PointerValue,
+        offset: values::IntValue,
     ) -> Result<values::BasicValueEnum, Error> {
-        unimplemented!()
+        let ptr = unsafe { self.inst_builder.build_gep(addr, &[offset], "load_ptr") };
+        Ok(self.inst_builder.build_load(ptr, "load"))
+    }
+
+    /// Allocate a stack slot in the entry block of the current function, so that allocations
+    /// emitted inside of a loop are not repeated on every iteration.
+    pub fn entry_alloca<T: types::BasicType>(
+        &mut self,
+        ty: T,
+        name: &str,
+    ) -> Result<values::PointerValue, Error> {
+        let entry = self
+            .inst_builder
+            .get_insert_block()
+            .and_then(|b| b.get_parent())
+            .and_then(|f| f.get_first_basic_block())
+            .ok_or(TranslationError::InvalidContextBranch)?;
+        match entry.get_first_instruction() {
+            Some(instruction) => self.alloca_builder.position_before(&instruction),
+            None => self.alloca_builder.position_at_end(&entry),
+        }
+        Ok(self.alloca_builder.build_alloca(ty, name))
     }
 
     pub fn create_block(&mut self) -> Result<Block, Error> {
@@ -519,6 +2470,57 @@ impl Builder {
         self.inst_builder.build_unconditional_branch(block.cl_ebb());
     }
 
+    pub fn unreachable(&mut self) {
+        self.inst_builder.build_unreachable();
+    }
+
+    /// Start translating the body of a loop. `break` stores its value to `result` and jumps to
+    /// `exit`, and `continue` jumps to `header`.
+    pub fn enter_loop(&mut self, header: Block, exit: Block, result: Option<values::PointerValue>) {
+        self.loops.push(Loop {
+            header,
+            exit,
+            result,
+        });
+    }
+
+    pub fn exit_loop(&mut self) -> Result<(Block, Block), Error> {
+        self.loops
+            .pop()
+            .map(|l| (l.header, l.exit))
+            .ok_or_else(|| TranslationError::InvalidContextBranch.into())
+    }
+
+    pub fn break_loop(&mut self, value: Option<values::BasicValueEnum>) -> Result<(), Error> {
+        let current = self
+            .loops
+            .last()
+            .ok_or(TranslationError::InvalidContextBranch)?;
+        if let (Some(value), Some(ptr)) = (value, current.result) {
+            self.inst_builder.build_store(ptr, value);
+        }
+        self.inst_builder
+            .build_unconditional_branch(current.exit.cl_ebb());
+        self.start_dead_block()
+    }
+
+    pub fn continue_loop(&mut self) -> Result<(), Error> {
+        let current = self
+            .loops
+            .last()
+            .ok_or(TranslationError::InvalidContextBranch)?;
+        self.inst_builder
+            .build_unconditional_branch(current.header.cl_ebb());
+        self.start_dead_block()
+    }
+
+    /// Continue translation in a block which is never reached
+    fn start_dead_block(&mut self) -> Result<(), Error> {
+        let block = self.create_block()?;
+        self.switch_to_block(&block);
+        Ok(())
+    }
+
     pub fn switch_to_block(&mut self, block: &Block) {
         self.inst_builder.position_at_end(block.cl_ebb());
     }
@@ -530,16 +2532,59 @@ impl Builder {
             .map(|ebb| Block { ebb })
     }
 
-    pub fn ret_int(&mut self, v: values::BasicValueEnum) -> Result<(), Error> {
+    pub fn ret_int(&mut self, v: values::BasicValueEnum, ty: &Type) -> Result<(), Error> {
         // TODO: Generic return
         let number_type: types::BasicTypeEnum = types::IntType::i64_type().into();
-        let return_value: values::BasicValueEnum = if self.type_of(v) != number_type {
-            self.cast_to(v, number_type)?
-        } else {
-            v
+        let return_value: values::BasicValueEnum = match v {
+            // Aggregates such as strings are returned by their address
+            values::BasicValueEnum::StructValue(_) => {
+                let ptr = self.inst_builder.build_malloc(self.type_of(v), "result");
+                self.inst_builder.build_store(ptr, v);
+                self.inst_builder
+                    .build_ptr_to_int(ptr, number_type.into_int_type(), "result_addr")
+                    .into()
+            }
+            // Floats are returned by their bit patterns
+            values::BasicValueEnum::FloatValue(_) => {
+                self.inst_builder
+                    .build_bitcast(v, number_type.into_int_type(), "result_bits")
+            }
+            _ if self.type_of(v) != number_type => self.cast_to(v, ty, &Type::Number)?,
+            _ => v,
         };
         // Emit the return instruction.
         self.inst_builder.build_return(Some(&return_value));
         Ok(())
     }
 }
+
+fn int_type(t: IntType) -> types::IntType {
+    match t.bits() {
+        8 => types::IntType::i8_type(),
+        16 => types::IntType::i16_type(),
+        32 => types::IntType::i32_type(),
+        _ => types::IntType::i64_type(),
+    }
+}
+
+/// `errno` for an invalid argument, which is the same in the C libraries below.
+const EINVAL: u64 = 22;
+
+/// Name of a symbol of libc which differs between the C libraries, such as the one for `errno`
+/// in glibc. Programs are linked with the libc of the host by default.
+fn libc_symbol(name: &'static str) -> &'static str {
+    if cfg!(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd"
+    )) {
+        match name {
+            "__errno_location" => "__error",
+            "stdin" => "__stdinp",
+            "stderr" => "__stderrp",
+            _ => name,
+        }
+    } else {
+        name
+    }
+}
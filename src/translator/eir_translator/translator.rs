@@ -1,29 +1,205 @@
+use super::builder::{Block, CondCode};
 use super::{Atom, Builder};
 use crate::error::TranslationError;
+use crate::expression::{Operator, RangeKind};
 use crate::ir;
+use crate::transform::type_infer::traits::Types;
 
 use failure::Error;
-use inkwell::values::BasicValueEnum;
+use inkwell::values::{BasicValueEnum, PointerValue};
 use std::collections::HashMap;
 
+/// The captures of the instance are used, as they may differ from the polymorphic one after the
+/// methods of classes are resolved.
 fn translate_monotype_function(
     builder: &mut Builder,
     param: String,
     ty: &ir::Type,
     body: ir::Node,
-    capture_list: &HashMap<ir::Identifier, ir::Type>,
 ) -> Result<BasicValueEnum, Error> {
     assert_eq!(Some(ty), body.type_());
     match body.value() {
-        ir::Value::Literal(ir::Literal::Function(_, box body, _)) => {
-            builder.function_constant(&ty, param, capture_list, |builder| {
+        ir::Value::Literal(ir::Literal::Function(_, box body, capture_list)) => builder
+            .function_constant(&ty, param, &capture_list, |builder| {
                 translate_eir(builder, body.clone())?.expect_value()
-            })
-        }
+            }),
         _ => unreachable!(),
     }
 }
 
+/// Monomorphic instances of the function literal `func`, paired with the bodies of them.
+fn function_instances(func: &ir::Node) -> Result<Vec<(ir::Type, ir::Node)>, Error> {
+    let ty = func.type_().ok_or(TranslationError::NotTyped)?;
+    let instances: Vec<&ir::Node> = if !func.ty_table().is_empty() {
+        func.ty_table().values().collect()
+    } else if ty.ftv().is_empty() {
+        vec![func]
+    } else {
+        // Polymorphic function which is never instantiated
+        Vec::new()
+    };
+
+    instances
+        .into_iter()
+        .map(|instance| match instance.value() {
+            ir::Value::Literal(ir::Literal::Function(_, box body, _)) => {
+                let ty = instance.type_().ok_or(TranslationError::NotTyped)?;
+                Ok((ty.clone(), body.clone()))
+            }
+            _ => Err(TranslationError::InvalidType.into()),
+        })
+        .collect()
+}
+
+/// A placeholder for the value of `break` and `continue`, which never produce a value in place
+fn unreachable_value(builder: &Builder, ty: &ir::Type) -> Result<BasicValueEnum, Error> {
+    if ty.ftv().is_empty() {
+        Ok(builder.undef_value(builder.llvm_type(ty)?))
+    } else {
+        builder.empty_constant()
+    }
+}
+
+fn translate_place(builder: &mut Builder, eir: ir::Node) -> Result<PointerValue, Error> {
+    match eir.value {
+        ir::Value::Variable(name) => builder.get_var_ptr(&name),
+        // Strings are immutable
+        ir::Value::BinOp(Operator::Index, box base, _)
+            if base.type_() == Some(&ir::Type::String) =>
+        {
+            Err(TranslationError::InvalidAssignTarget.into())
+        }
+        ir::Value::BinOp(Operator::Index, box base, box index) => {
+            let base_ptr = translate_place(builder, base)?;
+            let array = builder.load_place(base_ptr);
+            let index = translate_eir(builder, index)?.expect_value()?;
+            builder.index_ptr(array, index)
+        }
+        ir::Value::Field(box base, field) => {
+            let base_ty = base.type_().ok_or(TranslationError::NotTyped)?.clone();
+            let (index, kind) = builder.struct_field(&base_ty, &field)?;
+            if kind != ir::BindingKind::Mutable {
+                return Err(TranslationError::ImmutableFieldAssign { field }.into());
+            }
+            let base_ptr = translate_place(builder, base)?;
+            builder.field_ptr(base_ptr, index)
+        }
+        _ => Err(TranslationError::InvalidAssignTarget.into()),
+    }
+}
+
+/// Continue in a new block if `lhs` is equal to `rhs`, otherwise jump to `fail`.
+fn branch_eq(
+    builder: &mut Builder,
+    lhs: BasicValueEnum,
+    rhs: BasicValueEnum,
+    fail: &Block,
+) -> Result<(), Error> {
+    let cond = builder.cmp(CondCode::Equal, lhs.into_int_value(), rhs.into_int_value());
+    let next_block = builder.create_block()?;
+    builder.brz(cond.into(), &next_block, fail)?;
+    builder.switch_to_block(&next_block);
+    Ok(())
+}
+
+/// Emit the tests of `pattern` against `value` as a chain of branches which jump to `fail` as
+/// soon as one of them fails, and bind the variables in the pattern.
+fn translate_pattern(
+    builder: &mut Builder,
+    pattern: &ir::Pattern,
+    value: BasicValueEnum,
+    ty: &ir::Type,
+    fail: &Block,
+) -> Result<(), Error> {
+    match pattern {
+        ir::Pattern::Wildcard => {}
+        ir::Pattern::Variable(name) => {
+            builder.bind_var(name, &Atom::LLVMValue(value), ir::BindingKind::Immutable)?
+        }
+        ir::Pattern::Number(number) => {
+            let expected = match ty {
+                ir::Type::Int(t) => builder.int_constant(*t, *number),
+                _ => builder.number_constant(*number)?,
+            };
+            branch_eq(builder, value, expected, fail)?;
+        }
+        ir::Pattern::Boolean(tf) => {
+            let expected = builder.boolean_constant(*tf)?;
+            branch_eq(builder, value, expected, fail)?;
+        }
+        ir::Pattern::Tuple(elems) => {
+            let elem_types = match ty {
+                ir::Type::Tuple(elem_types) => elem_types,
+                _ => return Err(TranslationError::InvalidType.into()),
+            };
+            for (i, (elem, elem_ty)) in elems.iter().zip(elem_types).enumerate() {
+                let elem_value = builder.extract_element(value, i as u32)?;
+                translate_pattern(builder, elem, elem_value, elem_ty, fail)?;
+            }
+        }
+        ir::Pattern::Constructor(name, args) => {
+            let (tag, field_types) = builder.constructor_fields(ty, name)?;
+            let tag_value = builder.extract_element(value, 0)?;
+            let expected = builder.number_constant(tag as i64)?;
+            branch_eq(builder, tag_value, expected, fail)?;
+
+            if !args.is_empty() {
+                let fields = builder.data_fields(value, &field_types)?;
+                for ((arg, field), field_ty) in args.iter().zip(fields).zip(&field_types) {
+                    translate_pattern(builder, arg, field, field_ty, fail)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `&&` and `||`, whose right operand is evaluated only if the left one is not `decisive`.
+fn translate_short_circuit(
+    builder: &mut Builder,
+    lhs: ir::Node,
+    rhs: ir::Node,
+    decisive: bool,
+) -> Result<BasicValueEnum, Error> {
+    let lhs = translate_eir(builder, lhs)?.expect_value()?;
+    let bool_type = builder.type_of(lhs);
+    let result_ptr = builder.entry_alloca(bool_type, "logical_result")?;
+    builder.inst_builder().build_store(result_ptr, lhs);
+
+    let rhs_block = builder.create_block()?;
+    let merge_block = builder.create_block()?;
+    if decisive {
+        builder.brz(lhs, &merge_block, &rhs_block)?;
+    } else {
+        builder.brz(lhs, &rhs_block, &merge_block)?;
+    }
+
+    builder.switch_to_block(&rhs_block);
+    let rhs = translate_eir(builder, rhs)?.expect_value()?;
+    builder.inst_builder().build_store(result_ptr, rhs);
+    builder.jump(&merge_block);
+
+    builder.switch_to_block(&merge_block);
+    Ok(builder
+        .inst_builder()
+        .build_load(result_ptr, "logical_result"))
+}
+
+fn translate_fields(
+    builder: &mut Builder,
+    ty: &ir::Type,
+    fields: Vec<(ir::Identifier, ir::Node)>,
+) -> Result<Vec<(u32, BasicValueEnum)>, Error> {
+    fields
+        .into_iter()
+        .map(|(field, value)| {
+            let (index, _) = builder.struct_field(ty, &field)?;
+            let value = translate_eir(builder, value)?.expect_value()?;
+            Ok((index, value))
+        })
+        .collect()
+}
+
 pub fn translate_eir(builder: &mut Builder, eir: ir::Node) -> Result<Atom<BasicValueEnum>, Error> {
     let ir::Node {
         value,
@@ -34,12 +210,22 @@ pub fn translate_eir(builder: &mut Builder, eir: ir::Node) -> Result<Atom<BasicV
 
     Ok(match value {
         ir::Value::Literal(c) => match c {
-            ir::Literal::Number(number) => builder.number_constant(number)?.into(),
+            ir::Literal::Number(number) => match ty {
+                ir::Type::Int(t) => builder.int_constant(t, number).into(),
+                _ => builder.number_constant(number)?.into(),
+            },
+            ir::Literal::Int(bits, t) => builder.int_constant(t, bits).into(),
+            ir::Literal::Float(number) => builder.float_constant(number)?.into(),
             ir::Literal::Boolean(tf) => builder.boolean_constant(tf)?.into(),
+            ir::Literal::String(s) => builder.string_constant(&s)?.into(),
+            ir::Literal::Char(c) => builder.char_constant(c)?.into(),
             ir::Literal::Empty => builder.empty_constant()?.into(),
             ir::Literal::Function(param, box body, capture_list) => {
                 // TODO: Add more sufficient implementation to check whether PolyValue is needed or not
-                if instantiation_table.is_empty() {
+                if instantiation_table.is_empty() && !ty.ftv().is_empty() {
+                    // Polymorphic function which is never instantiated
+                    Atom::PolyValue(HashMap::new())
+                } else if instantiation_table.is_empty() {
                     builder
                         .function_constant(&ty, param, &capture_list, |builder| {
                             translate_eir(builder, body.clone())?.expect_value()
@@ -47,20 +233,13 @@ pub fn translate_eir(builder: &mut Builder, eir: ir::Node) -> Result<Atom<BasicV
                         .into()
                 } else if instantiation_table.len() == 1 {
                     let (ty, body) = instantiation_table.iter().next().unwrap();
-                    translate_monotype_function(builder, param, &ty, body.clone(), &capture_list)?
-                        .into()
+                    translate_monotype_function(builder, param, &ty, body.clone())?.into()
                 } else {
                     instantiation_table
                         .into_iter()
                         .map(|(ty, body)| {
-                            translate_monotype_function(
-                                builder,
-                                param.clone(),
-                                &ty,
-                                body.clone(),
-                                &capture_list,
-                            )
-                            .map(|v| (ty.clone(), v))
+                            translate_monotype_function(builder, param.clone(), &ty, body.clone())
+                                .map(|v| (ty.clone(), v))
                         })
                         .collect::<Result<HashMap<_, _>, _>>()?
                         .into()
@@ -76,12 +255,35 @@ pub fn translate_eir(builder: &mut Builder, eir: ir::Node) -> Result<Atom<BasicV
                 Atom::PolyValue(func_table) => builder.call(func_table[func_ty], arg)?.into(),
             }
         }
+        ir::Value::BinOp(Operator::And, box lhs, box rhs) => {
+            translate_short_circuit(builder, lhs, rhs, false)?.into()
+        }
+        ir::Value::BinOp(Operator::Or, box lhs, box rhs) => {
+            translate_short_circuit(builder, lhs, rhs, true)?.into()
+        }
         ir::Value::BinOp(op, lhs, rhs) => {
+            let lhs_ty = lhs.type_().ok_or(TranslationError::NotTyped)?.clone();
             let lhs = translate_eir(builder, *lhs)?.expect_value()?;
             let rhs = translate_eir(builder, *rhs)?.expect_value()?;
-            builder.apply_op(op, lhs, rhs)?.into()
+            if lhs_ty == ir::Type::String {
+                builder.string_op(op, lhs, rhs)?.into()
+            } else {
+                builder.apply_op(op, lhs, rhs, lhs_ty.is_signed())?.into()
+            }
+        }
+        ir::Value::UnaryOp(op, box value) => {
+            let value = translate_eir(builder, value)?.expect_value()?;
+            builder.apply_unary_op(op, value).into()
+        }
+
+        ir::Value::Cast(box value, _) => {
+            let from = value.type_().ok_or(TranslationError::NotTyped)?.clone();
+            let value = translate_eir(builder, value)?.expect_value()?;
+            builder.cast_to(value, &from, &ty)?.into()
         }
 
+        ir::Value::Ascribe(box value, _) => translate_eir(builder, value)?,
+
         ir::Value::Follow(lhs, rhs) => {
             translate_eir(builder, *lhs)?;
             translate_eir(builder, *rhs)?
@@ -98,13 +300,81 @@ pub fn translate_eir(builder: &mut Builder, eir: ir::Node) -> Result<Atom<BasicV
             content
         }
 
+        ir::Value::LetTuple(kind, names, box value, box body) => {
+            let tuple = translate_eir(builder, value)?.expect_value()?;
+            let elems = (0..names.len())
+                .map(|i| builder.extract_element(tuple, i as u32))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            builder.enter_new_scope();
+            for (name, elem) in names.iter().zip(elems) {
+                builder.bind_var(name, &Atom::LLVMValue(elem), kind)?;
+            }
+            let content = translate_eir(builder, body)?;
+            builder.exit_scope()?;
+
+            content
+        }
+
+        ir::Value::LetRec(bindings, box body) => {
+            let names: Vec<_> = bindings.iter().map(|(name, _)| name.clone()).collect();
+            let mut capture_list = HashMap::new();
+            let mut functions = Vec::new();
+            let mut bodies = Vec::new();
+            for (name, value) in bindings {
+                let param = match value.value() {
+                    ir::Value::Literal(ir::Literal::Function(param, ..)) => param.clone(),
+                    _ => return Err(TranslationError::RecursiveValue { name }.into()),
+                };
+                // Functions in the group refer to each other without capturing
+                for instance in std::iter::once(&value).chain(value.ty_table().values()) {
+                    if let ir::Value::Literal(ir::Literal::Function(_, _, captures)) =
+                        instance.value()
+                    {
+                        capture_list.extend(
+                            captures
+                                .iter()
+                                .filter(|(ident, _)| !names.contains(ident))
+                                .map(|(ident, ty)| (ident.clone(), ty.clone())),
+                        );
+                    }
+                }
+
+                let (types, instance_bodies): (Vec<_>, Vec<_>) =
+                    function_instances(&value)?.into_iter().unzip();
+                functions.push((name, param, types));
+                bodies.push(instance_bodies);
+            }
+
+            let closures = builder.recursive_function_constants(
+                &functions,
+                &capture_list,
+                |builder, i, j| translate_eir(builder, bodies[i][j].clone())?.expect_value(),
+            )?;
+
+            builder.enter_new_scope();
+            for ((name, _, _), closure) in functions.iter().zip(closures) {
+                builder.bind_var(name, &closure, ir::BindingKind::Immutable)?;
+            }
+            let content = translate_eir(builder, body)?;
+            builder.exit_scope()?;
+
+            content
+        }
+
         ir::Value::Assign(lhs, rhs) => {
             let new_value = translate_eir(builder, *rhs)?;
-            let name = match lhs.value() {
-                ir::Value::Variable(name) => name,
-                _ => panic!("Non-variable identifier"),
-            };
-            builder.assign_var(&name, &new_value)?;
+            match *lhs {
+                ir::Node {
+                    value: ir::Value::Variable(name),
+                    ..
+                } => builder.assign_var(&name, &new_value)?,
+                place => {
+                    let ptr = translate_place(builder, place)?;
+                    let value = new_value.clone().expect_value()?;
+                    builder.store_place(ptr, value);
+                }
+            }
             new_value
         }
 
@@ -123,12 +393,14 @@ pub fn translate_eir(builder: &mut Builder, eir: ir::Node) -> Result<Atom<BasicV
 
             builder.switch_to_block(&then_block);
             let then_return = translate_eir(builder, *then_expr)?;
+            // 'then' expression may end in a block other than `then_block`
+            let then_end_block = builder.current_block()?;
 
             builder.switch_to_block(&initial_block);
             let var_name = builder.declare_mut_var("__cond", &then_return, true)?;
             builder.brz(condition_value, &then_block, &else_block)?;
 
-            builder.switch_to_block(&then_block);
+            builder.switch_to_block(&then_end_block);
             builder.assign_var(&var_name, &then_return)?;
             builder.jump(&merge_block);
 
@@ -143,5 +415,260 @@ pub fn translate_eir(builder: &mut Builder, eir: ir::Node) -> Result<Atom<BasicV
             builder.switch_to_block(&merge_block);
             builder.get_var(&var_name)?.unwrap()
         }
+
+        ir::Value::Match(box value, arms) => {
+            let value_ty = value.type_().ok_or(TranslationError::NotTyped)?.clone();
+            let value = translate_eir(builder, value)?.expect_value()?;
+
+            // The type is left undetermined if every arm diverges
+            let result_ptr = if ty.ftv().is_empty() {
+                let result_type = builder.llvm_type(&ty)?;
+                Some(builder.entry_alloca(result_type, "match_result")?)
+            } else {
+                None
+            };
+            let merge_block = builder.create_block()?;
+
+            // Each arm is tried in order; a failed test jumps to the next arm
+            for arm in arms {
+                let next_block = builder.create_block()?;
+
+                builder.enter_new_scope();
+                translate_pattern(builder, &arm.pattern, value, &value_ty, &next_block)?;
+                if let Some(guard) = arm.guard {
+                    let guard_value = translate_eir(builder, guard)?.expect_value()?;
+                    let body_block = builder.create_block()?;
+                    builder.brz(guard_value, &body_block, &next_block)?;
+                    builder.switch_to_block(&body_block);
+                }
+                let body_value = translate_eir(builder, arm.body)?.expect_value()?;
+                if let Some(ptr) = result_ptr {
+                    builder.inst_builder().build_store(ptr, body_value);
+                }
+                builder.jump(&merge_block);
+                builder.exit_scope()?;
+
+                builder.switch_to_block(&next_block);
+            }
+
+            // Exhaustiveness is checked in type inference
+            builder.unreachable();
+
+            builder.switch_to_block(&merge_block);
+            match result_ptr {
+                Some(ptr) => builder
+                    .inst_builder()
+                    .build_load(ptr, "match_result")
+                    .into(),
+                None => unreachable_value(builder, &ty)?.into(),
+            }
+        }
+
+        ir::Value::While(box cond, box body) => {
+            let header_block = builder.create_block()?;
+            let body_block = builder.create_block()?;
+            let exit_block = builder.create_block()?;
+
+            let result_ptr = if ty != ir::Type::Empty {
+                let result_type = builder.llvm_type(&ty)?;
+                Some(builder.entry_alloca(result_type, "loop_result")?)
+            } else {
+                None
+            };
+
+            builder.jump(&header_block);
+            builder.switch_to_block(&header_block);
+            let condition_value = translate_eir(builder, cond)?.expect_value()?;
+            builder.brz(condition_value, &body_block, &exit_block)?;
+
+            builder.switch_to_block(&body_block);
+            builder.enter_loop(header_block, exit_block, result_ptr);
+            builder.enter_new_scope();
+            translate_eir(builder, body)?;
+            builder.exit_scope()?;
+            let (header_block, exit_block) = builder.exit_loop()?;
+            builder.jump(&header_block);
+
+            builder.switch_to_block(&exit_block);
+            match result_ptr {
+                Some(ptr) => builder.inst_builder().build_load(ptr, "loop_result").into(),
+                None => builder.empty_constant()?.into(),
+            }
+        }
+
+        ir::Value::Break(value) => {
+            let value = match value {
+                Some(box value) => Some(translate_eir(builder, value)?.expect_value()?),
+                None => None,
+            };
+            builder.break_loop(value)?;
+            unreachable_value(builder, &ty)?.into()
+        }
+
+        ir::Value::Continue => {
+            builder.continue_loop()?;
+            unreachable_value(builder, &ty)?.into()
+        }
+
+        ir::Value::Throw(box value) => {
+            let value = translate_eir(builder, value)?.expect_value()?;
+            builder.throw(value)?;
+            unreachable_value(builder, &ty)?.into()
+        }
+
+        ir::Value::Try(box body, name, box handler) => {
+            // The type is left undetermined if both the body and the handler always throw
+            let result_ptr = if ty.ftv().is_empty() {
+                let result_type = builder.llvm_type(&ty)?;
+                Some(builder.entry_alloca(result_type, "try_result")?)
+            } else {
+                None
+            };
+            let catch_block = builder.create_block()?;
+            let merge_block = builder.create_block()?;
+
+            let previous = builder.enter_try(&catch_block)?;
+            let value = translate_eir(builder, body)?.expect_value()?;
+            builder.exit_try(previous);
+            if let Some(ptr) = result_ptr {
+                builder.inst_builder().build_store(ptr, value);
+            }
+            builder.jump(&merge_block);
+
+            builder.switch_to_block(&catch_block);
+            builder.exit_try(previous);
+            let exception = builder.caught_value()?;
+            builder.enter_new_scope();
+            builder.bind_var(
+                &name,
+                &Atom::LLVMValue(exception),
+                ir::BindingKind::Immutable,
+            )?;
+            let value = translate_eir(builder, handler)?.expect_value()?;
+            builder.exit_scope()?;
+            if let Some(ptr) = result_ptr {
+                builder.inst_builder().build_store(ptr, value);
+            }
+            builder.jump(&merge_block);
+
+            builder.switch_to_block(&merge_block);
+            match result_ptr {
+                Some(ptr) => builder.inst_builder().build_load(ptr, "try_result").into(),
+                None => unreachable_value(builder, &ty)?.into(),
+            }
+        }
+
+        ir::Value::Array(elems) => {
+            let elem_type = match &ty {
+                ir::Type::Array(box elem_type) => builder.llvm_type(elem_type)?,
+                _ => return Err(TranslationError::InvalidType.into()),
+            };
+            let elems = elems
+                .into_iter()
+                .map(|elem| translate_eir(builder, elem)?.expect_value())
+                .collect::<Result<Vec<_>, _>>()?;
+            builder.array_constant(elem_type, &elems)?.into()
+        }
+
+        ir::Value::Tuple(elems) => {
+            let elems = elems
+                .into_iter()
+                .map(|elem| translate_eir(builder, elem)?.expect_value())
+                .collect::<Result<Vec<_>, _>>()?;
+            builder.tuple_constant(&elems)?.into()
+        }
+
+        ir::Value::Range(box start, box end, kind) => {
+            let start = translate_eir(builder, start)?.expect_value()?;
+            let end = translate_eir(builder, end)?.expect_value()?;
+            let inclusive = builder.boolean_constant(kind == RangeKind::Inclusive)?;
+            builder.tuple_constant(&[start, end, inclusive])?.into()
+        }
+
+        ir::Value::TupleIndex(box tuple, index) => {
+            let tuple = translate_eir(builder, tuple)?.expect_value()?;
+            builder.extract_element(tuple, index as u32)?.into()
+        }
+
+        ir::Value::DefineType(data_type, box body) => {
+            builder.define_type(data_type)?;
+            translate_eir(builder, body)?
+        }
+
+        ir::Value::CallBuiltin(builtin, args) => {
+            let arg_types = args
+                .iter()
+                .map(|arg| arg.type_().cloned().ok_or(TranslationError::NotTyped))
+                .collect::<Result<Vec<_>, _>>()?;
+            let args = args
+                .into_iter()
+                .map(|arg| translate_eir(builder, arg)?.expect_value())
+                .collect::<Result<Vec<_>, _>>()?;
+            builder
+                .call_builtin(builtin, &arg_types, &args, &ty)?
+                .into()
+        }
+
+        ir::Value::CallExtern(name, _, args) => {
+            let arg_types = args
+                .iter()
+                .map(|arg| arg.type_().cloned().ok_or(TranslationError::NotTyped))
+                .collect::<Result<Vec<_>, _>>()?;
+            let args = args
+                .into_iter()
+                .map(|arg| translate_eir(builder, arg)?.expect_value())
+                .collect::<Result<Vec<_>, _>>()?;
+            builder.call_extern(&name, &arg_types, &args, &ty)?.into()
+        }
+
+        ir::Value::DefineStruct(struct_type, box body) => {
+            builder.define_struct(struct_type);
+            translate_eir(builder, body)?
+        }
+
+        ir::Value::DefineAlias(alias, box body) => {
+            builder.define_alias(alias);
+            translate_eir(builder, body)?
+        }
+
+        ir::Value::DefineClass(_, box body) => translate_eir(builder, body)?,
+
+        ir::Value::DefineException(ty, box body) => {
+            builder.define_exception(&ty)?;
+            translate_eir(builder, body)?
+        }
+
+        // Instances are turned into bindings of their methods in type inference
+        ir::Value::DefineInstance(..) => unreachable!(),
+
+        // A method is resolved once its type is known
+        ir::Value::Method(..) => return Err(TranslationError::UnresolvedType.into()),
+
+        ir::Value::Struct(_, fields) => {
+            let fields = translate_fields(builder, &ty, fields)?;
+            builder.struct_constant(&ty, &fields)?.into()
+        }
+
+        ir::Value::Field(box value, field) => {
+            let value_ty = value.type_().ok_or(TranslationError::NotTyped)?.clone();
+            let (index, _) = builder.struct_field(&value_ty, &field)?;
+            let value = translate_eir(builder, value)?.expect_value()?;
+            builder.extract_element(value, index)?.into()
+        }
+
+        ir::Value::StructUpdate(box value, fields) => {
+            let value = translate_eir(builder, value)?.expect_value()?;
+            let fields = translate_fields(builder, &ty, fields)?;
+            builder.update_struct(value, &fields)?.into()
+        }
+
+        ir::Value::Construct(name, args) => {
+            let tag = builder.constructor_tag(&name)?;
+            let args = args
+                .into_iter()
+                .map(|arg| translate_eir(builder, arg)?.expect_value())
+                .collect::<Result<Vec<_>, _>>()?;
+            builder.data_constant(&ty, tag, &args)?.into()
+        }
     })
 }